@@ -0,0 +1,536 @@
+/*
+⟨program⟩ ⩴  ⟨declaration⟩*
+
+⟨declaration⟩ ⩴  'definition' ⟨variable⟩ ':=' ⟨term⟩
+                | 'definition' ⟨variable⟩ ':' ⟨term⟩ ':=' ⟨term⟩
+                | 'theorem' ⟨variable⟩ ':' ⟨term⟩ ':=' ⟨term⟩
+
+⟨term⟩ ⩴  ⟨variable⟩
+         | ⟨sort⟩
+         | ⟨product⟩
+         | ⟨arrow⟩
+         | ⟨abstraction⟩
+         | ⟨application⟩
+         | '(' ⟨term⟩ ')'
+
+⟨variable⟩ ⩴  ⟨start⟩ ⟨continue⟩*    (other than a keyword)
+
+⟨start⟩ ⩴  'a' | 'b' | ... | 'z' | 'A' | 'B' | ... | 'Z'
+
+⟨continue⟩ ⩴  ⟨start⟩ | ⟨digit⟩ | '_' | '\''
+
+⟨sort⟩ ⩴  'Prop' | 'Type'
+
+⟨product⟩ ⩴  'Π' ⟨binders⟩ '.' ⟨term⟩
+            | 'forall' ⟨binders⟩ '.' ⟨term⟩
+
+⟨arrow⟩ ⩴  ⟨term⟩ '->' ⟨term⟩    (right associative)
+
+⟨abstraction⟩ ⩴  'λ' ⟨binders⟩ '.' ⟨term⟩
+                | '\' ⟨binders⟩ '.' ⟨term⟩
+
+⟨binders⟩ ⩴  ⟨binder⟩+ ':' ⟨term⟩
+            | ('(' ⟨binder⟩+ ':' ⟨term⟩ ')')+
+
+⟨binder⟩ ⩴  ⟨variable⟩ | '_'
+
+⟨application⟩ ⩴  ⟨term⟩ ⟨term⟩
+
+⟨comment⟩ ⩴  '--' ...    (to the end of the line)
+*/
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+type Span = Range<usize>;
+
+/// The binder of an arrow `A -> B`, which is the product `Π (_ : A). B`.
+/// No variable can refer to it.
+pub const ANONYMOUS: &str = "_";
+
+/// A named definition or theorem of a program, which the declarations after
+/// it can refer to.
+#[derive(Debug, Clone)]
+pub enum Declaration {
+    Definition {
+        name: Variable,
+        /// The type stated for the value, inferred when omitted.
+        typ: Option<Term>,
+        value: Term,
+        span: Span,
+    },
+    Theorem {
+        name: Variable,
+        statement: Term,
+        proof: Term,
+        span: Span,
+    },
+}
+
+impl Declaration {
+    pub fn name(&self) -> &Variable {
+        match self {
+            Declaration::Definition { name, .. } | Declaration::Theorem { name, .. } => name,
+        }
+    }
+    pub fn get_span(&self) -> Span {
+        match self {
+            Declaration::Definition { span, .. } | Declaration::Theorem { span, .. } => {
+                span.clone()
+            }
+        }
+    }
+    /// The terms written in the declaration, in order.
+    fn terms(&self) -> Vec<&Term> {
+        match self {
+            Declaration::Definition { typ, value, .. } => typ.iter().chain([value]).collect(),
+            Declaration::Theorem {
+                statement, proof, ..
+            } => vec![statement, proof],
+        }
+    }
+}
+
+/// The span of the binder or declaration of the variable at `offset` in
+/// `program`, or of the binder or declared name itself when `offset` falls on
+/// one.
+pub fn definition(program: &[Declaration], offset: usize) -> Option<Span> {
+    for (i, declaration) in program.iter().enumerate() {
+        if declaration.name().span.contains(&offset) {
+            return Some(declaration.name().span.clone());
+        }
+        for term in declaration.terms() {
+            if let Some(span) = term.resolve(offset, &mut Vec::new()) {
+                return Some(span);
+            }
+            // A variable no binder of its own declaration binds refers to a
+            // previous declaration.
+            if let Some(variable) = term.variable_at(offset) {
+                return program[..i]
+                    .iter()
+                    .rev()
+                    .find(|previous| previous.name().name == variable.name)
+                    .map(|previous| previous.name().span.clone());
+            }
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sort {
+    /// The sort of propositions, and of the types of data as they are
+    /// encoded in the calculus.
+    Prop,
+    /// The sort of `Prop` and of the types of predicates, such as
+    /// `A -> Prop`. It has no type itself.
+    Type,
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sort::Prop => write!(f, "Prop"),
+            Sort::Type => write!(f, "Type"),
+        }
+    }
+}
+
+/// A term, types being terms themselves. Terms equal each other up to the
+/// renaming of their bound variables, so that `λ (x : A). x` and
+/// `λ (y : A). y` are the same term.
+#[derive(Debug, Clone)]
+pub enum Term {
+    Variable(Variable),
+    Sort {
+        sort: Sort,
+        span: Span,
+    },
+    /// The type `Π (x : A). B` of the functions taking an `x` of type `A`
+    /// to a `B` which may depend on `x`.
+    Pi {
+        variable: Variable,
+        domain: Box<Term>,
+        codomain: Box<Term>,
+        span: Span,
+    },
+    Lambda {
+        variable: Variable,
+        domain: Box<Term>,
+        body: Box<Term>,
+        span: Span,
+    },
+    Application {
+        callee: Box<Term>,
+        arg: Box<Term>,
+        span: Span,
+    },
+}
+
+impl Term {
+    pub fn get_span(&self) -> Span {
+        match self {
+            Term::Variable(Variable { span, .. }) => span.clone(),
+            Term::Sort { span, .. } => span.clone(),
+            Term::Pi { span, .. } => span.clone(),
+            Term::Lambda { span, .. } => span.clone(),
+            Term::Application { span, .. } => span.clone(),
+        }
+    }
+    /// How tightly the term binds, from 0 for the binders extending as far
+    /// right as possible to 3 for atoms.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Term::Pi {
+                variable, codomain, ..
+            } if !codomain.occurs(&variable.name) => 1,
+            Term::Pi { .. } | Term::Lambda { .. } => 0,
+            Term::Application { .. } => 2,
+            Term::Variable(_) | Term::Sort { .. } => 3,
+        }
+    }
+    /// The variables of the term not bound within it, in order of
+    /// appearance.
+    pub fn free_variables(&self) -> Vec<String> {
+        let mut variables = Vec::new();
+        self.collect_free_variables(&mut Vec::new(), &mut variables);
+        variables
+    }
+    fn collect_free_variables(&self, bound: &mut Vec<String>, free: &mut Vec<String>) {
+        match self {
+            Term::Variable(Variable { name, .. }) => {
+                if !bound.contains(name) && !free.contains(name) {
+                    free.push(name.clone());
+                }
+            }
+            Term::Sort { .. } => (),
+            Term::Pi {
+                variable,
+                domain,
+                codomain: body,
+                ..
+            }
+            | Term::Lambda {
+                variable,
+                domain,
+                body,
+                ..
+            } => {
+                domain.collect_free_variables(bound, free);
+                bound.push(variable.name.clone());
+                body.collect_free_variables(bound, free);
+                bound.pop();
+            }
+            Term::Application { callee, arg, .. } => {
+                callee.collect_free_variables(bound, free);
+                arg.collect_free_variables(bound, free);
+            }
+        }
+    }
+    /// Whether the variable `name` occurs free in the term.
+    pub fn occurs(&self, name: &str) -> bool {
+        self.free_variables().iter().any(|n| n == name)
+    }
+    /// The term with `replacement` in place of the free occurrences of the
+    /// variable `name`.
+    pub fn substitute(&self, name: &str, replacement: &Term) -> Term {
+        self.substitute_all(&HashMap::from([(name.to_string(), replacement.clone())]))
+    }
+    /// The term with every free variable mapped by `substitution` replaced
+    /// at once. A binder of a variable free in a replacement is renamed so
+    /// that the replacement does not get captured.
+    pub fn substitute_all(&self, substitution: &HashMap<String, Term>) -> Term {
+        match self {
+            Term::Variable(Variable { name, .. }) => substitution
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| self.clone()),
+            Term::Sort { .. } => self.clone(),
+            Term::Pi {
+                variable,
+                domain,
+                codomain,
+                span,
+            } => {
+                let (variable, codomain) = substitute_under(variable, codomain, substitution);
+                Term::Pi {
+                    variable,
+                    domain: Box::new(domain.substitute_all(substitution)),
+                    codomain: Box::new(codomain),
+                    span: span.clone(),
+                }
+            }
+            Term::Lambda {
+                variable,
+                domain,
+                body,
+                span,
+            } => {
+                let (variable, body) = substitute_under(variable, body, substitution);
+                Term::Lambda {
+                    variable,
+                    domain: Box::new(domain.substitute_all(substitution)),
+                    body: Box::new(body),
+                    span: span.clone(),
+                }
+            }
+            Term::Application { callee, arg, span } => Term::Application {
+                callee: Box::new(callee.substitute_all(substitution)),
+                arg: Box::new(arg.substitute_all(substitution)),
+                span: span.clone(),
+            },
+        }
+    }
+    /// Whether the terms are the same up to the renaming of their bound
+    /// variables, `bound` pairing the binders met so far in either term.
+    fn equivalent(&self, other: &Term, bound: &mut Vec<(String, String)>) -> bool {
+        match (self, other) {
+            (Term::Variable(variable1), Term::Variable(variable2)) => {
+                let (name1, name2) = (&variable1.name, &variable2.name);
+                match bound
+                    .iter()
+                    .rev()
+                    .find(|(binder1, binder2)| binder1 == name1 || binder2 == name2)
+                {
+                    Some((binder1, binder2)) => binder1 == name1 && binder2 == name2,
+                    None => name1 == name2,
+                }
+            }
+            (Term::Sort { sort: sort1, .. }, Term::Sort { sort: sort2, .. }) => sort1 == sort2,
+            (
+                Term::Pi {
+                    variable: variable1,
+                    domain: domain1,
+                    codomain: body1,
+                    ..
+                },
+                Term::Pi {
+                    variable: variable2,
+                    domain: domain2,
+                    codomain: body2,
+                    ..
+                },
+            )
+            | (
+                Term::Lambda {
+                    variable: variable1,
+                    domain: domain1,
+                    body: body1,
+                    ..
+                },
+                Term::Lambda {
+                    variable: variable2,
+                    domain: domain2,
+                    body: body2,
+                    ..
+                },
+            ) => {
+                if !domain1.equivalent(domain2, bound) {
+                    return false;
+                }
+                bound.push((variable1.name.clone(), variable2.name.clone()));
+                let equivalent = body1.equivalent(body2, bound);
+                bound.pop();
+                equivalent
+            }
+            (
+                Term::Application {
+                    callee: callee1,
+                    arg: arg1,
+                    ..
+                },
+                Term::Application {
+                    callee: callee2,
+                    arg: arg2,
+                    ..
+                },
+            ) => callee1.equivalent(callee2, bound) && arg1.equivalent(arg2, bound),
+            _ => false,
+        }
+    }
+    /// The span of the binder of the variable at `offset`, or of the binder
+    /// itself when `offset` falls on one. `None` for a variable bound by no
+    /// binder of the term.
+    fn resolve<'a>(&'a self, offset: usize, scope: &mut Vec<&'a Variable>) -> Option<Span> {
+        match self {
+            Term::Variable(variable) if variable.span.contains(&offset) => scope
+                .iter()
+                .rev()
+                .find(|binder| binder.name == variable.name)
+                .map(|binder| binder.span.clone()),
+            Term::Variable(_) | Term::Sort { .. } => None,
+            Term::Pi {
+                variable,
+                domain,
+                codomain: body,
+                ..
+            }
+            | Term::Lambda {
+                variable,
+                domain,
+                body,
+                ..
+            } => domain.resolve(offset, scope).or_else(|| {
+                if variable.span.contains(&offset) && variable.name != ANONYMOUS {
+                    return Some(variable.span.clone());
+                }
+                scope.push(variable);
+                let definition = body.resolve(offset, scope);
+                scope.pop();
+                definition
+            }),
+            Term::Application { callee, arg, .. } => callee
+                .resolve(offset, scope)
+                .or_else(|| arg.resolve(offset, scope)),
+        }
+    }
+    /// The variable at `offset`, if any.
+    fn variable_at(&self, offset: usize) -> Option<&Variable> {
+        match self {
+            Term::Variable(variable) if variable.span.contains(&offset) => Some(variable),
+            Term::Variable(_) | Term::Sort { .. } => None,
+            Term::Pi {
+                domain,
+                codomain: body,
+                ..
+            }
+            | Term::Lambda { domain, body, .. } => domain
+                .variable_at(offset)
+                .or_else(|| body.variable_at(offset)),
+            Term::Application { callee, arg, .. } => callee
+                .variable_at(offset)
+                .or_else(|| arg.variable_at(offset)),
+        }
+    }
+    /// The binder, domain and body of a `λ`, or of a `Π` whose codomain
+    /// depends on its variable when `product`.
+    fn binding(&self, product: bool) -> Option<(&Variable, &Term, &Term)> {
+        match self {
+            Term::Lambda {
+                variable,
+                domain,
+                body,
+                ..
+            } if !product => Some((variable, domain, body)),
+            Term::Pi {
+                variable,
+                domain,
+                codomain,
+                ..
+            } if product && codomain.occurs(&variable.name) => Some((variable, domain, codomain)),
+            _ => None,
+        }
+    }
+    /// Writes the chain of `λ`s or of dependent `Π`s the term starts with,
+    /// grouping the consecutive binders of a same domain as in
+    /// `Π (A B : Prop). A -> B`.
+    fn fmt_binders(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let product = matches!(self, Term::Pi { .. });
+        let mut groups: Vec<(Vec<&str>, &Term)> = Vec::new();
+        let mut term = self;
+        while let Some((variable, domain, body)) = term.binding(product) {
+            match groups.last_mut() {
+                // The domain cannot be shared with the previous binders when
+                // it refers to them.
+                Some((names, last))
+                    if *last == domain && names.iter().all(|name| !domain.occurs(name)) =>
+                {
+                    names.push(&variable.name)
+                }
+                _ => groups.push((vec![&variable.name], domain)),
+            }
+            term = body;
+        }
+        write!(f, "{}", if product { "Π" } else { "λ" })?;
+        for (names, domain) in groups {
+            write!(f, " ({} : {})", names.join(" "), domain)?;
+        }
+        write!(f, ". {}", term)
+    }
+    /// Writes the term, parenthesized if it binds looser than `level`.
+    fn fmt_at(&self, f: &mut fmt::Formatter, level: u8) -> fmt::Result {
+        if self.precedence() < level {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// The binder `variable` of `body` and the body with the substitution
+/// applied, the binder renamed if a replacement would get captured.
+fn substitute_under(
+    variable: &Variable,
+    body: &Term,
+    substitution: &HashMap<String, Term>,
+) -> (Variable, Term) {
+    let mut inner = substitution.clone();
+    inner.remove(&variable.name);
+    let free = body.free_variables();
+    let captured = inner
+        .iter()
+        .any(|(name, replacement)| free.contains(name) && replacement.occurs(&variable.name));
+    if !captured {
+        return (variable.clone(), body.substitute_all(&inner));
+    }
+    let mut avoid = free;
+    for (name, replacement) in &inner {
+        avoid.push(name.clone());
+        avoid.extend(replacement.free_variables());
+    }
+    let renamed = Variable {
+        name: fresh(&variable.name, &avoid),
+        span: variable.span.clone(),
+    };
+    inner.insert(variable.name.clone(), Term::Variable(renamed.clone()));
+    (renamed, body.substitute_all(&inner))
+}
+
+/// `name` primed as many times as needed not to be one of `avoid`.
+pub fn fresh(name: &str, avoid: &[String]) -> String {
+    let mut fresh = format!("{}'", name);
+    while avoid.contains(&fresh) {
+        fresh.push('\'');
+    }
+    fresh
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Term::Variable(Variable { name, .. }) => write!(f, "{}", name),
+            Term::Sort { sort, .. } => write!(f, "{}", sort),
+            // A product whose codomain does not depend on its variable is an
+            // arrow, which associates to the right.
+            Term::Pi {
+                variable,
+                domain,
+                codomain,
+                ..
+            } if !codomain.occurs(&variable.name) => {
+                domain.fmt_at(f, 2)?;
+                write!(f, " -> {}", codomain)
+            }
+            Term::Pi { .. } | Term::Lambda { .. } => self.fmt_binders(f),
+            Term::Application { callee, arg, .. } => {
+                callee.fmt_at(f, 2)?;
+                write!(f, " ")?;
+                arg.fmt_at(f, 3)
+            }
+        }
+    }
+}
+
+impl PartialEq for Term {
+    fn eq(&self, other: &Self) -> bool {
+        self.equivalent(other, &mut Vec::new())
+    }
+}
+
+/// A variable, named where it is bound or used.
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub name: String,
+    pub span: Span,
+}
@@ -0,0 +1,115 @@
+use diagnostic::Diagnostic;
+use std::io;
+use std::ops::Range;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A term whose type is not convertible to the one expected of it.
+    TypeMismatch {
+        expected: String,
+        found: String,
+        span: Range<usize>,
+    },
+    NotAFunction {
+        found: String,
+        span: Range<usize>,
+    },
+    /// A term used as a type, whose own type is not a sort.
+    NotAType {
+        found: String,
+        span: Range<usize>,
+    },
+    /// A theorem stating a type of sort `Type` rather than a proposition.
+    NotAProposition {
+        statement: String,
+        span: Range<usize>,
+    },
+    /// The sort `Type`, written where its type is needed.
+    TypeOfType {
+        span: Range<usize>,
+    },
+    /// An abstraction whose body has type `Type`, which no product could
+    /// type.
+    LargeAbstraction {
+        span: Range<usize>,
+    },
+    UndefinedSymbol {
+        name: String,
+        span: Range<usize>,
+    },
+    Redeclaration {
+        name: String,
+        span: Range<usize>,
+        previous: Range<usize>,
+    },
+}
+
+impl Error {
+    pub fn report(&self, filename: &str, source: &str) {
+        self.diagnostic().report(filename, source)
+    }
+
+    /// Renders the report into `out` rather than the standard output.
+    pub fn write(&self, filename: &str, source: &str, out: impl io::Write) -> io::Result<()> {
+        self.diagnostic().write(filename, source, out)
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        match &self {
+            Error::TypeMismatch {
+                expected,
+                found,
+                span,
+            } => Diagnostic::new(
+                "type-mismatch",
+                format!("Expected type `{}`, but found `{}`.", expected, found),
+            )
+            .with_primary(span.clone(), format!("Found type `{}`.", found)),
+            Error::NotAFunction { found, span } => Diagnostic::new(
+                "not-a-function",
+                format!("Expected a function, but found type `{}`.", found),
+            )
+            .with_primary(span.clone(), "This is applied to an argument."),
+            Error::NotAType { found, span } => Diagnostic::new(
+                "not-a-type",
+                format!("Expected a type, but found a term of type `{}`.", found),
+            )
+            .with_primary(
+                span.clone(),
+                "This is used as a type, but its type is not a sort.",
+            ),
+            Error::NotAProposition { statement, span } => Diagnostic::new(
+                "not-a-proposition",
+                format!("`{}` is not a proposition.", statement),
+            )
+            .with_primary(span.clone(), "A theorem states a type of sort `Prop`."),
+            Error::TypeOfType { span } => Diagnostic::new("type-of-type", "`Type` has no type.")
+                .with_primary(
+                    span.clone(),
+                    "`Type` is the sort of `Prop`, and has no sort itself.",
+                ),
+            Error::LargeAbstraction { span } => Diagnostic::new(
+                "large-abstraction",
+                "Functions cannot return terms of type `Type`.",
+            )
+            .with_primary(span.clone(), "This has type `Type`."),
+            Error::UndefinedSymbol { name, span } => {
+                Diagnostic::new("undefined-symbol", format!("Undefined symbol `{}`.", name))
+                    .with_primary(span.clone(), format!("`{}` is not defined.", name))
+            }
+            Error::Redeclaration {
+                name,
+                span,
+                previous,
+            } => Diagnostic::new(
+                "redeclaration",
+                format!("`{}` is declared more than once.", name),
+            )
+            .with_secondary(
+                previous.clone(),
+                format!("`{}` is first declared here.", name),
+            )
+            .with_primary(span.clone(), "And declared again here."),
+        }
+    }
+}
@@ -0,0 +1,5 @@
+#![feature(trait_alias)]
+pub mod ast;
+pub mod error;
+pub mod parser;
+pub mod typechecker;
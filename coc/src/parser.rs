@@ -0,0 +1,261 @@
+use crate::ast::*;
+use chumsky::error::SimpleReason;
+use chumsky::prelude::*;
+use std::ops::Range;
+
+pub trait CocParser<T> = Parser<char, T, Error = Simple<char>> + Clone;
+
+/// Parses the declarations of `source`, recovering from syntax errors so that
+/// every one of them is reported rather than only the first.
+pub fn parse(source: &str) -> Result<Vec<Declaration>, Vec<Simple<char>>> {
+    let program = declaration()
+        .repeated()
+        .then_ignore(blank())
+        .then_ignore(end());
+    match program.parse_recovery(source) {
+        (Some(program), errors) if errors.is_empty() => Ok(program),
+        (_, errors) => Err(errors),
+    }
+}
+
+/// Parses a single term, such as one typed in the REPL.
+pub fn parse_term(source: &str) -> Result<Term, Vec<Simple<char>>> {
+    match term()
+        .then_ignore(blank())
+        .then_ignore(end())
+        .parse_recovery(source)
+    {
+        (Some(term), errors) if errors.is_empty() => Ok(term),
+        (_, errors) => Err(errors),
+    }
+}
+
+/// Words reserved by the syntax, which cannot be used as variables.
+pub const KEYWORDS: &[&str] = &["Prop", "Type", "definition", "forall", "theorem"];
+
+/// Whether `name` can be declared.
+pub fn is_variable(name: &str) -> bool {
+    identifier().then_ignore(end()).parse(name).is_ok() && !KEYWORDS.contains(&name)
+}
+
+/// A letter, followed by letters, digits, `_` and `'`.
+fn identifier() -> impl CocParser<String> {
+    filter(|c: &char| c.is_ascii_alphabetic())
+        .chain(filter(|c: &char| c.is_ascii_alphanumeric() || *c == '_' || *c == '\'').repeated())
+        .collect()
+}
+
+/// Whitespace, and the comments running from `--` to the end of the line.
+fn blank() -> impl CocParser<()> {
+    let comment = just("--").then(take_until(text::newline().or(end())));
+    text::whitespace()
+        .then(comment.then(text::whitespace()).repeated())
+        .ignored()
+}
+
+/// `parser` after any blank. Nothing takes the blank after it, so that the
+/// span of a term ends where its last token does.
+fn token<T>(parser: impl CocParser<T>) -> impl CocParser<T> {
+    blank().ignore_then(parser)
+}
+
+/// The keyword `k`, which must not run into the letters after it.
+fn keyword(k: &'static str) -> impl CocParser<Range<usize>> {
+    debug_assert!(KEYWORDS.contains(&k), "`{}` is not a keyword", k);
+    token(identifier().try_map(move |name, span| {
+        if name == k {
+            Ok(span)
+        } else {
+            Err(diagnostic::expected_keyword(span, k))
+        }
+    }))
+}
+
+fn variable() -> impl CocParser<Variable> {
+    token(identifier().try_map(|name, span| {
+        if KEYWORDS.contains(&name.as_str()) {
+            Err(Simple::custom(
+                span,
+                format!("`{}` is a keyword, not a variable", name),
+            ))
+        } else {
+            Ok(Variable { name, span })
+        }
+    }))
+    .labelled("a variable")
+}
+
+/// A variable bound by a `λ` or a `Π`, which `_` leaves unnamed.
+fn binder() -> impl CocParser<Variable> {
+    variable().or(token(just('_').map_with_span(|_, span| Variable {
+        name: ANONYMOUS.to_string(),
+        span,
+    })))
+}
+
+/// Reports the end of the input within parentheses, `span` starting at the
+/// opening one, as this parenthesis being left unclosed.
+fn unclosed(error: Simple<char>, span: Range<usize>) -> Simple<char> {
+    let within = span.start < error.span().start;
+    if !within || error.found().is_some() || matches!(error.reason(), SimpleReason::Unclosed { .. })
+    {
+        return error;
+    }
+    Simple::unclosed_delimiter(span.start..span.start + 1, '(', error.span(), ')', None)
+        .merge(error)
+}
+
+/// Builds `λ`s or `Π`s out of the `groups` of binders sharing a domain, from
+/// the one starting at `start` to `body`.
+fn bind(product: bool, start: usize, groups: Vec<(Vec<Variable>, Term)>, body: Term) -> Term {
+    let end = body.get_span().end;
+    let binders = groups
+        .into_iter()
+        .flat_map(|(variables, domain)| variables.into_iter().map(move |v| (v, domain.clone())))
+        .collect::<Vec<_>>();
+    binders
+        .into_iter()
+        .enumerate()
+        .rev()
+        .fold(body, |body, (i, (variable, domain))| {
+            // Every binder but the first spans from its variable on.
+            let span = if i == 0 { start } else { variable.span.start }..end;
+            let (domain, body) = (Box::new(domain), Box::new(body));
+            if product {
+                Term::Pi {
+                    variable,
+                    domain,
+                    codomain: body,
+                    span,
+                }
+            } else {
+                Term::Lambda {
+                    variable,
+                    domain,
+                    body,
+                    span,
+                }
+            }
+        })
+}
+
+pub fn term() -> impl CocParser<Term> {
+    recursive(|term| {
+        let atom = choice((
+            keyword("Prop").map(|span| Term::Sort {
+                sort: Sort::Prop,
+                span,
+            }),
+            keyword("Type").map(|span| Term::Sort {
+                sort: Sort::Type,
+                span,
+            }),
+            variable().map(Term::Variable),
+            // Priority
+            token(
+                just('(')
+                    .ignore_then(term.clone())
+                    .then_ignore(token(just(')')))
+                    .map_err_with_span(unclosed),
+            ),
+        ))
+        // A parenthesized term that does not parse is skipped up to its
+        // closing parenthesis. The sort standing for it is never checked
+        // since the parse failed.
+        .recover_with(nested_delimiters('(', ')', [], |span| Term::Sort {
+            sort: Sort::Prop,
+            span,
+        }));
+
+        let application =
+            atom.clone()
+                .then(atom.repeated())
+                .foldl(|callee, arg| Term::Application {
+                    span: callee.get_span().start..arg.get_span().end,
+                    callee: Box::new(callee),
+                    arg: Box::new(arg),
+                });
+
+        let arrow = application
+            .then(token(just("->")).ignore_then(term.clone()).or_not())
+            .map(|(domain, codomain)| match codomain {
+                Some(codomain) => Term::Pi {
+                    span: domain.get_span().start..codomain.get_span().end,
+                    variable: Variable {
+                        name: ANONYMOUS.to_string(),
+                        span: domain.get_span(),
+                    },
+                    domain: Box::new(domain),
+                    codomain: Box::new(codomain),
+                },
+                None => domain,
+            });
+
+        let group = binder()
+            .repeated()
+            .at_least(1)
+            .then_ignore(token(just(':')))
+            .then(term.clone());
+        let binders = choice((
+            group
+                .clone()
+                .delimited_by(token(just('(')), token(just(')')))
+                .repeated()
+                .at_least(1),
+            group.map(|group| vec![group]),
+        ));
+
+        let product = choice((
+            token(just('Π').map_with_span(|_, span: Range<usize>| span)),
+            keyword("forall"),
+        ))
+        .then(binders.clone())
+        .then_ignore(token(just('.')))
+        .then(term.clone())
+        .map(|((start, groups), codomain)| bind(true, start.start, groups, codomain));
+
+        let abstraction = token(just('λ').or(just('\\')).map_with_span(|_, span| span))
+            .then(binders)
+            .then_ignore(token(just('.')))
+            .then(term)
+            .map(|((start, groups), body): ((Range<usize>, _), _)| {
+                bind(false, start.start, groups, body)
+            });
+
+        choice((product, abstraction, arrow))
+    })
+}
+
+fn declaration() -> impl CocParser<Declaration> {
+    let definition = keyword("definition")
+        .then(variable())
+        .then(choice((
+            token(just(":=")).to(None),
+            token(just(':'))
+                .ignore_then(term())
+                .then_ignore(token(just(":=")))
+                .map(Some),
+        )))
+        .then(term())
+        .map(|(((start, name), typ), value)| Declaration::Definition {
+            span: start.start..value.get_span().end,
+            name,
+            typ,
+            value,
+        });
+
+    let theorem = keyword("theorem")
+        .then(variable())
+        .then_ignore(token(just(':')))
+        .then(term())
+        .then_ignore(token(just(":=")))
+        .then(term())
+        .map(|(((start, name), statement), proof)| Declaration::Theorem {
+            span: start.start..proof.get_span().end,
+            name,
+            statement,
+            proof,
+        });
+
+    definition.or(theorem)
+}
@@ -0,0 +1,353 @@
+use crate::ast::*;
+use crate::error::Error;
+use std::ops::Range;
+
+/// A declaration checked so far, which later terms unfold to its value.
+#[derive(Debug, Clone)]
+struct Definition {
+    name: Variable,
+    typ: Term,
+    value: Term,
+}
+
+#[derive(Default, Clone)]
+pub struct TypeChecker {
+    /// The declarations checked so far, in order.
+    definitions: Vec<Definition>,
+    /// The typing context, a stack of the variables bound around the term
+    /// being checked along with their types. A binder of a name already in
+    /// scope is renamed, so that no name is bound twice.
+    context: Vec<(String, Term)>,
+    /// The type of every subterm and binder checked so far.
+    types: Vec<(Range<usize>, Term)>,
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The type of the innermost subterm or binder spanning `offset`.
+    ///
+    /// Only the subterms checked before an error are known.
+    pub fn type_at(&self, offset: usize) -> Option<&Term> {
+        self.types
+            .iter()
+            .filter(|(span, _)| span.contains(&offset))
+            .min_by_key(|(span, _)| span.len())
+            .map(|(_, typ)| typ)
+    }
+
+    /// Checks the declarations of `program` in order, returning the name and
+    /// type of each.
+    pub fn check(&mut self, program: Vec<Declaration>) -> Result<Vec<(String, Term)>, Error> {
+        program
+            .into_iter()
+            .map(|declaration| {
+                let name = declaration.name().name.clone();
+                self.declare(declaration).map(|typ| (name, typ))
+            })
+            .collect()
+    }
+
+    /// Checks `declaration`, making it available to the terms checked
+    /// afterwards, and returns its type.
+    pub fn declare(&mut self, declaration: Declaration) -> Result<Term, Error> {
+        let name = declaration.name().clone();
+        if let Some(previous) = self.definition(&name.name) {
+            return Err(Error::Redeclaration {
+                name: name.name,
+                span: name.span,
+                previous: previous.name.span.clone(),
+            });
+        }
+        let (typ, value) = match declaration {
+            Declaration::Definition {
+                typ: None, value, ..
+            } => (self.infer(value.clone())?, value),
+            Declaration::Definition {
+                typ: Some(typ),
+                value,
+                ..
+            } => {
+                self.sort(typ.clone())?;
+                self.check_against(value.clone(), &typ)?;
+                (typ, value)
+            }
+            Declaration::Theorem {
+                statement, proof, ..
+            } => {
+                let span = statement.get_span();
+                if self.sort(statement.clone())? != Sort::Prop {
+                    return Err(Error::NotAProposition {
+                        statement: statement.to_string(),
+                        span,
+                    });
+                }
+                self.check_against(proof.clone(), &statement)?;
+                (statement, proof)
+            }
+        };
+        self.definitions.push(Definition {
+            name,
+            typ: typ.clone(),
+            value,
+        });
+        Ok(typ)
+    }
+
+    pub fn infer(&mut self, term: Term) -> Result<Term, Error> {
+        let span = term.get_span();
+        let typ = match term {
+            Term::Variable(Variable { name, span }) => match self.lookup(&name) {
+                Some(typ) => typ.clone(),
+                None => return Err(Error::UndefinedSymbol { name, span }),
+            },
+            Term::Sort {
+                sort: Sort::Prop,
+                span,
+            } => Term::Sort {
+                sort: Sort::Type,
+                span,
+            },
+            Term::Sort {
+                sort: Sort::Type,
+                span,
+            } => return Err(Error::TypeOfType { span }),
+            Term::Pi {
+                variable,
+                domain,
+                codomain,
+                span,
+            } => {
+                self.sort(*domain.clone())?;
+                let (variable, codomain) = self.bind(variable, *codomain);
+                self.types.push((variable.span.clone(), *domain.clone()));
+                self.context.push((variable.name, *domain));
+                let sort = self.sort(codomain);
+                self.context.pop();
+                Term::Sort { sort: sort?, span }
+            }
+            Term::Lambda {
+                variable,
+                domain,
+                body,
+                span,
+            } => {
+                self.sort(*domain.clone())?;
+                let (variable, body) = self.bind(variable, *body);
+                self.types.push((variable.span.clone(), *domain.clone()));
+                self.context.push((variable.name.clone(), *domain.clone()));
+                let body_span = body.get_span();
+                let codomain =
+                    self.infer(body)
+                        .and_then(|codomain| match self.whnf(codomain.clone()) {
+                            Term::Sort {
+                                sort: Sort::Type, ..
+                            } => Err(Error::LargeAbstraction { span: body_span }),
+                            _ => Ok(codomain),
+                        });
+                self.context.pop();
+                Term::Pi {
+                    variable,
+                    domain,
+                    codomain: Box::new(codomain?),
+                    span,
+                }
+            }
+            Term::Application { callee, arg, .. } => {
+                let callee_span = callee.get_span();
+                let typ = self.infer(*callee)?;
+                match self.whnf(typ.clone()) {
+                    Term::Pi {
+                        variable,
+                        domain,
+                        codomain,
+                        ..
+                    } => {
+                        self.check_against((*arg).clone(), &domain)?;
+                        codomain.substitute(&variable.name, &arg)
+                    }
+                    _ => {
+                        return Err(Error::NotAFunction {
+                            found: typ.to_string(),
+                            span: callee_span,
+                        })
+                    }
+                }
+            }
+        };
+        self.types.push((span, typ.clone()));
+        Ok(typ)
+    }
+
+    /// Checks that `term` has a type convertible to `expected`.
+    fn check_against(&mut self, term: Term, expected: &Term) -> Result<(), Error> {
+        let span = term.get_span();
+        let typ = self.infer(term)?;
+        if self.convertible(&typ, expected) {
+            Ok(())
+        } else {
+            Err(Error::TypeMismatch {
+                expected: expected.to_string(),
+                found: typ.to_string(),
+                span,
+            })
+        }
+    }
+
+    /// The sort of `typ`, which must be a type.
+    fn sort(&mut self, typ: Term) -> Result<Sort, Error> {
+        let span = typ.get_span();
+        let sort = self.infer(typ)?;
+        match self.whnf(sort.clone()) {
+            Term::Sort { sort, .. } => Ok(sort),
+            _ => Err(Error::NotAType {
+                found: sort.to_string(),
+                span,
+            }),
+        }
+    }
+
+    /// Whether the terms are equal once normalized.
+    pub fn convertible(&self, t1: &Term, t2: &Term) -> bool {
+        self.normalize(t1) == self.normalize(t2)
+    }
+
+    /// The normal form of `term`, where no application of a `λ` is left and
+    /// every declaration is unfolded.
+    ///
+    /// Every well typed term has one, normalization being strong in the
+    /// calculus of constructions.
+    pub fn normalize(&self, term: &Term) -> Term {
+        let mut bound = self.context.iter().map(|(name, _)| name.clone()).collect();
+        self.normalize_under(term, &mut bound)
+    }
+
+    /// The normal form of `term` under the binders of `bound`, which shadow
+    /// the declarations of the same names.
+    fn normalize_under(&self, term: &Term, bound: &mut Vec<String>) -> Term {
+        match term {
+            Term::Variable(Variable { name, .. }) if !bound.contains(name) => {
+                match self.definition(name) {
+                    // A declaration is closed, only referring to the ones
+                    // before it.
+                    Some(definition) => self.normalize_under(&definition.value, &mut Vec::new()),
+                    None => term.clone(),
+                }
+            }
+            Term::Variable(_) | Term::Sort { .. } => term.clone(),
+            Term::Pi {
+                variable,
+                domain,
+                codomain,
+                span,
+            } => {
+                let domain = self.normalize_under(domain, bound);
+                bound.push(variable.name.clone());
+                let codomain = self.normalize_under(codomain, bound);
+                bound.pop();
+                Term::Pi {
+                    variable: variable.clone(),
+                    domain: Box::new(domain),
+                    codomain: Box::new(codomain),
+                    span: span.clone(),
+                }
+            }
+            Term::Lambda {
+                variable,
+                domain,
+                body,
+                span,
+            } => {
+                let domain = self.normalize_under(domain, bound);
+                bound.push(variable.name.clone());
+                let body = self.normalize_under(body, bound);
+                bound.pop();
+                Term::Lambda {
+                    variable: variable.clone(),
+                    domain: Box::new(domain),
+                    body: Box::new(body),
+                    span: span.clone(),
+                }
+            }
+            Term::Application { callee, arg, span } => {
+                let arg = self.normalize_under(arg, bound);
+                match self.normalize_under(callee, bound) {
+                    Term::Lambda { variable, body, .. } => {
+                        self.normalize_under(&body.substitute(&variable.name, &arg), bound)
+                    }
+                    callee => Term::Application {
+                        callee: Box::new(callee),
+                        arg: Box::new(arg),
+                        span: span.clone(),
+                    },
+                }
+            }
+        }
+    }
+
+    /// The weak head normal form of `term`, reduced until it no longer starts
+    /// with a declaration or an application of a `λ`. It tells a product or a
+    /// sort apart without unfolding the rest of the term.
+    fn whnf(&self, term: Term) -> Term {
+        match term {
+            Term::Variable(Variable { ref name, .. }) if self.lookup_local(name).is_none() => {
+                match self.definition(name) {
+                    Some(definition) => self.whnf(definition.value.clone()),
+                    None => term,
+                }
+            }
+            Term::Application { callee, arg, span } => match self.whnf(*callee) {
+                Term::Lambda { variable, body, .. } => {
+                    self.whnf(body.substitute(&variable.name, &arg))
+                }
+                callee => Term::Application {
+                    callee: Box::new(callee),
+                    arg,
+                    span,
+                },
+            },
+            term => term,
+        }
+    }
+
+    /// `variable` bound around `body`, renamed along with its occurrences in
+    /// `body` if a variable of the same name is already in scope.
+    fn bind(&self, variable: Variable, body: Term) -> (Variable, Term) {
+        let in_scope = self.context.iter().map(|(name, _)| name).chain(
+            self.definitions
+                .iter()
+                .map(|definition| &definition.name.name),
+        );
+        let mut avoid = in_scope.cloned().collect::<Vec<_>>();
+        if variable.name == ANONYMOUS || !avoid.contains(&variable.name) {
+            return (variable, body);
+        }
+        avoid.extend(body.free_variables());
+        let renamed = Variable {
+            name: fresh(&variable.name, &avoid),
+            span: variable.span,
+        };
+        let body = body.substitute(&variable.name, &Term::Variable(renamed.clone()));
+        (renamed, body)
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Term> {
+        self.lookup_local(name)
+            .or_else(|| self.definition(name).map(|definition| &definition.typ))
+    }
+
+    fn lookup_local(&self, name: &str) -> Option<&Term> {
+        self.context
+            .iter()
+            .rev()
+            .find_map(|(n, typ)| (n == name).then_some(typ))
+    }
+
+    fn definition(&self, name: &str) -> Option<&Definition> {
+        self.definitions
+            .iter()
+            .find(|definition| definition.name.name == name)
+    }
+}
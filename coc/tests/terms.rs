@@ -0,0 +1,137 @@
+use coc::ast::{Sort, Term, Variable};
+use coc::parser::parse_term;
+use coc::typechecker::TypeChecker;
+
+fn variable(name: &str) -> Term {
+    Term::Variable(Variable {
+        name: name.to_string(),
+        span: 0..0,
+    })
+}
+
+fn term(source: &str) -> Term {
+    let Ok(term) = parse_term(source) else {
+        panic!("`{}` does not parse", source);
+    };
+    term
+}
+
+/// A checker with the declarations of `source`.
+fn checker(source: &str) -> TypeChecker {
+    let Ok(program) = coc::parser::parse(source) else {
+        panic!("`{}` does not parse", source);
+    };
+    let mut tc = TypeChecker::new();
+    if let Err(e) = tc.check(program) {
+        panic!("`{}` is rejected: {}", source, e.diagnostic().message);
+    }
+    tc
+}
+
+#[test]
+fn terms_equal_up_to_the_names_of_their_binders() {
+    assert_eq!(
+        term("λ (A : Prop) (x : A). x"),
+        term("λ (B : Prop) (y : B). y")
+    );
+    assert_eq!(
+        term("Π (A : Prop). A -> A"),
+        term("Π (X : Prop) (_ : X). X")
+    );
+    assert_ne!(term("λ (x y : Prop). x"), term("λ (x y : Prop). y"));
+    // `B` is free in the first term and bound in the second.
+    assert_ne!(term("λ (A : Prop). B"), term("λ (B : Prop). B"));
+}
+
+#[test]
+fn substitution_renames_binders_that_would_capture() {
+    let body = term("λ (y : Prop). x");
+    let substituted = body.substitute("x", &variable("y"));
+    assert_eq!(substituted, term("λ (z : Prop). y"));
+    assert_eq!(substituted.to_string(), "λ (y' : Prop). y");
+}
+
+#[test]
+fn substitution_stops_at_binders_of_the_same_name() {
+    let body = term("x -> Π (x : Prop). x");
+    assert_eq!(
+        body.substitute("x", &variable("y")),
+        term("y -> Π (x : Prop). x")
+    );
+}
+
+#[test]
+fn arrows_print_as_products_they_do_not_depend_on() {
+    for source in [
+        "Π (A B : Prop). (A -> B) -> A -> B",
+        "Π (A : Prop) (x : A) (P : A -> Prop). P x -> P x",
+        "λ (A : Prop) (f : Π (B : Prop). B -> A). f A",
+        "(λ (A : Prop). A) (Π (A : Prop). A)",
+    ] {
+        assert_eq!(term(source).to_string(), source);
+    }
+}
+
+#[test]
+fn binders_share_a_domain_only_when_it_does_not_refer_to_them() {
+    // The second `A` is bound by the first binder.
+    let dependent = term("Π (A : A) (B : A). B");
+    assert_eq!(dependent.to_string(), "Π (A : A) (B : A). B");
+}
+
+#[test]
+fn normalization_unfolds_declarations_and_reduces_applications() {
+    let tc = checker(
+        "definition Nat : Prop := Π (X : Prop). (X -> X) -> X -> X
+         definition succ : Nat -> Nat :=
+           λ (n : Nat) (X : Prop) (s : X -> X) (z : X). s (n X s z)
+         definition one : Nat := λ (X : Prop) (s : X -> X) (z : X). s z",
+    );
+    assert_eq!(
+        tc.normalize(&term("succ one")),
+        term("λ (X : Prop) (s : X -> X) (z : X). s (s z)")
+    );
+    assert!(tc.convertible(&term("Nat -> Nat"), &term("(λ (A : Prop). A -> A) Nat")));
+    assert!(!tc.convertible(&term("succ one"), &term("one")));
+}
+
+#[test]
+fn binders_shadowing_declarations_are_not_unfolded() {
+    let tc = checker("definition T : Prop := Π (X : Prop). X");
+    let shadowed = term("λ (T : Prop). T");
+    assert_eq!(tc.normalize(&shadowed), shadowed);
+}
+
+#[test]
+fn prop_has_type_type() {
+    let mut tc = TypeChecker::new();
+    let Ok(typ) = tc.infer(term("Prop")) else {
+        panic!("`Prop` is rejected");
+    };
+    assert!(matches!(
+        typ,
+        Term::Sort {
+            sort: Sort::Type,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn definitions_resolve_to_their_declaration() {
+    // Spans count characters, so the program is written in ASCII for the
+    // byte offsets below to be ones.
+    let source = "definition True : Prop := forall (A : Prop). A -> A\n\
+                  theorem trivial : True := \\(A : Prop) (a : A). a";
+    let Ok(program) = coc::parser::parse(source) else {
+        panic!("`{}` does not parse", source);
+    };
+    let usage = source.rfind("True").unwrap();
+    assert_eq!(coc::ast::definition(&program, usage), Some(11..15));
+    let binder = source.rfind("(a").unwrap() + 1;
+    let bound = source.rfind(". a").unwrap() + 2;
+    assert_eq!(
+        coc::ast::definition(&program, bound),
+        Some(binder..binder + 1)
+    );
+}
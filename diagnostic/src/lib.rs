@@ -0,0 +1,236 @@
+use ariadne::{Color, Report, ReportKind, Source};
+use chumsky::error::{Simple, SimpleReason};
+use serde_json::json;
+use std::io;
+use std::ops::Range;
+use yansi::Paint;
+
+type Span = Range<usize>;
+
+/// The error of a parser expecting the keyword `keyword` at `span`.
+pub fn expected_keyword(span: Span, keyword: &str) -> Simple<char> {
+    Simple::custom(span, format!("expected `{}`", keyword))
+}
+
+/// The keyword `error` was raised expecting, if it was raised by
+/// [`expected_keyword`].
+pub fn missing_keyword(error: &Simple<char>) -> Option<&str> {
+    match error.reason() {
+        SimpleReason::Custom(message) => message
+            .strip_prefix("expected `")
+            .and_then(|rest| rest.strip_suffix('`')),
+        _ => None,
+    }
+}
+
+/// A type error or parse error, independent of the way it is rendered.
+///
+/// Spans are character offsets into the source, as produced by the parsers.
+/// Names and types are quoted with backticks in messages, which the text
+/// renderer highlights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: String,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+/// A span of the source with an explanation. The primary labels point at the
+/// error itself, the secondary ones at what it relates to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+impl Diagnostic {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Prints the diagnostic to the standard output.
+    pub fn report(&self, filename: &str, source: &str) {
+        self.build(filename)
+            .print((filename, Source::from(source)))
+            .unwrap()
+    }
+
+    /// Renders the diagnostic into `out` rather than the standard output.
+    pub fn write(&self, filename: &str, source: &str, out: impl io::Write) -> io::Result<()> {
+        self.build(filename)
+            .write((filename, Source::from(source)), out)
+    }
+
+    fn build<'a>(&self, filename: &'a str) -> Report<'a, (&'a str, Span)> {
+        // The header points at the first primary label.
+        let offset = self
+            .labels
+            .iter()
+            .find(|label| label.primary)
+            .map_or(0, |label| label.span.start);
+        let mut report = Report::build(ReportKind::Error, filename, offset)
+            .with_code(&self.code)
+            .with_message(highlight(&self.message));
+        for label in &self.labels {
+            report = report.with_label(
+                ariadne::Label::new((filename, label.span.clone()))
+                    .with_message(highlight(&label.message))
+                    .with_color(if label.primary {
+                        Color::Magenta
+                    } else {
+                        Color::Cyan
+                    }),
+            );
+        }
+        if !self.notes.is_empty() {
+            report = report.with_note(
+                self.notes
+                    .iter()
+                    .map(|note| highlight(note))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+        report.finish()
+    }
+
+    /// Serializes the diagnostic as a single line of JSON, locating the spans
+    /// in `source` both as byte offsets and as 1-based lines and columns.
+    pub fn to_json(&self, filename: &str, source: &str) -> String {
+        let labels = self
+            .labels
+            .iter()
+            .map(|label| {
+                json!({
+                    "primary": label.primary,
+                    "message": label.message,
+                    "start": position(source, label.span.start),
+                    "end": position(source, label.span.end),
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "file": filename,
+            "severity": "error",
+            "code": self.code,
+            "message": self.message,
+            "labels": labels,
+            "notes": self.notes,
+        })
+        .to_string()
+    }
+}
+
+impl From<Simple<char>> for Diagnostic {
+    fn from(error: Simple<char>) -> Self {
+        let message = match error.reason() {
+            SimpleReason::Custom(message) => {
+                let mut chars = message.chars();
+                match chars.next() {
+                    Some(first) => format!("{}{}.", first.to_uppercase(), chars.as_str()),
+                    None => "Invalid syntax.".to_string(),
+                }
+            }
+            SimpleReason::Unclosed { delimiter, .. } => format!("Unclosed `{}`.", delimiter),
+            SimpleReason::Unexpected => match error.found() {
+                Some(c) => format!("Unexpected `{}`.", c.escape_debug()),
+                None => "Unexpected end of input.".to_string(),
+            },
+        };
+        // Integers only announce the digit they may start with, which reads
+        // better as what it stands for.
+        let mut expected = error
+            .expected()
+            .filter(|c| !c.is_some_and(|c| c.is_ascii_digit()))
+            .map(|c| match c {
+                Some(c) => format!("`{}`", c.escape_debug()),
+                None => "end of input".to_string(),
+            })
+            .collect::<Vec<_>>();
+        expected.extend(missing_keyword(&error).map(|k| format!("`{}`", k)));
+        expected.sort();
+        if error
+            .expected()
+            .any(|c| c.is_some_and(|c| c.is_ascii_digit()))
+        {
+            expected.push("a number".to_string());
+        }
+        expected.extend(error.label().map(str::to_string));
+        let label = match expected.split_last() {
+            None => "Invalid syntax here.".to_string(),
+            Some((last, [])) => format!("Expected {}.", last),
+            Some((last, init)) => format!("Expected {} or {}.", init.join(", "), last),
+        };
+        let diagnostic = Diagnostic::new("parse-error", message).with_primary(error.span(), label);
+        match error.reason() {
+            SimpleReason::Unclosed { span, .. } => {
+                diagnostic.with_secondary(span.clone(), "Opened here.")
+            }
+            _ => diagnostic,
+        }
+    }
+}
+
+/// Colors the backquoted parts of `message`, keeping the backquotes.
+fn highlight(message: &str) -> String {
+    message
+        .split('`')
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                part.cyan().bold().to_string()
+            } else {
+                part.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("`")
+}
+
+/// Locates the character `offset` in `source`.
+fn position(source: &str, offset: usize) -> serde_json::Value {
+    let (mut line, mut column, mut byte) = (1, 1, source.len());
+    for (i, (index, c)) in source.char_indices().enumerate() {
+        if i == offset {
+            byte = index;
+            break;
+        }
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    json!({ "offset": byte, "line": line, "column": column })
+}
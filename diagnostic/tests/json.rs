@@ -0,0 +1,38 @@
+use diagnostic::Diagnostic;
+use serde_json::{json, Value};
+
+#[test]
+fn spans_are_located_in_bytes_lines_and_columns() {
+    let source = "λx:Int.\n  x + true";
+    let diagnostic = Diagnostic::new("type-mismatch", "Expected type `Int`, but found `Bool`.")
+        .with_primary(14..18, "Found type `Bool`.")
+        .with_secondary(0..1, "Here.")
+        .with_note("A note.");
+    let json: Value = serde_json::from_str(&diagnostic.to_json("a.stlc", source)).unwrap();
+
+    assert_eq!(json["file"], "a.stlc");
+    assert_eq!(json["code"], "type-mismatch");
+    assert_eq!(json["message"], "Expected type `Int`, but found `Bool`.");
+    assert_eq!(json["notes"], json!(["A note."]));
+    assert_eq!(
+        json["labels"][0],
+        json!({
+            "primary": true,
+            "message": "Found type `Bool`.",
+            "start": { "offset": 15, "line": 2, "column": 7 },
+            "end": { "offset": 19, "line": 2, "column": 11 },
+        })
+    );
+    assert_eq!(json["labels"][1]["primary"], false);
+    assert_eq!(
+        json["labels"][1]["end"],
+        json!({ "offset": 2, "line": 1, "column": 2 })
+    );
+}
+
+#[test]
+fn output_is_a_single_line() {
+    let diagnostic = Diagnostic::new("undefined-symbol", "Undefined symbol `x`.")
+        .with_primary(0..1, "`x` is not defined.");
+    assert!(!diagnostic.to_json("a.stlc", "x\n").contains('\n'));
+}
@@ -0,0 +1,67 @@
+//! Inference on large generated programs, run with `cargo +nightly bench -p hm`.
+
+#![feature(test)]
+
+extern crate test;
+
+use hm::inference::Inference;
+use im::HashMap;
+use test::Bencher;
+
+/// The `i`th variable name, variables being made of lowercase letters only.
+fn name(mut i: usize) -> String {
+    let mut name = "x".to_string();
+    loop {
+        name.push((b'a' + (i % 26) as u8) as char);
+        i /= 26;
+        if i == 0 {
+            return name;
+        }
+    }
+}
+
+/// `let xa = λy. y; in let xb = λy. xa (xa y); in … in xn 1`, each binding
+/// instantiating the previous one twice.
+fn nested_lets(n: usize) -> String {
+    let mut source = format!("let {} = \\y. y; in ", name(0));
+    for i in 1..=n {
+        let previous = name(i - 1);
+        source += &format!("let {} = \\y. {} ({} y); in ", name(i), previous, previous);
+    }
+    source + &format!("{} 1", name(n))
+}
+
+/// `let id = λx. x; in id id … id 1`, with `n` applications of `id`.
+fn application_chain(n: usize) -> String {
+    "let id = \\x. x; in id".to_string() + &" id".repeat(n - 1) + " 1"
+}
+
+fn bench(b: &mut Bencher, source: &str) {
+    let Ok(ast) = hm::parser::parse(source) else {
+        panic!("generated program does not parse");
+    };
+    b.iter(|| {
+        let mut inference = Inference::new();
+        assert!(inference.infer_scheme(ast.clone(), HashMap::new()).is_ok());
+    });
+}
+
+#[bench]
+fn nested_lets_100(b: &mut Bencher) {
+    bench(b, &nested_lets(100));
+}
+
+#[bench]
+fn nested_lets_400(b: &mut Bencher) {
+    bench(b, &nested_lets(400));
+}
+
+#[bench]
+fn application_chain_500(b: &mut Bencher) {
+    bench(b, &application_chain(500));
+}
+
+#[bench]
+fn application_chain_2000(b: &mut Bencher) {
+    bench(b, &application_chain(2000));
+}
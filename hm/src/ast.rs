@@ -4,10 +4,12 @@
 ⟨expression⟩ ⩴ ⟨variable⟩
               | ⟨abstraction⟩
               | ⟨application⟩
-              | ⟨addition⟩
+              | ⟨binary⟩
+              | ⟨conditional⟩
               | ⟨let⟩
               | ⟨letrec⟩
               | ⟨int⟩
+              | ⟨bool⟩
               | ⟨unit⟩
               | '(' ⟨expression⟩ ')'
 
@@ -28,15 +30,25 @@
 ⟨integer⟩ ⩴  ⟨digit⟩+
            | '-' ⟨digit⟩+
 
-⟨addition⟩ ⩴  ⟨digit⟩ '+' ⟨digit⟩
+⟨bool⟩ ⩴ 'true' | 'false'
+
+⟨binary⟩ ⩴ ⟨expression⟩ ⟨operator⟩ ⟨expression⟩
+
+⟨operator⟩ ⩴ '==' | '<' | '+' | '-' | '*'
+
+⟨conditional⟩ ⩴ 'if' ⟨expression⟩ 'then' ⟨expression⟩ 'else' ⟨expression⟩
 
 ⟨digit⟩ ⩴  '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9'
 
 ⟨abstraction⟩ ⩴ 'λ' ⟨variable⟩ '.' ⟨expression⟩
 
 ⟨application⟩ ⩴ ⟨expression⟩ ⟨expression⟩
+
+From the loosest to the tightest, `==` and `<` are non-associative, then come
+the left-associative `+` and `-`, `*`, and application.
 */
 
+use std::fmt;
 use std::ops::Range;
 
 type Span = Range<usize>;
@@ -54,11 +66,18 @@ pub enum Expression {
         arg: Box<Expression>,
         span: Span,
     },
-    Addition {
+    Binary {
+        operator: Operator,
         lhs: Box<Expression>,
         rhs: Box<Expression>,
         span: Span,
     },
+    If {
+        condition: Box<Expression>,
+        then_branch: Box<Expression>,
+        else_branch: Box<Expression>,
+        span: Span,
+    },
     Let {
         bind: Box<Bind>,
         body: Box<Expression>,
@@ -70,6 +89,11 @@ pub enum Expression {
         span: Span,
     },
     Int {
+        n: i32,
+        span: Span,
+    },
+    Bool {
+        value: bool,
         span: Span,
     },
     Unit {
@@ -77,6 +101,27 @@ pub enum Expression {
     },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Eq,
+    Lt,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operator::Add => write!(f, "+"),
+            Operator::Sub => write!(f, "-"),
+            Operator::Mul => write!(f, "*"),
+            Operator::Eq => write!(f, "=="),
+            Operator::Lt => write!(f, "<"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Bind {
     pub variable: Variable,
@@ -84,17 +129,86 @@ pub struct Bind {
 }
 
 impl Expression {
-    pub fn get_span(self) -> Span {
+    pub fn get_span(&self) -> Span {
+        match self {
+            Expression::Variable(Variable { span, .. }) => span.clone(),
+            Expression::Abstraction { span, .. } => span.clone(),
+            Expression::Application { span, .. } => span.clone(),
+            Expression::Let { span, .. } => span.clone(),
+            Expression::Letrec { span, .. } => span.clone(),
+            Expression::Binary { span, .. } => span.clone(),
+            Expression::If { span, .. } => span.clone(),
+            Expression::Int { span, .. } => span.clone(),
+            Expression::Bool { span, .. } => span.clone(),
+            Expression::Unit { span } => span.clone(),
+        }
+    }
+    /// The span of the binder of the variable at `offset`, or of the binder
+    /// itself when `offset` falls on one.
+    pub fn definition(&self, offset: usize) -> Option<Span> {
+        self.resolve(offset, &mut Vec::new())
+    }
+    fn resolve<'a>(&'a self, offset: usize, scope: &mut Vec<&'a Variable>) -> Option<Span> {
         match self {
-            Expression::Variable(Variable { span, .. }) => span,
-            Expression::Abstraction { span, .. } => span,
-            Expression::Application { span, .. } => span,
-            Expression::Let { span, .. } => span,
-            Expression::Letrec { span, .. } => span,
-            Expression::Addition { span, .. } => span,
-            Expression::Int { span, .. } => span,
-            Expression::Unit { span } => span,
+            Expression::Variable(variable) if variable.span.contains(&offset) => scope
+                .iter()
+                .rev()
+                .find(|binder| binder.name == variable.name)
+                .map(|binder| binder.span.clone()),
+            Expression::Abstraction {
+                variable,
+                expression,
+                ..
+            } => expression.resolve_under(&[variable], offset, scope),
+            Expression::Application {
+                callee: lhs,
+                arg: rhs,
+                ..
+            }
+            | Expression::Binary { lhs, rhs, .. } => lhs
+                .resolve(offset, scope)
+                .or_else(|| rhs.resolve(offset, scope)),
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => condition
+                .resolve(offset, scope)
+                .or_else(|| then_branch.resolve(offset, scope))
+                .or_else(|| else_branch.resolve(offset, scope)),
+            Expression::Let { bind, body, .. } => bind
+                .expression
+                .resolve(offset, scope)
+                .or_else(|| body.resolve_under(&[&bind.variable], offset, scope)),
+            Expression::Letrec { binds, body, .. } => {
+                // Every bind of the group is in scope of every other one.
+                let binders = binds.iter().map(|bind| &bind.variable).collect::<Vec<_>>();
+                binds
+                    .iter()
+                    .find_map(|bind| bind.expression.resolve_under(&binders, offset, scope))
+                    .or_else(|| body.resolve_under(&binders, offset, scope))
+            }
+            Expression::Variable(_)
+            | Expression::Int { .. }
+            | Expression::Bool { .. }
+            | Expression::Unit { .. } => None,
+        }
+    }
+    /// Resolves `offset` in the expression, in the scope of `binders`.
+    fn resolve_under<'a>(
+        &'a self,
+        binders: &[&'a Variable],
+        offset: usize,
+        scope: &mut Vec<&'a Variable>,
+    ) -> Option<Span> {
+        if let Some(binder) = binders.iter().find(|binder| binder.span.contains(&offset)) {
+            return Some(binder.span.clone());
         }
+        scope.extend(binders);
+        let definition = self.resolve(offset, scope);
+        scope.truncate(scope.len() - binders.len());
+        definition
     }
 }
 
@@ -0,0 +1,479 @@
+//! Elaboration of an inferred program into an explicitly typed System F
+//! term: every abstraction is annotated with the type of its parameter,
+//! every generalized binding abstracts over its quantified type variables
+//! and every use of a variable is applied to the types its scheme was
+//! instantiated with. Checking the term again with `systemf`, which infers
+//! nothing, verifies the inference.
+//!
+//! A `letrec` binding becomes a fixpoint. The bindings of a group of more
+//! than one are defined at once as the fixpoint of a Church-encoded tuple,
+//! each of them being a projection of it.
+//!
+//! A type variable no binding generalized is left unconstrained by the
+//! program, so it stands for `Unit`.
+
+use crate::ast::{Bind, Expression, Operator, Variable};
+use crate::inference::{Inference, Names, Naming, Scheme, Type};
+use std::fmt;
+use std::ops::Range;
+use std::slice::Iter;
+use systemf::ast as f;
+
+type Span = Range<usize>;
+
+/// A program in System F, along with the type its inferred scheme stands
+/// for there.
+pub struct Elaboration {
+    pub term: f::Expression,
+    pub typ: f::Type,
+}
+
+/// Why checking an elaborated program again failed, which means that
+/// inference is wrong.
+#[derive(Debug)]
+pub enum RecheckError {
+    Rejected(systemf::error::Error),
+    Disagreement {
+        inferred: f::Type,
+        rechecked: f::Type,
+    },
+}
+
+impl fmt::Display for RecheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecheckError::Rejected(e) => write!(
+                f,
+                "`systemf` rejects the elaborated program: {}",
+                e.diagnostic().message
+            ),
+            RecheckError::Disagreement {
+                inferred,
+                rechecked,
+            } => write!(
+                f,
+                "`systemf` checks the elaborated program as `{}` rather than `{}`",
+                rechecked, inferred
+            ),
+        }
+    }
+}
+
+impl Elaboration {
+    /// Checks the term with `systemf`, which must find the inferred type.
+    pub fn recheck(&self) -> Result<(), RecheckError> {
+        match systemf::typechecker::TypeChecker::new().infer(self.term.clone()) {
+            Ok(rechecked) if rechecked == self.typ => Ok(()),
+            Ok(rechecked) => Err(RecheckError::Disagreement {
+                inferred: self.typ.clone(),
+                rechecked,
+            }),
+            Err(e) => Err(RecheckError::Rejected(e)),
+        }
+    }
+}
+
+/// Elaborates `expr`, whose scheme `inference` inferred as `scheme` in an
+/// empty environment. `inference` must not have inferred anything else, its
+/// decisions being matched with the binders and variables of `expr` in the
+/// order they were made.
+pub fn elaborate(inference: &Inference, expr: &Expression, scheme: &Scheme) -> Elaboration {
+    let mut elaborator = Elaborator {
+        inference,
+        names: Names::new(Naming::Greek),
+        bound: scheme.variables.clone(),
+        parameters: inference.decisions.parameters.iter(),
+        instantiations: inference.decisions.instantiations.iter(),
+        generalizations: inference.decisions.generalizations.iter(),
+    };
+    let typ = elaborator.forall(&scheme.variables, &scheme.typ);
+    let term = elaborator.expression(expr);
+    let term = elaborator.generalized(&scheme.variables, term, expr.get_span());
+    Elaboration { term, typ }
+}
+
+struct Elaborator<'a> {
+    inference: &'a Inference,
+    /// Names the type variables, `α`, `β`, … as they are bound.
+    names: Names,
+    /// The type variables bound by the enclosing type abstractions.
+    bound: Vec<usize>,
+    parameters: Iter<'a, Type>,
+    instantiations: Iter<'a, Vec<Type>>,
+    generalizations: Iter<'a, Scheme>,
+}
+
+impl Elaborator<'_> {
+    fn typ(&mut self, t: &Type) -> f::Type {
+        match self.inference.substitute(t.clone()) {
+            Type::Function(param, ret) => f::Type::function(self.typ(&param), self.typ(&ret)),
+            Type::Variable(id) if self.bound.contains(&id) => {
+                f::Type::Variable(self.names.name(id))
+            }
+            Type::Variable(_) | Type::Unit => f::Type::Unit,
+            Type::Int => f::Type::Int,
+            Type::Bool => f::Type::Bool,
+        }
+    }
+    /// The type `∀variables. t`.
+    fn forall(&mut self, variables: &[usize], t: &Type) -> f::Type {
+        let names = variables
+            .iter()
+            .map(|id| self.names.name(*id))
+            .collect::<Vec<_>>();
+        let t = self.typ(t);
+        names
+            .into_iter()
+            .rev()
+            .fold(t, |t, name| f::Type::forall(name, t))
+    }
+    /// The term `Λvariables. term`.
+    fn generalized(
+        &mut self,
+        variables: &[usize],
+        term: f::Expression,
+        span: Span,
+    ) -> f::Expression {
+        let names = variables
+            .iter()
+            .map(|id| self.names.name(*id))
+            .collect::<Vec<_>>();
+        names
+            .into_iter()
+            .rev()
+            .fold(term, |term, name| f::Expression::TypeAbstraction {
+                variable: f::Variable {
+                    name,
+                    span: span.clone(),
+                },
+                expression: Box::new(term),
+                span: span.clone(),
+            })
+    }
+    fn expression(&mut self, expr: &Expression) -> f::Expression {
+        match expr {
+            Expression::Variable(variable) => {
+                let arguments = self
+                    .instantiations
+                    .next()
+                    .expect("a variable is instantiated");
+                let arguments = arguments.iter().map(|t| self.typ(t)).collect::<Vec<_>>();
+                let span = variable.span.clone();
+                type_applications(f::Expression::Variable(binder(variable)), arguments, span)
+            }
+            Expression::Abstraction {
+                variable,
+                expression,
+                span,
+            } => {
+                let param = self.parameters.next().expect("a parameter has a type");
+                let param = self.typ(param);
+                let expression = self.expression(expression);
+                abstraction(binder(variable), param, expression, span.clone())
+            }
+            Expression::Application { callee, arg, span } => f::Expression::Application {
+                callee: Box::new(self.expression(callee)),
+                arg: Box::new(self.expression(arg)),
+                span: span.clone(),
+            },
+            Expression::Binary {
+                operator,
+                lhs,
+                rhs,
+                span,
+            } => f::Expression::Binary {
+                operator: match operator {
+                    Operator::Add => f::Operator::Add,
+                    Operator::Sub => f::Operator::Sub,
+                    Operator::Mul => f::Operator::Mul,
+                    Operator::Eq => f::Operator::Eq,
+                    Operator::Lt => f::Operator::Lt,
+                },
+                lhs: Box::new(self.expression(lhs)),
+                rhs: Box::new(self.expression(rhs)),
+                span: span.clone(),
+            },
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                span,
+            } => f::Expression::If {
+                condition: Box::new(self.expression(condition)),
+                then_branch: Box::new(self.expression(then_branch)),
+                else_branch: Box::new(self.expression(else_branch)),
+                span: span.clone(),
+            },
+            Expression::Let { bind, body, span } => {
+                let scheme = self.generalizations.next().expect("a `let` is generalized");
+                let depth = self.bound.len();
+                self.bound.extend(&scheme.variables);
+                let definition = self.expression(&bind.expression);
+                self.bound.truncate(depth);
+                let definition = self.generalized(&scheme.variables, definition, span.clone());
+                f::Expression::Let {
+                    variable: binder(&bind.variable),
+                    definition: Box::new(definition),
+                    body: Box::new(self.expression(body)),
+                    span: span.clone(),
+                }
+            }
+            Expression::Letrec { binds, body, span } => {
+                let schemes = self
+                    .generalizations
+                    .by_ref()
+                    .take(binds.len())
+                    .collect::<Vec<_>>();
+                let definitions = match binds.as_slice() {
+                    [bind] => vec![self.fixpoint(bind, schemes[0], span.clone())],
+                    _ => self.group(binds, &schemes, span.clone()),
+                };
+                let body = self.expression(body);
+                definitions
+                    .into_iter()
+                    .rev()
+                    .fold(body, |body, (variable, definition)| f::Expression::Let {
+                        variable,
+                        definition: Box::new(definition),
+                        body: Box::new(body),
+                        span: span.clone(),
+                    })
+            }
+            Expression::Int { n, span } => f::Expression::Int {
+                n: *n,
+                span: span.clone(),
+            },
+            Expression::Bool { value, span } => f::Expression::Bool {
+                value: *value,
+                span: span.clone(),
+            },
+            Expression::Unit { span } => f::Expression::Unit { span: span.clone() },
+        }
+    }
+    /// The definition of the only binding of a `letrec`, as a fixpoint.
+    fn fixpoint(
+        &mut self,
+        bind: &Bind,
+        scheme: &Scheme,
+        span: Span,
+    ) -> (f::Variable, f::Expression) {
+        let depth = self.bound.len();
+        self.bound.extend(&scheme.variables);
+        let typ = self.typ(&scheme.typ);
+        let expression = self.expression(&bind.expression);
+        self.bound.truncate(depth);
+        let fixpoint = f::Expression::Fix {
+            expression: Box::new(abstraction(
+                binder(&bind.variable),
+                typ,
+                expression,
+                span.clone(),
+            )),
+            span: span.clone(),
+        };
+        let definition = self.generalized(&scheme.variables, fixpoint, span);
+        (binder(&bind.variable), definition)
+    }
+    /// The definitions of the bindings of a `letrec` group: the tuple of
+    /// them all as a fixpoint, generalized over every variable one of them
+    /// is, then each of them as a projection of it.
+    fn group(
+        &mut self,
+        binds: &[Bind],
+        schemes: &[&Scheme],
+        span: Span,
+    ) -> Vec<(f::Variable, f::Expression)> {
+        let mut variables = Vec::new();
+        for id in schemes.iter().flat_map(|scheme| &scheme.variables) {
+            if !variables.contains(id) {
+                variables.push(*id);
+            }
+        }
+        let depth = self.bound.len();
+        self.bound.extend(&variables);
+        let types = schemes
+            .iter()
+            .map(|scheme| self.typ(&scheme.typ))
+            .collect::<Vec<_>>();
+        // Within the group, the bindings are the components of the tuple
+        // being defined.
+        let tuple = f::Expression::Variable(generated(TUPLE, &span));
+        let components = binds
+            .iter()
+            .map(|bind| {
+                let expression = self.expression(&bind.expression);
+                binds
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .fold(expression, |expression, (i, other)| f::Expression::Let {
+                        variable: binder(&other.variable),
+                        definition: Box::new(projection(tuple.clone(), &types, i, &span)),
+                        body: Box::new(expression),
+                        span: span.clone(),
+                    })
+            })
+            .collect::<Vec<_>>();
+        self.bound.truncate(depth);
+
+        let result = f::Type::Variable(RESULT.to_string());
+        let continuation = types
+            .iter()
+            .rev()
+            .fold(result, |ret, t| f::Type::function(t.clone(), ret));
+        let construction = components.into_iter().fold(
+            f::Expression::Variable(generated(CONTINUATION, &span)),
+            |callee, component| f::Expression::Application {
+                callee: Box::new(callee),
+                arg: Box::new(component),
+                span: span.clone(),
+            },
+        );
+        let construction = f::Expression::TypeAbstraction {
+            variable: generated(RESULT, &span),
+            expression: Box::new(abstraction(
+                generated(CONTINUATION, &span),
+                continuation,
+                construction,
+                span.clone(),
+            )),
+            span: span.clone(),
+        };
+        let fixpoint = f::Expression::Fix {
+            expression: Box::new(abstraction(
+                generated(TUPLE, &span),
+                tuple_type(&types),
+                construction,
+                span.clone(),
+            )),
+            span: span.clone(),
+        };
+        let mut definitions = vec![(
+            generated(GROUP, &span),
+            self.generalized(&variables, fixpoint, span.clone()),
+        )];
+
+        for (i, (bind, scheme)) in binds.iter().zip(schemes).enumerate() {
+            // The variables of the group the binding is not generalized over
+            // do not occur in its type.
+            self.bound.extend(&scheme.variables);
+            let arguments = variables
+                .iter()
+                .map(|id| self.typ(&Type::Variable(*id)))
+                .collect::<Vec<_>>();
+            let types = schemes
+                .iter()
+                .map(|scheme| self.typ(&scheme.typ))
+                .collect::<Vec<_>>();
+            self.bound.truncate(depth);
+            let group = type_applications(
+                f::Expression::Variable(generated(GROUP, &span)),
+                arguments,
+                span.clone(),
+            );
+            let definition = projection(group, &types, i, &span);
+            definitions.push((
+                binder(&bind.variable),
+                self.generalized(&scheme.variables, definition, span.clone()),
+            ));
+        }
+        definitions
+    }
+}
+
+/// The names of the binders elaboration introduces, which `hm` programs
+/// cannot use since their variables are made of letters only.
+const GROUP: &str = "group'";
+const TUPLE: &str = "tuple'";
+const CONTINUATION: &str = "k'";
+const RESULT: &str = "R";
+
+/// The variable of `systemf` a variable of the program becomes, primed if
+/// its name is a keyword there.
+fn binder(variable: &Variable) -> f::Variable {
+    let name = if systemf::parser::KEYWORDS.contains(&variable.name.as_str()) {
+        format!("{}'", variable.name)
+    } else {
+        variable.name.clone()
+    };
+    f::Variable {
+        name,
+        span: variable.span.clone(),
+    }
+}
+
+fn generated(name: &str, span: &Span) -> f::Variable {
+    f::Variable {
+        name: name.to_string(),
+        span: span.clone(),
+    }
+}
+
+fn abstraction(
+    variable: f::Variable,
+    typ: f::Type,
+    expression: f::Expression,
+    span: Span,
+) -> f::Expression {
+    f::Expression::Abstraction {
+        annotation: f::Annotation {
+            typ,
+            span: variable.span.clone(),
+        },
+        variable,
+        expression: Box::new(expression),
+        span,
+    }
+}
+
+fn type_applications(
+    expression: f::Expression,
+    arguments: Vec<f::Type>,
+    span: Span,
+) -> f::Expression {
+    arguments.into_iter().fold(expression, |expression, typ| {
+        f::Expression::TypeApplication {
+            expression: Box::new(expression),
+            annotation: f::Annotation {
+                typ,
+                span: span.clone(),
+            },
+            span: span.clone(),
+        }
+    })
+}
+
+/// The Church encoding of the tuple of `types`, `∀R. (T1 -> … -> Tn -> R) -> R`.
+fn tuple_type(types: &[f::Type]) -> f::Type {
+    let result = f::Type::Variable(RESULT.to_string());
+    let continuation = types
+        .iter()
+        .rev()
+        .fold(result.clone(), |ret, t| f::Type::function(t.clone(), ret));
+    f::Type::forall(RESULT, f::Type::function(continuation, result))
+}
+
+/// The `i`th component of `tuple`, whose components have the types `types`.
+fn projection(tuple: f::Expression, types: &[f::Type], i: usize, span: &Span) -> f::Expression {
+    let name = |j: usize| format!("x{}", j + 1);
+    let selector = types.iter().enumerate().rev().fold(
+        f::Expression::Variable(generated(&name(i), span)),
+        |expression, (j, t)| {
+            abstraction(
+                generated(&name(j), span),
+                t.clone(),
+                expression,
+                span.clone(),
+            )
+        },
+    );
+    f::Expression::Application {
+        callee: Box::new(type_applications(
+            tuple,
+            vec![types[i].clone()],
+            span.clone(),
+        )),
+        arg: Box::new(selector),
+        span: span.clone(),
+    }
+}
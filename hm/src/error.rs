@@ -1,53 +1,107 @@
-use crate::inference::Type;
-use ariadne::{Color, Label, Report, ReportKind, Source};
+use crate::inference::{Names, Naming, Reason, Type};
+use diagnostic::Diagnostic;
+use std::io;
 use std::ops::Range;
-use yansi::Paint;
 
 type Span = Range<usize>;
 
 pub enum Error {
-    UnificationFailure { t1: Type, t2: Type },
-    InfiniteType { t1: Type, t2: Type },
-    UndefinedSymbol { name: String, span: Span },
+    UnificationFailure {
+        t1: Type,
+        t2: Type,
+        span: Span,
+        reason: Reason,
+    },
+    InfiniteType {
+        t1: Type,
+        t2: Type,
+        span: Span,
+        reason: Reason,
+    },
+    UndefinedSymbol {
+        name: String,
+        span: Span,
+    },
 }
 
 impl Error {
-    pub fn report(&self, filename: &str) {
-        let source = &std::fs::read_to_string(filename).unwrap();
+    pub fn report(&self, filename: &str, source: &str) {
+        self.diagnostic().report(filename, source)
+    }
 
-        let mut report = Report::build(ReportKind::Error, filename, 1);
+    /// Renders the report into `out` rather than the standard output.
+    pub fn write(&self, filename: &str, source: &str, out: impl io::Write) -> io::Result<()> {
+        self.diagnostic().write(filename, source, out)
+    }
 
+    pub fn diagnostic(&self) -> Diagnostic {
+        self.diagnostic_named(Naming::default())
+    }
+
+    /// The diagnostic, naming the type variables of both types it mentions
+    /// together.
+    pub fn diagnostic_named(&self, naming: Naming) -> Diagnostic {
+        let mut names = Names::new(naming);
         match &self {
-            Error::UnificationFailure { t1, t2 } => {
-                report = report
-                    .with_code("unification-failure")
-                    .with_message(format!(
-                        "Cannot unify `{}` with `{}`.",
-                        t1.to_string().cyan().bold(),
-                        t2.to_string().cyan().bold()
-                    ))
-            }
-            Error::InfiniteType { t1, t2 } => {
-                report = report.with_code("infinite-type").with_message(format!(
-                    "Infinite loop detected between `{}` and `{}`.",
-                    t1.to_string().cyan().bold(),
-                    t2.to_string().cyan().bold()
-                ))
+            Error::UnificationFailure {
+                t1,
+                t2,
+                span,
+                reason,
+            } => Self::with_reason(
+                Diagnostic::new(
+                    "unification-failure",
+                    format!("Cannot unify `{}` with `{}`.", names.typ(t1), names.typ(t2)),
+                ),
+                span,
+                reason,
+            ),
+            Error::InfiniteType {
+                t1,
+                t2,
+                span,
+                reason,
+            } => {
+                let (t1, t2) = (names.typ(t1), names.typ(t2));
+                Self::with_reason(
+                    Diagnostic::new(
+                        "infinite-type",
+                        format!("Infinite loop detected between `{}` and `{}`.", t1, t2),
+                    ),
+                    span,
+                    reason,
+                )
+                .with_note(format!("`{}` would have to contain itself.", t1))
             }
             Error::UndefinedSymbol { name, span } => {
-                report = report
-                    .with_code("undefined-symbol")
-                    .with_message(format!("Undefined symbol `{}`.", name.cyan().bold()))
-                    .with_label(
-                        Label::new((filename, span.start..span.end))
-                            .with_message(format!("`{}` is not defined.", name.cyan().bold()))
-                            .with_color(Color::Magenta),
-                    )
+                Diagnostic::new("undefined-symbol", format!("Undefined symbol `{}`.", name))
+                    .with_primary(span.clone(), format!("`{}` is not defined.", name))
+            }
+        }
+    }
+
+    fn with_reason(diagnostic: Diagnostic, span: &Span, reason: &Reason) -> Diagnostic {
+        match reason {
+            Reason::Application { callee } => diagnostic
+                .with_primary(span.clone(), "Argument of this application.")
+                .with_secondary(callee.clone(), "Function being applied."),
+            Reason::Operand { operator } => {
+                diagnostic.with_primary(span.clone(), format!("Operand of `{}`.", operator))
+            }
+            Reason::IfCondition => diagnostic.with_primary(span.clone(), "Condition of this `if`."),
+            Reason::IfBranches { then_branch } => diagnostic
+                .with_primary(span.clone(), "Type of the `else` branch.")
+                .with_secondary(
+                    then_branch.clone(),
+                    "Should match the type of the `then` branch.",
+                ),
+            Reason::LetrecBinding { name } => diagnostic.with_primary(
+                span.clone(),
+                format!("Definition of `{}` in this `letrec`.", name),
+            ),
+            Reason::Callee => {
+                diagnostic.with_primary(span.clone(), "Within the function being applied.")
             }
         }
-        report
-            .finish()
-            .print((filename, Source::from(source)))
-            .unwrap()
     }
 }
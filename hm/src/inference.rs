@@ -1,32 +1,39 @@
 use crate::ast::*;
 use crate::error::Error;
 use im::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+use std::str::FromStr;
 use yansi::Paint;
 
+type Span = Range<usize>;
+
 #[derive(Debug, Clone)]
 pub enum Type {
-    Function(Box<Type>, Box<Type>),
-    Variable(u16),
+    Function(Rc<Type>, Rc<Type>),
+    Variable(usize),
     Unit,
     Int,
+    Bool,
 }
 
-impl Type {
-    pub fn to_string(&self) -> String {
+/// Displays the variables by their internal ids, as the constraints and
+/// substitutions refer to them. See [`Names`] for readable names.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Type::Function(param1, ret1) => {
-                let param_str = param1.to_string();
-                let ret_str = ret1.to_string();
-                let param_parenthesized = if let Type::Function(..) = **param1 {
-                    format!("({})", param_str)
+                if let Type::Function(..) = **param1 {
+                    write!(f, "({}) -> {}", param1, ret1)
                 } else {
-                    param_str
-                };
-                format!("{} -> {}", param_parenthesized, ret_str)
+                    write!(f, "{} -> {}", param1, ret1)
+                }
             }
-            Type::Unit => "Unit".to_string(),
-            Type::Int => "Int".to_string(),
-            Type::Variable(id) => format!("t{}", id),
+            Type::Unit => write!(f, "Unit"),
+            Type::Int => write!(f, "Int"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Variable(id) => write!(f, "t{}", id),
         }
     }
 }
@@ -39,50 +46,426 @@ impl PartialEq for Type {
             }
             (Type::Unit, Type::Unit) => true,
             (Type::Int, Type::Int) => true,
+            (Type::Bool, Type::Bool) => true,
             (Type::Variable(id1), Type::Variable(id2)) => id1 == id2,
             _ => false,
         }
     }
 }
 
+impl Type {
+    /// Builds `param -> ret`.
+    pub fn function(param: Type, ret: Type) -> Type {
+        Type::Function(Rc::new(param), Rc::new(ret))
+    }
+    /// Type variables occurring in the type, in order of first appearance.
+    pub fn free_variables(&self) -> Vec<usize> {
+        let mut variables = Vec::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+    fn collect_variables(&self, variables: &mut Vec<usize>) {
+        match self {
+            Type::Function(param, ret) => {
+                param.collect_variables(variables);
+                ret.collect_variables(variables);
+            }
+            Type::Variable(id) if !variables.contains(id) => variables.push(*id),
+            _ => (),
+        }
+    }
+    fn replace(&self, mapping: &HashMap<usize, Type>) -> Type {
+        match self {
+            Type::Function(param, ret) => {
+                Type::function(param.replace(mapping), ret.replace(mapping))
+            }
+            Type::Variable(id) => mapping.get(id).cloned().unwrap_or(Type::Variable(*id)),
+            t => t.clone(),
+        }
+    }
+}
+
+/// A type quantified over some of its type variables, `∀t0 t1. t`.
+#[derive(Debug, Clone)]
+pub struct Scheme {
+    pub variables: Vec<usize>,
+    pub typ: Type,
+}
+
+impl Scheme {
+    pub fn monomorphic(typ: Type) -> Self {
+        Self {
+            variables: Vec::new(),
+            typ,
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Names::new(Naming::default()).scheme(self))
+    }
+}
+
+/// How type variables are named when printing types.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Naming {
+    /// `a`, `b`, … in order of appearance.
+    #[default]
+    Letters,
+    /// `α`, `β`, … in order of appearance.
+    Greek,
+    /// The internal ids, as in `t7 -> t12`.
+    Raw,
+}
+
+impl FromStr for Naming {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "letters" => Ok(Naming::Letters),
+            "greek" => Ok(Naming::Greek),
+            "raw" => Ok(Naming::Raw),
+            _ => Err(format!("unknown type variable naming `{}`", s)),
+        }
+    }
+}
+
+/// Names the type variables of the types it prints, the same variable
+/// getting the same name in all of them.
+pub struct Names {
+    naming: Naming,
+    /// The variables named so far, in order of appearance.
+    variables: Vec<usize>,
+}
+
+impl Names {
+    pub fn new(naming: Naming) -> Self {
+        Self {
+            naming,
+            variables: Vec::new(),
+        }
+    }
+    pub fn name(&mut self, id: usize) -> String {
+        // `λ` and `ο` would read as an abstraction and as an `o`.
+        const GREEK: [char; 22] = [
+            'α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'ι', 'κ', 'μ', 'ν', 'ξ', 'π', 'ρ', 'σ', 'τ',
+            'υ', 'φ', 'χ', 'ψ', 'ω',
+        ];
+
+        let index = match self.variables.iter().position(|variable| *variable == id) {
+            Some(index) => index,
+            None => {
+                self.variables.push(id);
+                self.variables.len() - 1
+            }
+        };
+        let (letter, round) = match self.naming {
+            Naming::Raw => return format!("t{}", id),
+            Naming::Letters => ((b'a' + (index % 26) as u8) as char, index / 26),
+            Naming::Greek => (GREEK[index % GREEK.len()], index / GREEK.len()),
+        };
+        if round == 0 {
+            letter.to_string()
+        } else {
+            format!("{}{}", letter, round)
+        }
+    }
+    pub fn typ(&mut self, t: &Type) -> String {
+        match t {
+            Type::Function(param, ret) => {
+                let param = match **param {
+                    Type::Function(..) => format!("({})", self.typ(param)),
+                    _ => self.typ(param),
+                };
+                format!("{} -> {}", param, self.typ(ret))
+            }
+            Type::Variable(id) => self.name(*id),
+            t => t.to_string(),
+        }
+    }
+    /// Prints the quantifier of a generalized scheme, naming its variables
+    /// in their order of appearance in the type.
+    pub fn scheme(&mut self, scheme: &Scheme) -> String {
+        let typ = self.typ(&scheme.typ);
+        if scheme.variables.is_empty() {
+            return typ;
+        }
+        let variables = scheme
+            .typ
+            .free_variables()
+            .into_iter()
+            .filter(|id| scheme.variables.contains(id))
+            .map(|id| self.name(id))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("∀{}. {}", variables, typ)
+    }
+}
+
+/// The syntactic construct a constraint originates from.
+#[derive(Debug, Clone)]
+pub enum Reason {
+    /// The argument of an application, whose callee spans `callee`.
+    Application { callee: Span },
+    /// An operand of an arithmetic or comparison operator.
+    Operand { operator: Operator },
+    /// The condition of an `if`.
+    IfCondition,
+    /// The `else` branch of an `if`, whose `then` branch spans `then_branch`.
+    IfBranches { then_branch: Span },
+    /// The expression bound to `name` in a `letrec`.
+    LetrecBinding { name: String },
+    /// A part of the callee of an application, which algorithm M expects to
+    /// be a function before inferring its type.
+    Callee,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reason::Application { .. } => write!(f, "argument of an application"),
+            Reason::Operand { operator } => write!(f, "operand of `{}`", operator),
+            Reason::IfCondition => write!(f, "condition of an `if`"),
+            Reason::IfBranches { .. } => write!(f, "`else` branch of an `if`"),
+            Reason::LetrecBinding { name } => write!(f, "definition of `{}` in a `letrec`", name),
+            Reason::Callee => write!(f, "function being applied"),
+        }
+    }
+}
+
+/// An equation between two types, remembering the subexpression it was
+/// generated for.
 #[derive(Clone)]
-pub struct Constraint(Type, Type);
+pub struct Constraint {
+    pub t1: Type,
+    pub t2: Type,
+    pub span: Span,
+    pub reason: Reason,
+}
 
+pub enum UnificationError {
+    Mismatch { t1: Type, t2: Type },
+    InfiniteType { t1: Type, t2: Type },
+}
+
+impl UnificationError {
+    /// The error for an equation required by `reason` at `span`.
+    pub fn at(self, span: Span, reason: Reason) -> Error {
+        match self {
+            UnificationError::Mismatch { t1, t2 } => Error::UnificationFailure {
+                t1,
+                t2,
+                span,
+                reason,
+            },
+            UnificationError::InfiniteType { t1, t2 } => Error::InfiniteType {
+                t1,
+                t2,
+                span,
+                reason,
+            },
+        }
+    }
+}
+
+/// The unification of a constraint when solving them again, see
+/// [`Inference::replay`].
+pub struct Step<'a> {
+    pub constraint: &'a Constraint,
+    /// The variables the unification substituted, with the types they stand
+    /// for once the constraints so far are solved, or why it failed.
+    pub result: Result<Vec<(usize, Type)>, UnificationError>,
+}
+
+/// What inference decided about the binders and variables of the programs it
+/// inferred, in the order it met them, for [`crate::elaboration`] to make
+/// explicit.
+#[derive(Debug, Clone, Default)]
+pub struct Decisions {
+    /// The type of the parameter of every abstraction.
+    pub parameters: Vec<Type>,
+    /// The types the quantified variables of the scheme of every variable
+    /// were instantiated with, in the order of the quantifier.
+    pub instantiations: Vec<Vec<Type>>,
+    /// The scheme of every `let` binding and of every binding of a `letrec`.
+    /// A binding gets its place when met, so that it comes before the ones
+    /// nested in its definition, and its scheme once generalized.
+    pub generalizations: Vec<Scheme>,
+}
+
+/// What a type variable stands for, the nodes of the union-find the
+/// variables are unified in.
+#[derive(Debug, Clone)]
+enum Binding {
+    /// Not unified with any other type yet. The level is the number of `let`
+    /// bindings the variable was introduced under, lowered when the variable
+    /// is unified with one introduced under fewer of them.
+    Unbound { level: usize },
+    /// Unified with a type, possibly another variable.
+    Link(Type),
+}
+
+#[derive(Default, Clone)]
 pub struct Inference {
-    pub constraints: HashMap<u16, Constraint>,
-    pub substitutions: HashMap<u16, Type>,
+    /// Every constraint generated so far, in the order they were generated,
+    /// which is the order they are solved in.
+    pub constraints: Vec<Constraint>,
+    /// The number of constraints already solved.
+    solved: usize,
+    bindings: Vec<Binding>,
+    /// The number of `let` bindings around the expression being inferred.
+    level: usize,
+    /// The type of every subexpression and binder inferred so far, before
+    /// substitution.
+    pub types: Vec<(Span, Type)>,
+    /// What was decided about the binders and variables met so far.
+    pub decisions: Decisions,
 }
 
 impl Inference {
     pub fn new() -> Self {
-        Self {
-            constraints: HashMap::new(),
-            substitutions: HashMap::new(),
-        }
+        Self::default()
     }
     pub fn debug(&self) {
         println!("Constraints:");
-        for (id, constraint) in &self.constraints {
+        for (id, constraint) in self.constraints.iter().enumerate() {
             println!(
                 "{}: {} = {}",
                 id.bold(),
-                constraint.0.to_string().bold().cyan(),
-                constraint.1.to_string().bold().cyan()
+                constraint.t1.to_string().bold().cyan(),
+                constraint.t2.to_string().bold().cyan()
             );
         }
 
         println!("\nSubstitutions:");
-        for (id, substitution) in &self.substitutions {
+        for (id, binding) in self.bindings.iter().enumerate() {
+            let substitution = match binding {
+                Binding::Link(t) => t.clone(),
+                Binding::Unbound { .. } => Type::Variable(id),
+            };
             println!("{}: {}", id.bold(), substitution.to_string().bold().cyan());
         }
     }
+    /// Solves the constraints solved so far again from the start, one
+    /// unification at a time, stopping after the one that failed if any.
+    pub fn replay(&self) -> impl Iterator<Item = Step<'_>> {
+        let mut solver = Inference {
+            bindings: vec![Binding::Unbound { level: 0 }; self.bindings.len()],
+            ..Inference::new()
+        };
+        let mut failed = false;
+        self.constraints[..self.solved]
+            .iter()
+            .map_while(move |constraint| {
+                if failed {
+                    return None;
+                }
+                // Only the variables the constraint still mentions once
+                // substituted can be substituted by unifying it.
+                let mut unbound = solver.substitute(constraint.t1.clone()).free_variables();
+                for id in solver.substitute(constraint.t2.clone()).free_variables() {
+                    if !unbound.contains(&id) {
+                        unbound.push(id);
+                    }
+                }
+                let result = solver
+                    .unify(constraint.t1.clone(), constraint.t2.clone())
+                    .map(|()| {
+                        unbound
+                            .into_iter()
+                            .filter(|id| matches!(solver.bindings[*id], Binding::Link(_)))
+                            .map(|id| (id, solver.substitute(Type::Variable(id))))
+                            .collect()
+                    });
+                failed = result.is_err();
+                Some(Step { constraint, result })
+            })
+    }
     fn fresh_type_variable(&mut self) -> Type {
-        let tyvar = Type::Variable(self.substitutions.len() as u16);
-        self.substitutions
-            .insert(self.substitutions.len() as u16, tyvar.clone());
-        tyvar
+        self.bindings.push(Binding::Unbound { level: self.level });
+        Type::Variable(self.bindings.len() - 1)
     }
-    pub fn infer(&mut self, expr: Expression, env: HashMap<String, Type>) -> Result<Type, Error> {
+    /// Replaces the quantified variables of `scheme` with fresh type variables.
+    pub fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        self.instantiation(scheme).0
+    }
+    /// The instance of `scheme` by fresh type variables, along with these
+    /// variables in the order of the quantifier.
+    fn instantiation(&mut self, scheme: &Scheme) -> (Type, Vec<Type>) {
+        let arguments = scheme
+            .variables
+            .iter()
+            .map(|_| self.fresh_type_variable())
+            .collect::<Vec<_>>();
+        let mapping = scheme
+            .variables
+            .iter()
+            .copied()
+            .zip(arguments.iter().cloned())
+            .collect::<HashMap<_, _>>();
+        (scheme.typ.replace(&mapping), arguments)
+    }
+    /// Quantifies `t` over the variables introduced deeper than the current
+    /// level, which cannot occur in the environment.
+    ///
+    /// Constraints must have been solved beforehand, otherwise variables that
+    /// are still awaiting a substitution would be wrongly generalized.
+    fn generalize(&self, t: Type) -> Scheme {
+        let typ = self.substitute(t);
+        Scheme {
+            variables: typ
+                .free_variables()
+                .into_iter()
+                .filter(|id| {
+                    matches!(self.bindings[*id], Binding::Unbound { level } if level > self.level)
+                })
+                .collect(),
+            typ,
+        }
+    }
+    /// Runs `infer` one level deeper, so that the variables it introduces can
+    /// be generalized once it returns.
+    fn deeper<T>(&mut self, infer: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        self.level += 1;
+        let result = infer(self);
+        self.level -= 1;
+        result
+    }
+    /// Infers the type scheme of a whole program in `env`.
+    pub fn infer_scheme(
+        &mut self,
+        expr: Expression,
+        env: HashMap<String, Scheme>,
+    ) -> Result<Scheme, Error> {
+        let t = self.deeper(|inference| {
+            let t = inference.infer(expr, env)?;
+            inference.solve_constraints()?;
+            Ok(t)
+        })?;
+        Ok(self.generalize(t))
+    }
+    /// The type of the innermost subexpression or binder spanning `offset`,
+    /// as far as the constraints solved so far determine it.
+    pub fn type_at(&self, offset: usize) -> Option<Type> {
+        self.types
+            .iter()
+            .filter(|(span, _)| span.contains(&offset))
+            .min_by_key(|(span, _)| span.len())
+            .map(|(_, t)| self.substitute(t.clone()))
+    }
+    pub fn infer(&mut self, expr: Expression, env: HashMap<String, Scheme>) -> Result<Type, Error> {
+        let span = expr.get_span();
+        let t = self.infer_expression(expr, env)?;
+        self.types.push((span, t.clone()));
+        Ok(t)
+    }
+    fn infer_expression(
+        &mut self,
+        expr: Expression,
+        env: HashMap<String, Scheme>,
+    ) -> Result<Type, Error> {
         match expr {
             Expression::Abstraction {
                 variable,
@@ -90,118 +473,238 @@ impl Inference {
                 ..
             } => {
                 let t1 = self.fresh_type_variable();
+                self.types.push((variable.span.clone(), t1.clone()));
+                self.decisions.parameters.push(t1.clone());
                 let mut env = env.clone();
-                env.insert(variable.name, t1.clone());
+                env.insert(variable.name, Scheme::monomorphic(t1.clone()));
                 let t2 = self.infer(*expression, env)?;
-                Ok(Type::Function(Box::new(t1), Box::new(t2)))
+                Ok(Type::function(t1, t2))
             }
             Expression::Application { callee, arg, .. } => {
+                let reason = Reason::Application {
+                    callee: callee.get_span(),
+                };
+                let span = arg.get_span();
                 let t1 = self.infer(*callee, env.clone())?;
                 let t2 = self.infer(*arg, env)?;
                 let ret = self.fresh_type_variable();
-                self.constraints.insert(
-                    self.constraints.len() as u16,
-                    Constraint(t1, Type::Function(Box::new(t2), Box::new(ret.clone()))),
-                );
+                self.constrain(t1, Type::function(t2, ret.clone()), span, reason);
                 Ok(ret)
             }
-            Expression::Variable(Variable { name, span }) => env
-                .get(&name)
-                .ok_or(Error::UndefinedSymbol { name, span })
-                .cloned(),
-            Expression::Addition { lhs, rhs, .. } => {
+            Expression::Variable(Variable { name, span }) => match env.get(&name) {
+                Some(scheme) => {
+                    let (t, arguments) = self.instantiation(scheme);
+                    self.decisions.instantiations.push(arguments);
+                    Ok(t)
+                }
+                None => Err(Error::UndefinedSymbol { name, span }),
+            },
+            Expression::Binary {
+                operator, lhs, rhs, ..
+            } => {
+                let (lhs_span, rhs_span) = (lhs.get_span(), rhs.get_span());
                 let t1 = self.infer(*lhs, env.clone())?;
                 let t2 = self.infer(*rhs, env)?;
-                self.constraints
-                    .insert(self.constraints.len() as u16, Constraint(t1, Type::Int));
-                self.constraints
-                    .insert(self.constraints.len() as u16, Constraint(t2, Type::Int));
-                Ok(Type::Int)
+                self.constrain(t1, Type::Int, lhs_span, Reason::Operand { operator });
+                self.constrain(t2, Type::Int, rhs_span, Reason::Operand { operator });
+                match operator {
+                    Operator::Eq | Operator::Lt => Ok(Type::Bool),
+                    Operator::Add | Operator::Sub | Operator::Mul => Ok(Type::Int),
+                }
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let condition_span = condition.get_span();
+                let then_span = then_branch.get_span();
+                let else_span = else_branch.get_span();
+                let t1 = self.infer(*condition, env.clone())?;
+                let t2 = self.infer(*then_branch, env.clone())?;
+                let t3 = self.infer(*else_branch, env)?;
+                self.constrain(t1, Type::Bool, condition_span, Reason::IfCondition);
+                self.constrain(
+                    t2.clone(),
+                    t3,
+                    else_span,
+                    Reason::IfBranches {
+                        then_branch: then_span,
+                    },
+                );
+                Ok(t2)
             }
             Expression::Let { bind, body, .. } => {
-                let t = self.infer(bind.expression.clone(), env.clone())?;
+                let Bind {
+                    variable,
+                    expression,
+                } = *bind;
+                let place = self.decisions.generalizations.len();
+                self.decisions
+                    .generalizations
+                    .push(Scheme::monomorphic(Type::Unit));
+                let t = self.deeper(|inference| {
+                    let t = inference.infer(expression, env.clone())?;
+                    inference.solve_constraints()?;
+                    Ok(t)
+                })?;
+                self.types.push((variable.span, t.clone()));
+                let scheme = self.generalize(t);
+                self.decisions.generalizations[place] = scheme.clone();
                 let mut env = env.clone();
-                env.insert(bind.variable.name, t);
+                env.insert(variable.name, scheme);
                 self.infer(*body, env)
             }
             Expression::Letrec { binds, body, .. } => {
-                let mut env = env.clone();
-                for bind in binds.iter() {
-                    let new_tyvar = self.fresh_type_variable();
-                    env.insert(bind.variable.name.clone(), new_tyvar);
+                // The binds are monomorphic within the group and only
+                // generalized once every one of them has been inferred.
+                let place = self.decisions.generalizations.len();
+                for _ in &binds {
+                    self.decisions
+                        .generalizations
+                        .push(Scheme::monomorphic(Type::Unit));
                 }
-                for bind in binds.iter() {
-                    let tyvar = env.get(&bind.variable.name).unwrap();
-                    let t = self.infer(bind.expression.clone(), env.clone())?;
-                    self.constraints.insert(
-                        self.constraints.len() as u16,
-                        Constraint(tyvar.clone(), t.clone()),
-                    );
+                let tyvars = self.deeper(|inference| {
+                    let mut group_env = env.clone();
+                    let tyvars = binds
+                        .iter()
+                        .map(|bind| {
+                            let tyvar = inference.fresh_type_variable();
+                            inference
+                                .types
+                                .push((bind.variable.span.clone(), tyvar.clone()));
+                            group_env.insert(
+                                bind.variable.name.clone(),
+                                Scheme::monomorphic(tyvar.clone()),
+                            );
+                            tyvar
+                        })
+                        .collect::<Vec<_>>();
+                    for (bind, tyvar) in binds.iter().zip(tyvars.iter()) {
+                        let t = inference.infer(bind.expression.clone(), group_env.clone())?;
+                        inference.constrain(
+                            tyvar.clone(),
+                            t,
+                            bind.expression.get_span(),
+                            Reason::LetrecBinding {
+                                name: bind.variable.name.clone(),
+                            },
+                        );
+                    }
+                    inference.solve_constraints()?;
+                    Ok(tyvars)
+                })?;
+                let mut body_env = env.clone();
+                for (i, (bind, tyvar)) in binds.into_iter().zip(tyvars).enumerate() {
+                    let scheme = self.generalize(tyvar);
+                    self.decisions.generalizations[place + i] = scheme.clone();
+                    body_env.insert(bind.variable.name, scheme);
                 }
-                self.infer(*body, env)
+                self.infer(*body, body_env)
             }
             Expression::Int { .. } => Ok(Type::Int),
+            Expression::Bool { .. } => Ok(Type::Bool),
             Expression::Unit { .. } => Ok(Type::Unit),
         }
     }
+    fn constrain(&mut self, t1: Type, t2: Type, span: Span, reason: Reason) {
+        self.constraints.push(Constraint {
+            t1,
+            t2,
+            span,
+            reason,
+        });
+    }
+    /// Solves the constraints generated since the last call, in the order
+    /// they were generated.
     pub fn solve_constraints(&mut self) -> Result<(), Error> {
-        let constraints = self.constraints.clone();
-        self.constraints.resetting();
-        for (_, constraint) in constraints {
-            self.unify(constraint.0, constraint.1)?;
+        while self.solved < self.constraints.len() {
+            let constraint = self.constraints[self.solved].clone();
+            self.solved += 1;
+            self.unify(constraint.t1, constraint.t2)
+                .map_err(|e| e.at(constraint.span, constraint.reason))?;
         }
         Ok(())
     }
-    pub fn unify(&mut self, t1: Type, t2: Type) -> Result<(), Error> {
-        match (t1, t2) {
-            (t1 @ Type::Variable(id), t2) if *self.substitutions.get(&id).unwrap() != t1 => {
-                self.unify(self.substitutions.get(&id).unwrap().clone(), t2)
-            }
-            (t1, t2 @ Type::Variable(id)) if *self.substitutions.get(&id).unwrap() != t2 => {
-                self.unify(t1, self.substitutions.get(&id).unwrap().clone())
-            }
+    pub fn unify(&mut self, t1: Type, t2: Type) -> Result<(), UnificationError> {
+        match (self.resolve(t1), self.resolve(t2)) {
+            (Type::Variable(id1), Type::Variable(id2)) if id1 == id2 => Ok(()),
             (t1 @ Type::Variable(id), t2) => {
-                if Self::occurs_in(id, t2.clone()) {
-                    return Err(Error::InfiniteType { t1, t2 });
+                if self.occurs_in(id, &t2) {
+                    Err(UnificationError::InfiniteType { t1, t2 })
                 } else {
-                    self.substitutions.insert(id, t2);
+                    self.bindings[id] = Binding::Link(t2);
                     Ok(())
                 }
             }
             (t1, t2 @ Type::Variable(id)) => {
-                if Self::occurs_in(id, t1.clone()) {
-                    return Err(Error::InfiniteType { t1, t2 });
+                if self.occurs_in(id, &t1) {
+                    Err(UnificationError::InfiniteType { t1, t2 })
                 } else {
-                    self.substitutions.insert(id, t1);
+                    self.bindings[id] = Binding::Link(t1);
                     Ok(())
                 }
             }
             (Type::Function(param1, ret1), Type::Function(param2, ret2)) => {
-                self.unify(*param1, *param2)?;
-                self.unify(*ret1, *ret2)
+                self.unify((*param1).clone(), (*param2).clone())?;
+                self.unify((*ret1).clone(), (*ret2).clone())
             }
             (Type::Int, Type::Int) => Ok(()),
+            (Type::Bool, Type::Bool) => Ok(()),
             (Type::Unit, Type::Unit) => Ok(()),
-            (t1, t2) => Err(Error::UnificationFailure { t1, t2 }),
+            (t1, t2) => Err(UnificationError::Mismatch { t1, t2 }),
         }
     }
-    fn occurs_in(index: u16, t: Type) -> bool {
-        match t {
+    /// Follows the links from `t` to the type it was unified with, linking
+    /// every variable along the way directly to it.
+    fn resolve(&mut self, t: Type) -> Type {
+        let mut path = Vec::new();
+        let mut t = t;
+        while let Type::Variable(id) = t {
+            match &self.bindings[id] {
+                Binding::Link(next) => {
+                    path.push(id);
+                    t = next.clone();
+                }
+                Binding::Unbound { .. } => break,
+            }
+        }
+        for id in path {
+            self.bindings[id] = Binding::Link(t.clone());
+        }
+        t
+    }
+    /// Whether the variable `index` occurs in `t`. The variables of `t` are
+    /// lowered to the level of `index` along the way, since `t` is about to
+    /// be reachable from wherever `index` is.
+    fn occurs_in(&mut self, index: usize, t: &Type) -> bool {
+        let Binding::Unbound { level } = self.bindings[index] else {
+            unreachable!("`t{}` is unified already", index)
+        };
+        match self.resolve(t.clone()) {
             Type::Function(param, ret) => {
-                Self::occurs_in(index, *param) || Self::occurs_in(index, *ret)
+                self.occurs_in(index, &param) || self.occurs_in(index, &ret)
+            }
+            Type::Variable(id) if id == index => true,
+            Type::Variable(id) => {
+                if let Binding::Unbound { level: other } = &mut self.bindings[id] {
+                    *other = (*other).min(level);
+                }
+                false
             }
-            Type::Variable(id) => id == index,
             _ => false,
         }
     }
     pub fn substitute(&self, t: Type) -> Type {
         match t {
-            t @ Type::Variable(id) if *self.substitutions.get(&id).unwrap() != t => {
-                self.substitute(self.substitutions.get(&id).unwrap().clone())
-            }
-            Type::Function(param, ret) => Type::Function(
-                Box::new(self.substitute(*param)),
-                Box::new(self.substitute(*ret)),
+            Type::Variable(id) => match &self.bindings[id] {
+                Binding::Link(t) => self.substitute(t.clone()),
+                Binding::Unbound { .. } => Type::Variable(id),
+            },
+            Type::Function(param, ret) => Type::function(
+                self.substitute((*param).clone()),
+                self.substitute((*ret).clone()),
             ),
             _ => t,
         }
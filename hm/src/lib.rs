@@ -0,0 +1,10 @@
+#![feature(trait_alias)]
+pub mod ast;
+pub mod elaboration;
+pub mod error;
+pub mod inference;
+pub mod m;
+pub mod parser;
+pub mod strategy;
+pub mod substitution;
+pub mod w;
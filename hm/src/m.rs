@@ -0,0 +1,219 @@
+//! Algorithm M: types are inferred top-down, every subexpression being
+//! checked against the type its context expects of it. A type error is met
+//! as soon as a subexpression cannot have the expected type, which is often
+//! deeper in the program than where W or the constraints would meet it.
+
+use crate::ast::{Bind, Expression, Operator, Variable};
+use crate::error::Error;
+use crate::inference::{Reason, Scheme, Type};
+use crate::strategy::Strategy;
+use crate::substitution::{generalize, unify, Substitution};
+use im::HashMap;
+use std::ops::Range;
+
+type Span = Range<usize>;
+
+#[derive(Default)]
+pub struct AlgorithmM {
+    /// The number of type variables created so far.
+    variables: usize,
+}
+
+impl AlgorithmM {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn fresh_type_variable(&mut self) -> Type {
+        self.variables += 1;
+        Type::Variable(self.variables - 1)
+    }
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        scheme
+            .variables
+            .iter()
+            .map(|id| (*id, self.fresh_type_variable()))
+            .collect::<Substitution>()
+            .apply(&scheme.typ)
+    }
+    /// The substitution under which `expr` has the type `expected`.
+    ///
+    /// `reason` is why the context expects that type of `expr`. It is `None`
+    /// when the context expects nothing of it but a type variable of its own,
+    /// which cannot fail to unify.
+    fn check(
+        &mut self,
+        env: &HashMap<String, Scheme>,
+        expr: Expression,
+        expected: &Type,
+        reason: Option<Reason>,
+    ) -> Result<Substitution, Error> {
+        match expr {
+            Expression::Variable(Variable { name, span }) => match env.get(&name) {
+                Some(scheme) => {
+                    let t = self.instantiate(scheme);
+                    expect(&t, expected, span, reason)
+                }
+                None => Err(Error::UndefinedSymbol { name, span }),
+            },
+            Expression::Abstraction {
+                variable,
+                expression,
+                span,
+            } => {
+                let t1 = self.fresh_type_variable();
+                let t2 = self.fresh_type_variable();
+                let s1 = expect(
+                    &Type::function(t1.clone(), t2.clone()),
+                    expected,
+                    span,
+                    reason.clone(),
+                )?;
+                let env = s1
+                    .apply_env(env)
+                    .update(variable.name, Scheme::monomorphic(s1.apply(&t1)));
+                let s2 = self.check(&env, *expression, &s1.apply(&t2), reason)?;
+                Ok(s2.compose(&s1))
+            }
+            Expression::Application { callee, arg, .. } => {
+                let reason = Reason::Application {
+                    callee: callee.get_span(),
+                };
+                let t = self.fresh_type_variable();
+                let s1 = self.check(
+                    env,
+                    *callee,
+                    &Type::function(t.clone(), expected.clone()),
+                    Some(Reason::Callee),
+                )?;
+                let s2 = self.check(&s1.apply_env(env), *arg, &s1.apply(&t), Some(reason))?;
+                Ok(s2.compose(&s1))
+            }
+            Expression::Binary {
+                operator,
+                lhs,
+                rhs,
+                span,
+            } => {
+                let t = match operator {
+                    Operator::Eq | Operator::Lt => Type::Bool,
+                    Operator::Add | Operator::Sub | Operator::Mul => Type::Int,
+                };
+                let s1 = expect(&t, expected, span, reason)?;
+                let env = s1.apply_env(env);
+                let s2 = self.check(&env, *lhs, &Type::Int, Some(Reason::Operand { operator }))?;
+                let s = s2.compose(&s1);
+                let s3 = self.check(
+                    &s.apply_env(&env),
+                    *rhs,
+                    &Type::Int,
+                    Some(Reason::Operand { operator }),
+                )?;
+                Ok(s3.compose(&s))
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let then_span = then_branch.get_span();
+                let s1 = self.check(env, *condition, &Type::Bool, Some(Reason::IfCondition))?;
+                let s2 = self.check(
+                    &s1.apply_env(env),
+                    *then_branch,
+                    &s1.apply(expected),
+                    reason,
+                )?;
+                let s = s2.compose(&s1);
+                let s3 = self.check(
+                    &s.apply_env(env),
+                    *else_branch,
+                    &s.apply(expected),
+                    Some(Reason::IfBranches {
+                        then_branch: then_span,
+                    }),
+                )?;
+                Ok(s3.compose(&s))
+            }
+            Expression::Let { bind, body, .. } => {
+                let Bind {
+                    variable,
+                    expression,
+                } = *bind;
+                let t = self.fresh_type_variable();
+                let s1 = self.check(env, expression, &t, None)?;
+                let env = s1.apply_env(env);
+                let scheme = generalize(&env, s1.apply(&t));
+                let s2 = self.check(
+                    &env.update(variable.name, scheme),
+                    *body,
+                    &s1.apply(expected),
+                    reason,
+                )?;
+                Ok(s2.compose(&s1))
+            }
+            Expression::Letrec { binds, body, .. } => {
+                let tyvars = binds
+                    .iter()
+                    .map(|_| self.fresh_type_variable())
+                    .collect::<Vec<_>>();
+                let mut group_env = env.clone();
+                for (bind, tyvar) in binds.iter().zip(tyvars.iter()) {
+                    group_env.insert(
+                        bind.variable.name.clone(),
+                        Scheme::monomorphic(tyvar.clone()),
+                    );
+                }
+                let mut s = Substitution::new();
+                for (bind, tyvar) in binds.iter().zip(tyvars.iter()) {
+                    let reason = Reason::LetrecBinding {
+                        name: bind.variable.name.clone(),
+                    };
+                    let s1 = self.check(
+                        &s.apply_env(&group_env),
+                        bind.expression.clone(),
+                        &s.apply(tyvar),
+                        Some(reason),
+                    )?;
+                    s = s1.compose(&s);
+                }
+                let env = s.apply_env(env);
+                let mut body_env = env.clone();
+                for (bind, tyvar) in binds.into_iter().zip(tyvars) {
+                    body_env.insert(bind.variable.name, generalize(&env, s.apply(&tyvar)));
+                }
+                let s1 = self.check(&body_env, *body, &s.apply(expected), reason)?;
+                Ok(s1.compose(&s))
+            }
+            Expression::Int { span, .. } => expect(&Type::Int, expected, span, reason),
+            Expression::Bool { span, .. } => expect(&Type::Bool, expected, span, reason),
+            Expression::Unit { span } => expect(&Type::Unit, expected, span, reason),
+        }
+    }
+}
+
+/// Unifies the type `t` of the expression at `span` with the type its
+/// context expects of it.
+fn expect(
+    t: &Type,
+    expected: &Type,
+    span: Span,
+    reason: Option<Reason>,
+) -> Result<Substitution, Error> {
+    unify(t, expected).map_err(|e| match reason {
+        Some(reason) => e.at(span, reason),
+        None => unreachable!("a fresh type variable unifies with any type"),
+    })
+}
+
+impl Strategy for AlgorithmM {
+    fn infer_scheme(
+        &mut self,
+        expr: Expression,
+        env: HashMap<String, Scheme>,
+    ) -> Result<Scheme, Error> {
+        let t = self.fresh_type_variable();
+        let s = self.check(&env, expr, &t, None)?;
+        Ok(generalize(&s.apply_env(&env), s.apply(&t)))
+    }
+}
@@ -1,16 +1,89 @@
 use crate::ast::*;
+use chumsky::error::SimpleReason;
 use chumsky::prelude::*;
+use std::ops::Range;
 
 pub trait HMParser = Parser<char, Expression, Error = Simple<char>>;
 
+/// Parses `source`, recovering from syntax errors so that every one of them
+/// is reported rather than only the first.
+pub fn parse(source: &str) -> Result<Expression, Vec<Simple<char>>> {
+    match parser().parse_recovery(source) {
+        (Some(ast), errors) if errors.is_empty() => Ok(ast),
+        (_, errors) => Err(errors),
+    }
+}
+
+/// Words reserved by the syntax, which cannot be used as variables.
+pub const KEYWORDS: &[&str] = &["let", "letrec", "in", "if", "then", "else", "true", "false"];
+
+/// Whether `name` can be bound as a variable.
+pub fn is_variable(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase()) && !KEYWORDS.contains(&name)
+}
+
+/// Builds `lhs operator rhs`, spanning both operands.
+fn binary(lhs: Expression, (operator, rhs): (Operator, Expression)) -> Expression {
+    Expression::Binary {
+        span: lhs.get_span().start..rhs.get_span().end,
+        operator,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    }
+}
+
+/// Reports the end of the input within parentheses, `span` starting at the
+/// opening one, as this parenthesis being left unclosed.
+fn unclosed(error: Simple<char>, span: Range<usize>) -> Simple<char> {
+    let within = span.start < error.span().start;
+    if !within
+        || error.found().is_some()
+        || matches!(
+            error.reason(),
+            SimpleReason::Unclosed { .. } | SimpleReason::Custom(_)
+        )
+    {
+        return error;
+    }
+    Simple::unclosed_delimiter(span.start..span.start + 1, '(', error.span(), ')', None)
+        .merge(error)
+}
+
+/// Stands for every alternative of an expression failing at its start, as
+/// only some of them announce the token they start with, with the label
+/// `an expression` rather than a partial list of tokens.
+fn expected_expression(error: Simple<char>, span: Range<usize>) -> Simple<char> {
+    let unexpected = *error.reason() == SimpleReason::Unexpected
+        || diagnostic::missing_keyword(&error).is_some();
+    if error.span().start != span.start || !unexpected {
+        return error;
+    }
+    Simple::expected_input_found(error.span(), [], error.found().copied())
+        .with_label("an expression")
+}
+
 pub fn parser() -> impl HMParser {
+    let keyword = |k| {
+        text::keyword(k)
+            .map_err_with_span(move |_, span| diagnostic::expected_keyword(span, k))
+            .padded()
+    };
+
     let variable = filter(|l: &char| l.is_ascii_lowercase())
         .repeated()
         .at_least(1)
-        .map_with_span(|x, span| Variable {
-            name: x.iter().collect::<String>(),
-            span,
+        .collect::<String>()
+        .try_map(|name, span| {
+            if KEYWORDS.contains(&name.as_str()) {
+                Err(Simple::custom(
+                    span,
+                    format!("`{}` is a keyword, not a variable", name),
+                ))
+            } else {
+                Ok(Variable { name, span })
+            }
         })
+        .labelled("a variable")
         .padded();
 
     let expr = recursive(|expr| {
@@ -18,12 +91,29 @@ pub fn parser() -> impl HMParser {
             // Unit
             just("()").map_with_span(|_, span| Expression::Unit { span }),
             // Int
-            text::int(10).map_with_span(|_, span| Expression::Int { span }),
+            text::int(10).try_map(|n: String, span: Range<usize>| match n.parse::<i32>() {
+                Ok(n) => Ok(Expression::Int { n, span }),
+                Err(_) => Err(Simple::custom(span, "integer literal out of range")),
+            }),
+            // Bool
+            text::keyword("true")
+                .to(true)
+                .or(text::keyword("false").to(false))
+                .map_with_span(|value, span| Expression::Bool { value, span }),
             // Variable
-            variable.map(|var| Expression::Variable(var)),
+            variable.map(Expression::Variable),
             // Priority
-            expr.clone().delimited_by(just('('), just(')')),
+            expr.clone()
+                .delimited_by(just('('), just(')'))
+                .map_err_with_span(unclosed),
         ))
+        // A parenthesized expression that does not parse is skipped up to its
+        // closing parenthesis. The unit standing for it is never inferred
+        // since the parse failed.
+        .map_err_with_span(expected_expression)
+        .recover_with(nested_delimiters('(', ')', [], |span| Expression::Unit {
+            span,
+        }))
         .padded();
 
         let bind = variable
@@ -36,20 +126,78 @@ pub fn parser() -> impl HMParser {
             })
             .padded();
 
-        let op = choice((
-            // Sum
+        let application =
             atom.clone()
-                .then_ignore(just('+').padded())
+                .then(atom.clone().repeated())
+                .foldl(|e1, e2| Expression::Application {
+                    callee: Box::new(e1.clone()),
+                    arg: Box::new(e2.clone()),
+                    span: {
+                        let first_span = e1.get_span();
+                        let second_span = e2.get_span();
+
+                        first_span.start..second_span.end
+                    },
+                });
+
+        let product = application
+            .clone()
+            .then(
+                just('*')
+                    .padded()
+                    .to(Operator::Mul)
+                    .then(application)
+                    .repeated(),
+            )
+            .foldl(binary);
+
+        let sum = product
+            .clone()
+            .then(
+                choice((
+                    just('+').padded().to(Operator::Add),
+                    just('-').padded().to(Operator::Sub),
+                ))
+                .then(product)
+                .repeated(),
+            )
+            .foldl(binary);
+
+        let comparison = sum
+            .clone()
+            .then(
+                choice((
+                    just("==").padded().to(Operator::Eq),
+                    just('<').padded().to(Operator::Lt),
+                ))
+                .then(sum)
+                .or_not(),
+            )
+            .map(|(lhs, rhs)| match rhs {
+                Some(rhs) => binary(lhs, rhs),
+                None => lhs,
+            });
+
+        choice((
+            // If
+            keyword("if")
+                .ignore_then(expr.clone())
+                .then_ignore(keyword("then"))
                 .then(expr.clone())
-                .map_with_span(|(lhs, rhs), span| Expression::Addition {
-                    lhs: Box::new(lhs),
-                    rhs: Box::new(rhs),
-                    span,
-                }),
+                .then_ignore(keyword("else"))
+                .then(expr.clone())
+                .map_with_span(
+                    |((condition, then_branch), else_branch), span| Expression::If {
+                        condition: Box::new(condition),
+                        then_branch: Box::new(then_branch),
+                        else_branch: Box::new(else_branch),
+                        span,
+                    },
+                ),
             // Let
-            just("let")
+            keyword("let")
                 .ignore_then(bind.clone())
-                .then_ignore(just("in"))
+                .then_ignore(keyword("in"))
                 .then(expr.clone())
                 .map_with_span(|(bind, body), span| Expression::Let {
                     bind: Box::new(bind),
@@ -57,9 +205,9 @@ pub fn parser() -> impl HMParser {
                     span,
                 }),
             // Letrec
-            just("letrec")
+            keyword("letrec")
                 .ignore_then(bind.clone().repeated())
-                .then_ignore(just("in"))
+                .then_ignore(keyword("in"))
                 .then(expr.clone())
                 .map_with_span(|(binds, body), span| Expression::Letrec {
                     binds,
@@ -77,23 +225,11 @@ pub fn parser() -> impl HMParser {
                     expression: Box::new(expression),
                     span,
                 }),
-            // App
-            atom.clone()
-                .then(atom.clone().repeated())
-                .foldl(|e1, e2| Expression::Application {
-                    callee: Box::new(e1.clone()),
-                    arg: Box::new(e2.clone()),
-                    span: {
-                        let first_span = e1.get_span();
-                        let second_span = e2.get_span();
-
-                        first_span.start..second_span.end
-                    },
-                }),
+            // Operators and applications
+            comparison,
         ))
-        .padded();
-
-        op.or(atom)
+        .map_err_with_span(expected_expression)
+        .padded()
     })
     .then_ignore(end());
 
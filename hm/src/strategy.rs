@@ -0,0 +1,29 @@
+//! The interchangeable ways of inferring the principal type scheme of a
+//! program. They accept the same programs and infer the same schemes, up to
+//! the names of the type variables, but do not meet a type error at the same
+//! point and so may report it at different subexpressions.
+
+use crate::ast::Expression;
+use crate::error::Error;
+use crate::inference::{Inference, Scheme};
+use im::HashMap;
+
+pub trait Strategy {
+    /// Infers the type scheme of a whole program in `env`.
+    fn infer_scheme(
+        &mut self,
+        expr: Expression,
+        env: HashMap<String, Scheme>,
+    ) -> Result<Scheme, Error>;
+}
+
+/// Generates every constraint of a `let` binding before solving them.
+impl Strategy for Inference {
+    fn infer_scheme(
+        &mut self,
+        expr: Expression,
+        env: HashMap<String, Scheme>,
+    ) -> Result<Scheme, Error> {
+        Inference::infer_scheme(self, expr, env)
+    }
+}
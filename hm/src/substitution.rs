@@ -0,0 +1,107 @@
+//! Substitutions of types for type variables, as composed by the classic
+//! presentations of inference, algorithms W and M.
+
+use crate::inference::{Scheme, Type, UnificationError};
+use im::HashMap;
+
+/// A mapping from type variables to types. Substitutions are kept
+/// idempotent: no variable they replace occurs in the types they replace
+/// variables with, so applying one once is enough.
+#[derive(Debug, Clone, Default)]
+pub struct Substitution(HashMap<usize, Type>);
+
+impl Substitution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn apply(&self, t: &Type) -> Type {
+        match t {
+            Type::Variable(id) => self.0.get(id).cloned().unwrap_or(Type::Variable(*id)),
+            Type::Function(param, ret) => Type::function(self.apply(param), self.apply(ret)),
+            t => t.clone(),
+        }
+    }
+    /// Applies the substitution to the variables of `scheme` that are not
+    /// quantified.
+    pub fn apply_scheme(&self, scheme: &Scheme) -> Scheme {
+        let mut free = self.clone();
+        for id in &scheme.variables {
+            free.0.remove(id);
+        }
+        Scheme {
+            variables: scheme.variables.clone(),
+            typ: free.apply(&scheme.typ),
+        }
+    }
+    pub fn apply_env(&self, env: &HashMap<String, Scheme>) -> HashMap<String, Scheme> {
+        env.iter()
+            .map(|(name, scheme)| (name.clone(), self.apply_scheme(scheme)))
+            .collect()
+    }
+    /// The substitution applying `other`, then `self`.
+    pub fn compose(&self, other: &Substitution) -> Substitution {
+        let applied = other
+            .0
+            .iter()
+            .map(|(id, t)| (*id, self.apply(t)))
+            .collect::<HashMap<_, _>>();
+        Substitution(applied.union(self.0.clone()))
+    }
+}
+
+impl FromIterator<(usize, Type)> for Substitution {
+    fn from_iter<I: IntoIterator<Item = (usize, Type)>>(iter: I) -> Self {
+        Substitution(iter.into_iter().collect())
+    }
+}
+
+/// The most general substitution making `t1` and `t2` equal.
+pub fn unify(t1: &Type, t2: &Type) -> Result<Substitution, UnificationError> {
+    match (t1, t2) {
+        (Type::Variable(id1), Type::Variable(id2)) if id1 == id2 => Ok(Substitution::new()),
+        (Type::Variable(id), t) | (t, Type::Variable(id)) => {
+            if t.free_variables().contains(id) {
+                Err(UnificationError::InfiniteType {
+                    t1: t1.clone(),
+                    t2: t2.clone(),
+                })
+            } else {
+                Ok(Substitution(HashMap::unit(*id, t.clone())))
+            }
+        }
+        (Type::Function(param1, ret1), Type::Function(param2, ret2)) => {
+            let s1 = unify(param1, param2)?;
+            let s2 = unify(&s1.apply(ret1), &s1.apply(ret2))?;
+            Ok(s2.compose(&s1))
+        }
+        (Type::Int, Type::Int) | (Type::Bool, Type::Bool) | (Type::Unit, Type::Unit) => {
+            Ok(Substitution::new())
+        }
+        _ => Err(UnificationError::Mismatch {
+            t1: t1.clone(),
+            t2: t2.clone(),
+        }),
+    }
+}
+
+/// Quantifies `t` over the variables that are not free in `env`.
+pub fn generalize(env: &HashMap<String, Scheme>, t: Type) -> Scheme {
+    let env_variables = env
+        .values()
+        .flat_map(|scheme| {
+            scheme
+                .typ
+                .free_variables()
+                .into_iter()
+                .filter(|id| !scheme.variables.contains(id))
+        })
+        .collect::<Vec<_>>();
+    Scheme {
+        variables: t
+            .free_variables()
+            .into_iter()
+            .filter(|id| !env_variables.contains(id))
+            .collect(),
+        typ: t,
+    }
+}
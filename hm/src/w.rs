@@ -0,0 +1,167 @@
+//! Algorithm W: types are inferred bottom-up, each unification being solved
+//! as soon as it is met and its substitution composed with the ones before.
+
+use crate::ast::{Bind, Expression, Operator, Variable};
+use crate::error::Error;
+use crate::inference::{Reason, Scheme, Type};
+use crate::strategy::Strategy;
+use crate::substitution::{generalize, unify, Substitution};
+use im::HashMap;
+
+#[derive(Default)]
+pub struct AlgorithmW {
+    /// The number of type variables created so far.
+    variables: usize,
+}
+
+impl AlgorithmW {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn fresh_type_variable(&mut self) -> Type {
+        self.variables += 1;
+        Type::Variable(self.variables - 1)
+    }
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        scheme
+            .variables
+            .iter()
+            .map(|id| (*id, self.fresh_type_variable()))
+            .collect::<Substitution>()
+            .apply(&scheme.typ)
+    }
+    /// The type of `expr`, along with the substitution its subexpressions
+    /// required of the variables of `env`.
+    fn infer(
+        &mut self,
+        env: &HashMap<String, Scheme>,
+        expr: Expression,
+    ) -> Result<(Substitution, Type), Error> {
+        match expr {
+            Expression::Variable(Variable { name, span }) => match env.get(&name) {
+                Some(scheme) => Ok((Substitution::new(), self.instantiate(scheme))),
+                None => Err(Error::UndefinedSymbol { name, span }),
+            },
+            Expression::Abstraction {
+                variable,
+                expression,
+                ..
+            } => {
+                let t1 = self.fresh_type_variable();
+                let env = env.update(variable.name, Scheme::monomorphic(t1.clone()));
+                let (s, t2) = self.infer(&env, *expression)?;
+                Ok((s.clone(), Type::function(s.apply(&t1), t2)))
+            }
+            Expression::Application { callee, arg, .. } => {
+                let reason = Reason::Application {
+                    callee: callee.get_span(),
+                };
+                let span = arg.get_span();
+                let (s1, t1) = self.infer(env, *callee)?;
+                let (s2, t2) = self.infer(&s1.apply_env(env), *arg)?;
+                let ret = self.fresh_type_variable();
+                let s3 = unify(&s2.apply(&t1), &Type::function(t2, ret.clone()))
+                    .map_err(|e| e.at(span, reason))?;
+                Ok((s3.compose(&s2).compose(&s1), s3.apply(&ret)))
+            }
+            Expression::Binary {
+                operator, lhs, rhs, ..
+            } => {
+                let (lhs_span, rhs_span) = (lhs.get_span(), rhs.get_span());
+                let (s1, t1) = self.infer(env, *lhs)?;
+                let (s2, t2) = self.infer(&s1.apply_env(env), *rhs)?;
+                let s3 = unify(&s2.apply(&t1), &Type::Int)
+                    .map_err(|e| e.at(lhs_span, Reason::Operand { operator }))?;
+                let s4 = unify(&s3.apply(&t2), &Type::Int)
+                    .map_err(|e| e.at(rhs_span, Reason::Operand { operator }))?;
+                let t = match operator {
+                    Operator::Eq | Operator::Lt => Type::Bool,
+                    Operator::Add | Operator::Sub | Operator::Mul => Type::Int,
+                };
+                Ok((s4.compose(&s3).compose(&s2).compose(&s1), t))
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let condition_span = condition.get_span();
+                let then_span = then_branch.get_span();
+                let else_span = else_branch.get_span();
+                let (s1, t1) = self.infer(env, *condition)?;
+                let s2 = unify(&t1, &Type::Bool)
+                    .map_err(|e| e.at(condition_span, Reason::IfCondition))?;
+                let s = s2.compose(&s1);
+                let (s3, t2) = self.infer(&s.apply_env(env), *then_branch)?;
+                let s = s3.compose(&s);
+                let (s4, t3) = self.infer(&s.apply_env(env), *else_branch)?;
+                let s5 = unify(&s4.apply(&t2), &t3).map_err(|e| {
+                    e.at(
+                        else_span,
+                        Reason::IfBranches {
+                            then_branch: then_span,
+                        },
+                    )
+                })?;
+                Ok((s5.compose(&s4).compose(&s), s5.apply(&t3)))
+            }
+            Expression::Let { bind, body, .. } => {
+                let Bind {
+                    variable,
+                    expression,
+                } = *bind;
+                let (s1, t1) = self.infer(env, expression)?;
+                let env = s1.apply_env(env);
+                let scheme = generalize(&env, t1);
+                let (s2, t2) = self.infer(&env.update(variable.name, scheme), *body)?;
+                Ok((s2.compose(&s1), t2))
+            }
+            Expression::Letrec { binds, body, .. } => {
+                let tyvars = binds
+                    .iter()
+                    .map(|_| self.fresh_type_variable())
+                    .collect::<Vec<_>>();
+                let mut group_env = env.clone();
+                for (bind, tyvar) in binds.iter().zip(tyvars.iter()) {
+                    group_env.insert(
+                        bind.variable.name.clone(),
+                        Scheme::monomorphic(tyvar.clone()),
+                    );
+                }
+                let mut s = Substitution::new();
+                for (bind, tyvar) in binds.iter().zip(tyvars.iter()) {
+                    let reason = Reason::LetrecBinding {
+                        name: bind.variable.name.clone(),
+                    };
+                    let span = bind.expression.get_span();
+                    let (s1, t) = self.infer(&s.apply_env(&group_env), bind.expression.clone())?;
+                    s = s1.compose(&s);
+                    let s2 = unify(&s.apply(tyvar), &t).map_err(|e| e.at(span, reason))?;
+                    s = s2.compose(&s);
+                }
+                let env = s.apply_env(env);
+                let mut body_env = env.clone();
+                for (bind, tyvar) in binds.into_iter().zip(tyvars) {
+                    body_env.insert(bind.variable.name, generalize(&env, s.apply(&tyvar)));
+                }
+                let (s1, t) = self.infer(&body_env, *body)?;
+                Ok((s1.compose(&s), t))
+            }
+            Expression::Int { .. } => Ok((Substitution::new(), Type::Int)),
+            Expression::Bool { .. } => Ok((Substitution::new(), Type::Bool)),
+            Expression::Unit { .. } => Ok((Substitution::new(), Type::Unit)),
+        }
+    }
+}
+
+impl Strategy for AlgorithmW {
+    fn infer_scheme(
+        &mut self,
+        expr: Expression,
+        env: HashMap<String, Scheme>,
+    ) -> Result<Scheme, Error> {
+        let (s, t) = self.infer(&env, expr)?;
+        Ok(generalize(&s.apply_env(&env), t))
+    }
+}
@@ -0,0 +1,84 @@
+use hm::error::Error;
+use hm::inference::{Inference, Reason, Scheme, Type, UnificationError};
+use im::HashMap;
+
+fn infer(inference: &mut Inference, source: &str) -> Result<Scheme, Error> {
+    let Ok(ast) = hm::parser::parse(source) else {
+        panic!("`{}` does not parse", source);
+    };
+    inference.infer_scheme(ast, HashMap::new())
+}
+
+#[test]
+fn constraints_are_logged_in_the_order_they_are_generated() {
+    let mut inference = Inference::new();
+    let source = "if 1 < 2 then (\\x. x) 3 else 4";
+    assert!(infer(&mut inference, source).is_ok());
+    let provenance = inference
+        .constraints
+        .iter()
+        .map(|constraint| (constraint.reason.to_string(), constraint.span.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        provenance,
+        [
+            ("operand of `<`".to_string(), 3..4),
+            ("operand of `<`".to_string(), 7..8),
+            ("argument of an application".to_string(), 22..23),
+            ("condition of an `if`".to_string(), 3..8),
+            ("`else` branch of an `if`".to_string(), 29..30),
+        ]
+    );
+}
+
+#[test]
+fn replaying_substitutes_every_variable_as_solving_did() {
+    let mut inference = Inference::new();
+    let source = "letrec f = \\n. if n == 0 then 1 else n * f (n - 1); in \
+                  let twice = \\g. \\x. g (g x); in twice f";
+    assert!(infer(&mut inference, source).is_ok());
+    let steps = inference.replay().collect::<Vec<_>>();
+    assert_eq!(steps.len(), inference.constraints.len());
+    let mut substituted = std::collections::HashMap::new();
+    for step in steps {
+        let Ok(substitutions) = step.result else {
+            panic!("`{}` fails when replayed", step.constraint.t1);
+        };
+        for (id, t) in substitutions {
+            assert!(
+                substituted.insert(id, t).is_none(),
+                "`t{}` substituted twice",
+                id
+            );
+        }
+    }
+    for (id, t) in substituted {
+        assert_eq!(
+            inference.substitute(Type::Variable(id)),
+            inference.substitute(t)
+        );
+    }
+}
+
+#[test]
+fn replaying_stops_at_the_constraint_that_failed() {
+    let mut inference = Inference::new();
+    assert!(infer(
+        &mut inference,
+        "let f = \\x. x + 1; in if f 1 then 2 else 3"
+    )
+    .is_err());
+    let steps = inference.replay().collect::<Vec<_>>();
+    let last = steps.last().unwrap();
+    assert!(steps[..steps.len() - 1]
+        .iter()
+        .all(|step| step.result.is_ok()));
+    assert!(matches!(last.constraint.reason, Reason::IfCondition));
+    assert!(matches!(
+        &last.result,
+        Err(UnificationError::Mismatch {
+            t1: Type::Int,
+            t2: Type::Bool
+        })
+    ));
+}
@@ -0,0 +1,86 @@
+use hm::elaboration::{elaborate, Elaboration};
+use hm::inference::Inference;
+use im::HashMap;
+
+fn elaboration(source: &str) -> Elaboration {
+    let Ok(ast) = hm::parser::parse(source) else {
+        panic!("`{}` does not parse", source);
+    };
+    let mut inference = Inference::new();
+    let Ok(scheme) = inference.infer_scheme(ast.clone(), HashMap::new()) else {
+        panic!("`{}` is rejected", source);
+    };
+    elaborate(&inference, &ast, &scheme)
+}
+
+fn assert_rechecks(source: &str) {
+    let elaboration = elaboration(source);
+    if let Err(e) = elaboration.recheck() {
+        panic!("`{}` elaborates to `{}`: {}", source, elaboration.term, e);
+    }
+}
+
+#[test]
+fn let_bound_variables_are_type_abstractions_applied_at_each_use() {
+    let elaboration = elaboration("let id = \\x. x; in id id");
+    assert_eq!(
+        elaboration.term.to_string(),
+        "Λα. let id = Λβ. λx:β. x in id [α -> α] (id [α])"
+    );
+    assert_eq!(elaboration.typ.to_string(), "∀α. α -> α");
+}
+
+#[test]
+fn unconstrained_variables_are_instantiated_to_unit() {
+    let elaboration = elaboration("(\\x. 1) (\\y. y)");
+    assert_eq!(
+        elaboration.term.to_string(),
+        "(λx:Unit -> Unit. 1) (λy:Unit. y)"
+    );
+    assert!(elaboration.recheck().is_ok());
+}
+
+#[test]
+fn recursive_bindings_elaborate_to_fixpoints() {
+    let elaboration = elaboration("letrec f = \\n. if n == 0 then 1 else n * f (n - 1); in f");
+    assert_eq!(
+        elaboration.term.to_string(),
+        "let f = fix (λf:Int -> Int. λn:Int. if n == 0 then 1 else n * f (n - 1)) in f"
+    );
+    assert!(elaboration.recheck().is_ok());
+}
+
+#[test]
+fn elaborations_check_as_the_inferred_scheme() {
+    for source in [
+        "let k = \\x. \\y. x; in k (k 1) true",
+        "let compose = \\f. \\g. \\x. f (g x); in compose (\\b. if b then 1 else 0) (\\n. n < 2)",
+        "let twice = \\f. \\x. f (f x); in twice twice (\\n. n + 1) 0",
+        "letrec map = \\f. \\x. f x; in map (map (\\n. n == 1)) 2",
+        "letrec even = \\n. if n == 0 then true else odd (n - 1); \
+                odd = \\n. if n == 0 then false else even (n - 1); in even",
+        "letrec f = \\x. g x; g = \\y. f y; in f",
+        "letrec f = \\x. \\y. if true then x else g y x; g = \\a. \\b. f b a; in g",
+        "let f = \\x. let g = \\y. x; in g; in f 1 true",
+        "let fix = \\f. f; in fix fix",
+        "let forall = \\x. x; in forall 1",
+    ] {
+        assert_rechecks(source);
+    }
+}
+
+#[test]
+fn printed_elaborations_parse_back_to_the_same_type() {
+    let source = "letrec even = \\n. if n == 0 then true else odd (n - 1); \
+                  odd = \\n. if n == 0 then false else even (n - 1); in \
+                  let pick = \\b. \\x. \\y. if b then x else y; in pick (even 4) odd";
+    let elaboration = elaboration(source);
+    let printed = elaboration.term.to_string();
+    let Ok(term) = systemf::parser::parse(&printed) else {
+        panic!("`{}` does not parse", printed);
+    };
+    let Ok(typ) = systemf::typechecker::TypeChecker::new().infer(term) else {
+        panic!("`{}` is rejected", printed);
+    };
+    assert_eq!(typ, elaboration.typ);
+}
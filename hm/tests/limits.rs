@@ -0,0 +1,54 @@
+use hm::ast::{Bind, Expression, Variable};
+use hm::inference::{Inference, Type};
+use im::HashMap;
+
+fn variable(name: &str) -> Expression {
+    Expression::Variable(Variable {
+        name: name.to_string(),
+        span: 0..0,
+    })
+}
+
+/// `id` applied to itself in a balanced tree of the given depth, which takes
+/// a type variable and a constraint per application and a type variable per
+/// leaf.
+fn applications(depth: u32) -> Expression {
+    if depth == 0 {
+        return variable("id");
+    }
+    Expression::Application {
+        callee: Box::new(applications(depth - 1)),
+        arg: Box::new(applications(depth - 1)),
+        span: 0..0,
+    }
+}
+
+#[test]
+fn infers_programs_with_more_than_65535_type_variables() {
+    let program = Expression::Let {
+        bind: Box::new(Bind {
+            variable: Variable {
+                name: "id".to_string(),
+                span: 0..0,
+            },
+            expression: Expression::Abstraction {
+                variable: Variable {
+                    name: "x".to_string(),
+                    span: 0..0,
+                },
+                expression: Box::new(variable("x")),
+                span: 0..0,
+            },
+        }),
+        body: Box::new(applications(17)),
+        span: 0..0,
+    };
+    let mut inference = Inference::new();
+    let Ok(scheme) = inference.infer_scheme(program, HashMap::new()) else {
+        panic!("`id` applied to itself is rejected");
+    };
+    assert!(inference.constraints.len() > u16::MAX as usize);
+    assert_eq!(scheme.variables.len(), 1);
+    let a = Type::Variable(scheme.variables[0]);
+    assert_eq!(scheme.typ, Type::function(a.clone(), a));
+}
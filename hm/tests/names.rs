@@ -0,0 +1,45 @@
+use hm::inference::{Names, Naming, Scheme, Type};
+
+fn arrow(ids: &[usize]) -> Type {
+    let (last, params) = ids.split_last().unwrap();
+    params.iter().rev().fold(Type::Variable(*last), |ret, id| {
+        Type::function(Type::Variable(*id), ret)
+    })
+}
+
+#[test]
+fn variables_are_named_in_order_of_appearance() {
+    let scheme = Scheme {
+        variables: vec![7, 12],
+        typ: Type::function(arrow(&[12, 7]), arrow(&[7, 12])),
+    };
+    assert_eq!(scheme.to_string(), "∀a b. (a -> b) -> b -> a");
+    assert_eq!(
+        Names::new(Naming::Greek).scheme(&scheme),
+        "∀α β. (α -> β) -> β -> α"
+    );
+    assert_eq!(
+        Names::new(Naming::Raw).scheme(&scheme),
+        "∀t12 t7. (t12 -> t7) -> t7 -> t12"
+    );
+}
+
+#[test]
+fn names_are_shared_by_the_types_printed_together() {
+    let mut names = Names::new(Naming::Letters);
+    assert_eq!(names.typ(&arrow(&[3, 5])), "a -> b");
+    assert_eq!(names.typ(&arrow(&[5, 4])), "b -> c");
+}
+
+#[test]
+fn names_run_past_the_alphabet() {
+    let ids = (0..30).collect::<Vec<_>>();
+    let names = Names::new(Naming::Letters).typ(&arrow(&ids));
+    assert!(names.ends_with("z -> a1 -> b1 -> c1 -> d1"), "{}", names);
+    let names = Names::new(Naming::Greek).typ(&arrow(&ids));
+    assert!(
+        names.ends_with("ω -> α1 -> β1 -> γ1 -> δ1 -> ε1 -> ζ1 -> η1 -> θ1"),
+        "{}",
+        names
+    );
+}
@@ -0,0 +1,133 @@
+use hm::error::Error;
+use hm::inference::{Inference, Scheme, Type};
+use hm::m::AlgorithmM;
+use hm::strategy::Strategy;
+use hm::w::AlgorithmW;
+use im::HashMap;
+use std::ops::Range;
+
+fn strategies() -> [(&'static str, Box<dyn Strategy>); 3] {
+    [
+        ("constraints", Box::new(Inference::new())),
+        ("w", Box::new(AlgorithmW::new())),
+        ("m", Box::new(AlgorithmM::new())),
+    ]
+}
+
+fn infer(strategy: &mut dyn Strategy, source: &str) -> Result<Scheme, Error> {
+    let Ok(ast) = hm::parser::parse(source) else {
+        panic!("`{}` does not parse", source);
+    };
+    strategy.infer_scheme(ast, HashMap::new())
+}
+
+/// Whether `t1` and `t2` are the same up to a renaming of their variables,
+/// `mapping` renaming the ones of `t1` met so far.
+fn renaming(t1: &Type, t2: &Type, mapping: &mut HashMap<usize, usize>) -> bool {
+    match (t1, t2) {
+        (Type::Variable(id1), Type::Variable(id2)) => match mapping.get(id1) {
+            Some(id) => id == id2,
+            None if mapping.values().any(|id| id == id2) => false,
+            None => {
+                mapping.insert(*id1, *id2);
+                true
+            }
+        },
+        (Type::Function(param1, ret1), Type::Function(param2, ret2)) => {
+            renaming(param1, param2, mapping) && renaming(ret1, ret2, mapping)
+        }
+        (Type::Int, Type::Int) | (Type::Bool, Type::Bool) | (Type::Unit, Type::Unit) => true,
+        _ => false,
+    }
+}
+
+fn assert_agree(source: &str) {
+    let schemes = strategies().map(|(name, mut strategy)| match infer(&mut *strategy, source) {
+        Ok(scheme) => (name, scheme),
+        Err(e) => panic!(
+            "`{}` rejects `{}`: {}",
+            name,
+            source,
+            e.diagnostic().message
+        ),
+    });
+    let (_, expected) = schemes[0].clone();
+    for (name, scheme) in &schemes[1..] {
+        assert!(
+            renaming(&expected.typ, &scheme.typ, &mut HashMap::new())
+                && expected.variables.len() == scheme.variables.len(),
+            "`{}` infers `{}` for `{}`, but the constraints infer `{}`",
+            name,
+            scheme,
+            source,
+            expected
+        );
+    }
+}
+
+/// The span each strategy reports the error of `source` at.
+fn error_spans(source: &str) -> [Range<usize>; 3] {
+    strategies().map(|(name, mut strategy)| match infer(&mut *strategy, source) {
+        Ok(scheme) => panic!("`{}` accepts `{}` as `{}`", name, source, scheme),
+        Err(
+            Error::UnificationFailure { span, .. }
+            | Error::InfiniteType { span, .. }
+            | Error::UndefinedSymbol { span, .. },
+        ) => span,
+    })
+}
+
+#[test]
+fn strategies_agree_on_principal_types() {
+    for source in [
+        "\\x. x",
+        "\\f. \\g. \\x. f (g x)",
+        "\\f. \\x. f (f x)",
+        "let id = \\x. x; in id id",
+        "let id = \\x. x; in if id true then id 1 else 2",
+        "let k = \\x. \\y. x; in k (k 1) true",
+        "\\x. let y = x; in y",
+        "\\f. if f 1 then f else \\x. x == 1",
+        "letrec fact = \\n. if n == 0 then 1 else n * fact (n - 1); in fact",
+        "letrec f = \\x. g x; g = \\y. f y; in f",
+        "letrec even = \\n. if n == 0 then true else odd (n - 1); \
+         odd = \\n. if n == 0 then false else even (n - 1); in even",
+        "letrec loop = \\x. loop x; in let id = \\x. x; in loop id",
+    ] {
+        assert_agree(source);
+    }
+}
+
+#[test]
+fn strategies_reject_the_same_programs() {
+    for source in [
+        "1 2",
+        "\\x. x x",
+        "true + 1",
+        "if 1 then 2 else 3",
+        "if true then 1 else false",
+        "\\f. if f true then f 1 else 0",
+        "(\\f. if f true then f 1 else 0) (\\x. x)",
+        "letrec f = \\x. f; in f",
+        "let x = y; in x",
+    ] {
+        error_spans(source);
+    }
+}
+
+#[test]
+fn constraints_and_w_report_errors_where_the_types_meet() {
+    let [constraints, w, _] = error_spans("(if true then 1 else 2) 3");
+    assert_eq!(constraints, 24..25);
+    assert_eq!(w, 24..25);
+}
+
+#[test]
+fn m_reports_errors_where_the_expected_type_is_first_contradicted() {
+    let [_, _, m] = error_spans("(if true then 1 else 2) 3");
+    assert_eq!(m, 14..15);
+    let [constraints, w, m] = error_spans("1 + (if true then \\x. x else \\y. y)");
+    assert_eq!(constraints, 5..34);
+    assert_eq!(w, 5..34);
+    assert_eq!(m, 18..24);
+}
@@ -0,0 +1,327 @@
+use hm::inference::Naming;
+use std::path::Path;
+use std::str::FromStr;
+
+pub const USAGE: &str = "\
+Usage: tc [<command>] [options] [<file>]
+
+Commands:
+    check       Type check a program (default)
+    eval        Type check a program, then evaluate it
+    prove       Check the proofs of the theorems of a `stlc` proof script
+    repl        Start an interactive session, or run the commands piped to it
+    lsp         Start a language server on the standard input and output
+
+Options:
+    -s, --system <system>   Type system to use: `stlc`, `hm`, `systemf` or `coc`.
+                            Inferred from the file extension when omitted.
+        --stdin             Read the program from the standard input.
+        --trace             Print every reduction step when evaluating, or every
+                            unification when solving the constraints of `hm`.
+        --steps <n>         Give up evaluating after <n> reduction steps (10000
+                            by default), for programs that may not terminate.
+        --derivation <fmt>  Print the typing derivation of a `stlc` program as
+                            `text`, `bussproofs` or `mathpartir`.
+        --algorithm <name>  Inference algorithm of `hm`: `constraints` (default),
+                            `w` or `m`.
+        --type-variables <naming>
+                            Name the type variables of `hm` as `letters`
+                            (default), `greek` or by their internal ids with
+                            `raw`, as the constraints do.
+        --debug             Print the constraints and substitutions of `hm`.
+        --elaborate         Print a `hm` program as the explicitly typed System F
+                            term its inference elaborates to, checked again by
+                            `systemf`.
+        --format <fmt>      Print the result and the diagnostics as `text` or as
+                            `json`, one object per line.
+    -h, --help              Print this message.
+    -V, --version           Print the version.";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Check,
+    Eval,
+    Prove,
+    Repl,
+    Lsp,
+    Help,
+    Version,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum System {
+    Stlc,
+    Hm,
+    SystemF,
+    Coc,
+}
+
+impl FromStr for System {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stlc" => Ok(System::Stlc),
+            "hm" => Ok(System::Hm),
+            "systemf" => Ok(System::SystemF),
+            "coc" => Ok(System::Coc),
+            _ => Err(format!("unknown type system `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DerivationFormat {
+    Text,
+    Bussproofs,
+    Mathpartir,
+}
+
+impl FromStr for DerivationFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(DerivationFormat::Text),
+            "bussproofs" => Ok(DerivationFormat::Bussproofs),
+            "mathpartir" => Ok(DerivationFormat::Mathpartir),
+            _ => Err(format!("unknown derivation format `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Constraints,
+    W,
+    M,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "constraints" => Ok(Algorithm::Constraints),
+            "w" => Ok(Algorithm::W),
+            "m" => Ok(Algorithm::M),
+            _ => Err(format!("unknown inference algorithm `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown output format `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    File(String),
+    Stdin,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub command: Command,
+    pub system: System,
+    pub input: Input,
+    pub trace: bool,
+    pub steps: Option<usize>,
+    pub debug: bool,
+    pub elaborate: bool,
+    pub derivation: Option<DerivationFormat>,
+    pub algorithm: Option<Algorithm>,
+    pub naming: Option<Naming>,
+    pub format: Format,
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut args = args.into_iter().peekable();
+    let mut command = match args.peek().map(String::as_str) {
+        Some("check") => {
+            args.next();
+            Command::Check
+        }
+        Some("eval") => {
+            args.next();
+            Command::Eval
+        }
+        Some("prove") => {
+            args.next();
+            Command::Prove
+        }
+        Some("repl") => {
+            args.next();
+            Command::Repl
+        }
+        Some("lsp") => {
+            args.next();
+            Command::Lsp
+        }
+        _ => Command::Check,
+    };
+
+    let mut system = None;
+    let mut file = None;
+    let mut stdin = false;
+    let mut trace = false;
+    let mut steps = None;
+    let mut debug = false;
+    let mut elaborate = false;
+    let mut derivation = None;
+    let mut algorithm = None;
+    let mut naming = None;
+    let mut format = Format::Text;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-s" | "--system" => {
+                let name = args.next().ok_or(format!("missing value for `{}`", arg))?;
+                system = Some(name.parse()?);
+            }
+            "--derivation" => {
+                let format = args.next().ok_or(format!("missing value for `{}`", arg))?;
+                derivation = Some(format.parse()?);
+            }
+            "--algorithm" => {
+                let name = args.next().ok_or(format!("missing value for `{}`", arg))?;
+                algorithm = Some(name.parse()?);
+            }
+            "--type-variables" => {
+                let name = args.next().ok_or(format!("missing value for `{}`", arg))?;
+                naming = Some(name.parse()?);
+            }
+            "--format" => {
+                let name = args.next().ok_or(format!("missing value for `{}`", arg))?;
+                format = name.parse()?;
+            }
+            "--steps" => {
+                let n = args.next().ok_or(format!("missing value for `{}`", arg))?;
+                steps = Some(
+                    n.parse()
+                        .map_err(|_| format!("invalid number of steps `{}`", n))?,
+                );
+            }
+            "--stdin" => stdin = true,
+            "--trace" => trace = true,
+            "--debug" => debug = true,
+            "--elaborate" => elaborate = true,
+            "-h" | "--help" => command = Command::Help,
+            "-V" | "--version" => command = Command::Version,
+            arg if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ if file.is_some() => return Err(format!("unexpected argument `{}`", arg)),
+            _ => file = Some(arg),
+        }
+    }
+
+    if let (Command::Repl | Command::Lsp, true) = (command, file.is_some() || stdin) {
+        let name = if command == Command::Repl {
+            "repl"
+        } else {
+            "lsp"
+        };
+        return Err(format!("`{}` does not take an input", name));
+    }
+    if let Command::Help | Command::Version | Command::Repl | Command::Lsp = command {
+        return Ok(Options {
+            command,
+            system: system.unwrap_or(System::Stlc),
+            input: Input::Stdin,
+            trace,
+            steps,
+            debug,
+            elaborate,
+            derivation,
+            algorithm,
+            naming,
+            format,
+        });
+    }
+
+    let input = match (file, stdin) {
+        (Some(_), true) => return Err("cannot read both a file and the standard input".into()),
+        (Some(file), false) => Input::File(file),
+        (None, true) => Input::Stdin,
+        (None, false) => return Err("missing input file".into()),
+    };
+    let system = match (system, &input) {
+        (Some(system), _) => system,
+        (None, Input::File(file)) => Path::new(file)
+            .extension()
+            .and_then(|extension| extension.to_str()?.parse().ok())
+            .ok_or(format!(
+                "cannot infer the type system of `{}`, use `--system`",
+                file
+            ))?,
+        (None, Input::Stdin) => return Err("`--stdin` requires `--system`".into()),
+    };
+    if command == Command::Eval && system != System::Stlc {
+        return Err("evaluation is only supported by `stlc`".into());
+    }
+    if command == Command::Prove && system != System::Stlc {
+        return Err("proofs are only supported by `stlc`".into());
+    }
+    if command == Command::Prove && derivation.is_some() {
+        return Err("`prove` does not print derivations".into());
+    }
+    if steps.is_some() && command != Command::Eval {
+        return Err("`--steps` is only supported by `eval`".into());
+    }
+    if trace && command != Command::Eval && system != System::Hm {
+        return Err("`--trace` is only supported by `eval` and by `hm`".into());
+    }
+    if derivation.is_some() && system != System::Stlc {
+        return Err("derivations are only supported by `stlc`".into());
+    }
+    if algorithm.is_some() && system != System::Hm {
+        return Err("inference algorithms are only supported by `hm`".into());
+    }
+    if naming.is_some() && system != System::Hm {
+        return Err("type variables are only supported by `hm`".into());
+    }
+    if elaborate && system != System::Hm {
+        return Err("elaboration is only supported by `hm`".into());
+    }
+    if (debug || trace) && algorithm.is_some_and(|algorithm| algorithm != Algorithm::Constraints) {
+        return Err(
+            "`--debug` and `--trace` are only supported by `--algorithm constraints`".into(),
+        );
+    }
+    if elaborate && algorithm.is_some_and(|algorithm| algorithm != Algorithm::Constraints) {
+        return Err("`--elaborate` is only supported by `--algorithm constraints`".into());
+    }
+    if format == Format::Json && (trace || debug || elaborate || derivation.is_some()) {
+        return Err(
+            "`--format json` cannot be combined with `--trace`, `--debug`, `--elaborate` or \
+             `--derivation`"
+                .into(),
+        );
+    }
+
+    Ok(Options {
+        command,
+        system,
+        input,
+        trace,
+        steps,
+        debug,
+        elaborate,
+        derivation,
+        algorithm,
+        naming,
+        format,
+    })
+}
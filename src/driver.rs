@@ -0,0 +1,337 @@
+use crate::cli::{Algorithm, DerivationFormat, Format, Options};
+use chumsky::error::Simple;
+use diagnostic::Diagnostic;
+use im::HashMap;
+use serde_json::json;
+use yansi::Paint;
+
+/// Type checks a `stlc` program, returning whether it was accepted.
+pub fn check_stlc(filename: &str, source: &str, options: &Options) -> bool {
+    let Some(format) = options.derivation else {
+        return typecheck_stlc(filename, source, options.format)
+            .map(|(_, typ)| print_result(filename, options.format, &typ.to_string(), None))
+            .is_some();
+    };
+    let derivation = parse_stlc(filename, source, options.format).and_then(|ast| {
+        stlc::typechecker::TypeChecker::new()
+            .derive(ast)
+            .map_err(|e| e.report(filename, source))
+            .ok()
+    });
+    derivation
+        .map(|derivation| match format {
+            DerivationFormat::Text => print!("{}", derivation.to_text()),
+            DerivationFormat::Bussproofs => print!("{}", derivation.to_bussproofs()),
+            DerivationFormat::Mathpartir => print!("{}", derivation.to_mathpartir()),
+        })
+        .is_some()
+}
+
+/// Reduction steps taken before giving up on a program evaluated without
+/// `--steps`, so that a diverging one is reported rather than run forever.
+const DEFAULT_STEPS: usize = 10_000;
+
+/// Type checks then evaluates a `stlc` program with the small-step
+/// evaluator, which is the one that can be stopped, returning whether it was
+/// accepted and reached a value within the given number of `steps`, or
+/// [`DEFAULT_STEPS`].
+pub fn eval_stlc(filename: &str, source: &str, options: &Options) -> bool {
+    let Some((ast, typ)) = typecheck_stlc(filename, source, options.format) else {
+        return false;
+    };
+    let typ = typ.to_string();
+    if options.format == Format::Text {
+        println!("{}", typ.green().bold());
+    }
+    let steps = options.steps.unwrap_or(DEFAULT_STEPS);
+    let mut reduction = stlc::evaluator::Reduction::new(ast);
+    if options.trace {
+        println!("{} {}", " ".dim(), reduction.expression());
+    }
+    for _ in 0..steps {
+        if !reduction.step() {
+            break;
+        }
+        if options.trace {
+            println!("{} {}", "→".dim(), reduction.expression());
+        }
+    }
+    if reduction.is_value() {
+        let value = reduction.into_expression().to_string();
+        print_result(filename, options.format, &typ, Some(&value));
+        true
+    } else {
+        let message = format!("no value reached after {} steps", steps);
+        match options.format {
+            Format::Text => eprintln!("{} {}", "error:".red().bold(), message),
+            Format::Json => println!(
+                "{}",
+                json!({ "file": filename, "type": typ, "error": message })
+            ),
+        }
+        false
+    }
+}
+
+fn typecheck_stlc(
+    filename: &str,
+    source: &str,
+    format: Format,
+) -> Option<(stlc::ast::Expression, stlc::ast::Type)> {
+    let ast = parse_stlc(filename, source, format)?;
+    let mut tc = stlc::typechecker::TypeChecker::new();
+    match tc.infer(ast.clone()) {
+        Ok(typ) => Some((ast, typ)),
+        Err(e) => {
+            emit(filename, source, format, e.diagnostic());
+            None
+        }
+    }
+}
+
+fn parse_stlc(filename: &str, source: &str, format: Format) -> Option<stlc::ast::Expression> {
+    stlc::parser::parse(source)
+        .map_err(|parse_error| emit_parse_errors(filename, source, format, parse_error))
+        .ok()
+}
+
+/// Checks the theorems of a `stlc` proof script in order, saying whether the
+/// proof of each is accepted, and returns whether all of them were.
+pub fn prove_stlc(filename: &str, source: &str, options: &Options) -> bool {
+    let theorems = match stlc::parser::parse_theorems(source) {
+        Ok(theorems) => theorems,
+        Err(parse_error) => {
+            emit_parse_errors(filename, source, options.format, parse_error);
+            return false;
+        }
+    };
+    let mut tc = stlc::typechecker::TypeChecker::new();
+    for theorem in theorems {
+        let (name, statement) = (theorem.name.clone(), theorem.statement.clone());
+        match tc.prove(theorem) {
+            Ok(()) => match options.format {
+                Format::Text => println!(
+                    "{} : {}  {}",
+                    name.name.as_str().bold(),
+                    statement.as_proposition(),
+                    "proof accepted".green().bold()
+                ),
+                Format::Json => println!(
+                    "{}",
+                    json!({
+                        "file": filename,
+                        "name": name.name.as_str(),
+                        "statement": statement.as_proposition().to_string(),
+                        "status": "accepted"
+                    })
+                ),
+            },
+            Err(e) => {
+                emit(
+                    filename,
+                    source,
+                    options.format,
+                    e.proof_diagnostic(&name, &statement),
+                );
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Type checks a `systemf` program, returning whether it was accepted.
+pub fn check_systemf(filename: &str, source: &str, options: &Options) -> bool {
+    match systemf::parser::parse(source) {
+        Ok(ast) => match systemf::typechecker::TypeChecker::new().infer(ast) {
+            Ok(typ) => {
+                print_result(filename, options.format, &typ.to_string(), None);
+                true
+            }
+            Err(e) => {
+                emit(filename, source, options.format, e.diagnostic());
+                false
+            }
+        },
+        Err(parse_error) => {
+            emit_parse_errors(filename, source, options.format, parse_error);
+            false
+        }
+    }
+}
+
+/// Type checks the declarations of a `coc` program in order, printing the
+/// type of each, and returns whether all of them were accepted.
+pub fn check_coc(filename: &str, source: &str, options: &Options) -> bool {
+    let program = match coc::parser::parse(source) {
+        Ok(program) => program,
+        Err(parse_error) => {
+            emit_parse_errors(filename, source, options.format, parse_error);
+            return false;
+        }
+    };
+    let mut tc = coc::typechecker::TypeChecker::new();
+    for declaration in program {
+        let name = declaration.name().name.clone();
+        match tc.declare(declaration) {
+            Ok(typ) => match options.format {
+                Format::Text => println!("{} : {}", name.bold(), typ.to_string().green().bold()),
+                Format::Json => println!(
+                    "{}",
+                    json!({ "file": filename, "name": name, "type": typ.to_string() })
+                ),
+            },
+            Err(e) => {
+                emit(filename, source, options.format, e.diagnostic());
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Infers the type scheme of a `hm` program, returning whether it was
+/// accepted.
+pub fn check_hm(filename: &str, source: &str, options: &Options) -> bool {
+    use hm::inference::{Inference, Names};
+    use hm::m::AlgorithmM;
+    use hm::strategy::Strategy;
+    use hm::w::AlgorithmW;
+
+    match hm::parser::parse(source) {
+        Ok(ast) => {
+            let result = match options.algorithm.unwrap_or(Algorithm::Constraints) {
+                Algorithm::Constraints => {
+                    let mut inference = Inference::new();
+                    let result = inference.infer_scheme(ast.clone(), HashMap::new());
+                    if options.debug {
+                        inference.debug();
+                    }
+                    if options.trace {
+                        print_unifications(&inference, source);
+                    }
+                    if let (true, Ok(scheme)) = (options.elaborate, &result) {
+                        if !print_elaboration(&inference, &ast, scheme) {
+                            return false;
+                        }
+                    }
+                    result
+                }
+                Algorithm::W => AlgorithmW::new().infer_scheme(ast, HashMap::new()),
+                Algorithm::M => AlgorithmM::new().infer_scheme(ast, HashMap::new()),
+            };
+            let naming = options.naming.unwrap_or_default();
+            match result {
+                Ok(scheme) => {
+                    let scheme = Names::new(naming).scheme(&scheme);
+                    print_result(filename, options.format, &scheme, None);
+                    true
+                }
+                Err(e) => {
+                    emit(filename, source, options.format, e.diagnostic_named(naming));
+                    false
+                }
+            }
+        }
+        Err(parse_error) => {
+            emit_parse_errors(filename, source, options.format, parse_error);
+            false
+        }
+    }
+}
+
+/// Prints the System F term `ast` elaborates to once its scheme inferred,
+/// returning whether `systemf` checks it as that scheme.
+fn print_elaboration(
+    inference: &hm::inference::Inference,
+    ast: &hm::ast::Expression,
+    scheme: &hm::inference::Scheme,
+) -> bool {
+    let elaboration = hm::elaboration::elaborate(inference, ast, scheme);
+    println!("{}", elaboration.term);
+    match elaboration.recheck() {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red().bold(), e);
+            false
+        }
+    }
+}
+
+/// Prints every unification made solving the constraints of `inference`,
+/// along with the subexpression each constraint was generated for and the
+/// variables it substituted.
+fn print_unifications(inference: &hm::inference::Inference, source: &str) {
+    use hm::inference::Type;
+
+    for (id, step) in inference.replay().enumerate() {
+        let constraint = step.constraint;
+        println!(
+            "{} {} = {}",
+            format!("{}:", id).bold(),
+            constraint.t1.to_string().bold().cyan(),
+            constraint.t2.to_string().bold().cyan()
+        );
+        println!(
+            "   {} {}",
+            format!("{}:", constraint.reason).dim(),
+            excerpt(source, constraint.span.clone())
+        );
+        match step.result {
+            Ok(substitutions) => {
+                for (variable, t) in substitutions {
+                    println!(
+                        "   {} := {}",
+                        Type::Variable(variable),
+                        t.to_string().cyan()
+                    );
+                }
+            }
+            Err(_) => println!("   {}", "no unifier".red()),
+        }
+    }
+}
+
+/// The text of `span` in `source` on a single line, cut short when long.
+fn excerpt(source: &str, span: std::ops::Range<usize>) -> String {
+    const MAX_WIDTH: usize = 40;
+
+    let text = source
+        .chars()
+        .skip(span.start)
+        .take(span.len())
+        .collect::<String>();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > MAX_WIDTH {
+        format!("{}…", text.chars().take(MAX_WIDTH - 1).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// Prints the type of an accepted program, or its value once evaluated.
+fn print_result(filename: &str, format: Format, typ: &str, value: Option<&str>) {
+    match (format, value) {
+        (Format::Text, None) => println!("{}", typ.green().bold()),
+        (Format::Text, Some(value)) => println!("{}", value.bold()),
+        (Format::Json, None) => println!("{}", json!({ "file": filename, "type": typ })),
+        (Format::Json, Some(value)) => println!(
+            "{}",
+            json!({ "file": filename, "type": typ, "value": value })
+        ),
+    }
+}
+
+/// Prints a diagnostic as an ariadne report or as a line of JSON.
+fn emit(filename: &str, source: &str, format: Format, diagnostic: Diagnostic) {
+    match format {
+        Format::Text => diagnostic.report(filename, source),
+        Format::Json => println!("{}", diagnostic.to_json(filename, source)),
+    }
+}
+
+fn emit_parse_errors(filename: &str, source: &str, format: Format, errors: Vec<Simple<char>>) {
+    for e in errors {
+        emit(filename, source, format, e.into());
+    }
+}
@@ -0,0 +1,352 @@
+//! A language server for `stlc`, `hm`, `systemf` and `coc` programs, speaking
+//! the Language Server Protocol over the standard input and output.
+//!
+//! Documents are checked again on every change, the diagnostics being
+//! published to the client. Hovering a subexpression shows its type, and the
+//! definition of a variable is the binder it refers to.
+
+use crate::cli::System;
+use diagnostic::Diagnostic;
+use hm::inference::Naming;
+use im::HashMap;
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{GotoDefinition, HoverRequest, Request as RequestTrait};
+use lsp_types::{
+    DiagnosticRelatedInformation, DiagnosticSeverity, GotoDefinitionParams, GotoDefinitionResponse,
+    Hover, HoverContents, HoverParams, HoverProviderCapability, Location, MarkupContent,
+    MarkupKind, NumberOrString, OneOf, Position, PublishDiagnosticsParams, Range,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+};
+use std::error::Error;
+use std::path::Path;
+
+type Span = std::ops::Range<usize>;
+
+/// The open documents by URI.
+type Documents = std::collections::HashMap<String, Document>;
+
+/// An open document, checked with the type system named by its extension.
+struct Document {
+    system: System,
+    text: String,
+}
+
+/// Serves the client on the standard input and output until it exits.
+/// Documents whose type system cannot be told from their extension or
+/// language are checked with `default`.
+pub fn run(default: System) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut documents = Documents::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                let response = respond(&documents, request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some((uri, diagnostics)) = update(&mut documents, notification, default) {
+                    let params = PublishDiagnosticsParams {
+                        uri,
+                        diagnostics,
+                        version: None,
+                    };
+                    connection
+                        .sender
+                        .send(Message::Notification(Notification::new(
+                            PublishDiagnostics::METHOD.to_string(),
+                            params,
+                        )))?;
+                }
+            }
+            Message::Response(_) => (),
+        }
+    }
+    // The writer only stops once every sender is gone.
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+/// Applies a change to the open documents, returning the diagnostics to
+/// publish for the document it concerns.
+fn update(
+    documents: &mut Documents,
+    notification: Notification,
+    default: System,
+) -> Option<(Uri, Vec<lsp_types::Diagnostic>)> {
+    let uri = match notification.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params = notification
+                .extract::<lsp_types::DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)
+                .ok()?;
+            let document = params.text_document;
+            let system = system_of(&document.uri)
+                .or_else(|| document.language_id.parse().ok())
+                .unwrap_or(default);
+            documents.insert(
+                document.uri.to_string(),
+                Document {
+                    system,
+                    text: document.text,
+                },
+            );
+            document.uri
+        }
+        DidChangeTextDocument::METHOD => {
+            let params = notification
+                .extract::<lsp_types::DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD)
+                .ok()?;
+            let document = documents.get_mut(params.text_document.uri.as_str())?;
+            // Only full synchronization is advertised, so the last change is
+            // the whole text.
+            document.text = params.content_changes.into_iter().last()?.text;
+            params.text_document.uri
+        }
+        DidCloseTextDocument::METHOD => {
+            let params = notification
+                .extract::<lsp_types::DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)
+                .ok()?;
+            documents.remove(params.text_document.uri.as_str());
+            return Some((params.text_document.uri, Vec::new()));
+        }
+        _ => return None,
+    };
+    let document = &documents[uri.as_str()];
+    let diagnostics = check(document.system, &document.text)
+        .into_iter()
+        .map(|diagnostic| to_lsp(&uri, &document.text, diagnostic))
+        .collect();
+    Some((uri, diagnostics))
+}
+
+fn respond(documents: &Documents, request: Request) -> Response {
+    let id = request.id.clone();
+    let result = match request.method.as_str() {
+        HoverRequest::METHOD => request
+            .extract::<HoverParams>(HoverRequest::METHOD)
+            .ok()
+            .and_then(|(_, params)| {
+                let position = params.text_document_position_params;
+                let document = documents.get(position.text_document.uri.as_str())?;
+                let offset = offset(&document.text, position.position);
+                let typ = type_at(document.system, &document.text, offset)?;
+                Some(Hover {
+                    contents: HoverContents::Markup(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: format!("```\n{}\n```", typ),
+                    }),
+                    range: None,
+                })
+            })
+            .map(serde_json::to_value),
+        GotoDefinition::METHOD => request
+            .extract::<GotoDefinitionParams>(GotoDefinition::METHOD)
+            .ok()
+            .and_then(|(_, params)| {
+                let position = params.text_document_position_params;
+                let uri = position.text_document.uri;
+                let document = documents.get(uri.as_str())?;
+                let offset = offset(&document.text, position.position);
+                let span = definition(document.system, &document.text, offset)?;
+                Some(GotoDefinitionResponse::Scalar(Location {
+                    range: range(&document.text, span),
+                    uri,
+                }))
+            })
+            .map(serde_json::to_value),
+        method => {
+            return Response::new_err(
+                id,
+                lsp_server::ErrorCode::MethodNotFound as i32,
+                format!("unsupported request `{}`", method),
+            )
+        }
+    };
+    match result {
+        Some(Ok(value)) => Response::new_ok(id, value),
+        _ => Response::new_ok(id, serde_json::Value::Null),
+    }
+}
+
+fn system_of(uri: &Uri) -> Option<System> {
+    Path::new(uri.path().as_str())
+        .extension()?
+        .to_str()?
+        .parse()
+        .ok()
+}
+
+/// The parse errors of `source`, or the type error if it parses.
+fn check(system: System, source: &str) -> Vec<Diagnostic> {
+    match system {
+        System::Stlc => match stlc::parser::parse(source) {
+            Ok(ast) => stlc::typechecker::TypeChecker::new()
+                .infer(ast)
+                .err()
+                .map(|e| e.diagnostic())
+                .into_iter()
+                .collect(),
+            Err(errors) => errors.into_iter().map(Diagnostic::from).collect(),
+        },
+        System::Hm => match hm::parser::parse(source) {
+            Ok(ast) => {
+                let mut inference = hm::inference::Inference::new();
+                inference
+                    .infer_scheme(ast, HashMap::new())
+                    .err()
+                    .map(|e| e.diagnostic())
+                    .into_iter()
+                    .collect()
+            }
+            Err(errors) => errors.into_iter().map(Diagnostic::from).collect(),
+        },
+        System::SystemF => match systemf::parser::parse(source) {
+            Ok(ast) => systemf::typechecker::TypeChecker::new()
+                .infer(ast)
+                .err()
+                .map(|e| e.diagnostic())
+                .into_iter()
+                .collect(),
+            Err(errors) => errors.into_iter().map(Diagnostic::from).collect(),
+        },
+        System::Coc => match coc::parser::parse(source) {
+            Ok(program) => coc::typechecker::TypeChecker::new()
+                .check(program)
+                .err()
+                .map(|e| e.diagnostic())
+                .into_iter()
+                .collect(),
+            Err(errors) => errors.into_iter().map(Diagnostic::from).collect(),
+        },
+    }
+}
+
+/// The type of the innermost subexpression of `source` at `offset`, as far
+/// as the checker went before an error.
+fn type_at(system: System, source: &str, offset: usize) -> Option<String> {
+    match system {
+        System::Stlc => {
+            let ast = stlc::parser::parse(source).ok()?;
+            let mut tc = stlc::typechecker::TypeChecker::new();
+            let _ = tc.infer(ast);
+            tc.type_at(offset).map(ToString::to_string)
+        }
+        System::Hm => {
+            let ast = hm::parser::parse(source).ok()?;
+            let mut inference = hm::inference::Inference::new();
+            let _ = inference.infer_scheme(ast, HashMap::new());
+            let typ = inference.type_at(offset)?;
+            Some(hm::inference::Names::new(Naming::default()).typ(&typ))
+        }
+        System::SystemF => {
+            let ast = systemf::parser::parse(source).ok()?;
+            let mut tc = systemf::typechecker::TypeChecker::new();
+            let _ = tc.infer(ast);
+            tc.type_at(offset).map(ToString::to_string)
+        }
+        System::Coc => {
+            let program = coc::parser::parse(source).ok()?;
+            let mut tc = coc::typechecker::TypeChecker::new();
+            let _ = tc.check(program);
+            tc.type_at(offset).map(ToString::to_string)
+        }
+    }
+}
+
+fn definition(system: System, source: &str, offset: usize) -> Option<Span> {
+    match system {
+        System::Stlc => stlc::parser::parse(source).ok()?.definition(offset),
+        System::Hm => hm::parser::parse(source).ok()?.definition(offset),
+        System::SystemF => systemf::parser::parse(source).ok()?.definition(offset),
+        System::Coc => coc::ast::definition(&coc::parser::parse(source).ok()?, offset),
+    }
+}
+
+fn to_lsp(uri: &Uri, source: &str, diagnostic: Diagnostic) -> lsp_types::Diagnostic {
+    let primary = diagnostic.labels.iter().find(|label| label.primary);
+    let related = diagnostic
+        .labels
+        .iter()
+        .filter(|label| !label.primary)
+        .map(|label| DiagnosticRelatedInformation {
+            location: Location {
+                uri: uri.clone(),
+                range: range(source, label.span.clone()),
+            },
+            message: label.message.clone(),
+        })
+        .collect::<Vec<_>>();
+    let mut message = diagnostic.message;
+    if let Some(label) = primary {
+        message = format!("{}\n{}", message, label.message);
+    }
+    for note in &diagnostic.notes {
+        message = format!("{}\n{}", message, note);
+    }
+    lsp_types::Diagnostic {
+        range: primary.map_or(Range::default(), |label| range(source, label.span.clone())),
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: Some(NumberOrString::String(diagnostic.code)),
+        source: Some("tc".to_string()),
+        message,
+        related_information: (!related.is_empty()).then_some(related),
+        ..Default::default()
+    }
+}
+
+/// Converts a span of characters into a range of UTF-16 positions.
+fn range(source: &str, span: Span) -> Range {
+    Range {
+        start: position(source, span.start),
+        end: position(source, span.end),
+    }
+}
+
+fn position(source: &str, offset: usize) -> Position {
+    let (mut line, mut character) = (0, 0);
+    for c in source.chars().take(offset) {
+        if c == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += c.len_utf16() as u32;
+        }
+    }
+    Position { line, character }
+}
+
+/// Converts a UTF-16 position into an offset in characters, the one the
+/// parsers count spans in.
+fn offset(source: &str, position: Position) -> usize {
+    let (mut line, mut character) = (0, 0);
+    for (offset, c) in source.chars().enumerate() {
+        if line == position.line && character >= position.character {
+            return offset;
+        }
+        if c == '\n' {
+            if line == position.line {
+                return offset;
+            }
+            line += 1;
+            character = 0;
+        } else if line == position.line {
+            character += c.len_utf16() as u32;
+        }
+    }
+    source.chars().count()
+}
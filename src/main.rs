@@ -1,5 +1,14 @@
-fn main() {
-    let message = "
+mod cli;
+mod driver;
+mod lsp;
+mod repl;
+
+use cli::{Command, Input, System};
+use std::io::Read;
+use std::process::ExitCode;
+use yansi::Paint;
+
+const BANNER: &str = "
     **********************************************************
     *                                                        *
     *               Typecheckers Experiments                 *
@@ -15,7 +24,81 @@ fn main() {
     *        This program is licensed under the GPL-3.       *
     *                                                        *
     **********************************************************
-    ";
+";
+
+fn main() -> ExitCode {
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{} {}", "error:".red().bold(), message);
+            eprintln!("\nFor more information, try `tc --help`.");
+            return ExitCode::from(2);
+        }
+    };
+
+    match options.command {
+        Command::Help => {
+            println!("{}\n{}", BANNER, cli::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Command::Version => {
+            println!("tc {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Command::Repl => {
+            return match repl::Repl::new(options.system).run() {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("{} {}", "error:".red().bold(), e);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Command::Lsp => {
+            return match lsp::run(options.system) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("{} {}", "error:".red().bold(), e);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Command::Check | Command::Eval | Command::Prove => (),
+    }
+
+    let (filename, source) = match &options.input {
+        Input::File(filename) => (filename.clone(), std::fs::read_to_string(filename)),
+        Input::Stdin => {
+            let mut source = String::new();
+            let result = std::io::stdin().read_to_string(&mut source);
+            ("<stdin>".to_string(), result.map(|_| source))
+        }
+    };
+    let source = match source {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!(
+                "{} cannot read `{}`: {}",
+                "error:".red().bold(),
+                filename,
+                e
+            );
+            return ExitCode::FAILURE;
+        }
+    };
 
-    println!("{}", message);
+    let accepted = match (options.command, options.system) {
+        (Command::Repl | Command::Lsp | Command::Help | Command::Version, _) => unreachable!(),
+        (Command::Eval, _) => driver::eval_stlc(&filename, &source, &options),
+        (Command::Prove, _) => driver::prove_stlc(&filename, &source, &options),
+        (_, System::Stlc) => driver::check_stlc(&filename, &source, &options),
+        (_, System::Hm) => driver::check_hm(&filename, &source, &options),
+        (_, System::SystemF) => driver::check_systemf(&filename, &source, &options),
+        (_, System::Coc) => driver::check_coc(&filename, &source, &options),
+    };
+    if accepted {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
@@ -0,0 +1,290 @@
+use crate::cli::System;
+use chumsky::error::Simple;
+use diagnostic::Diagnostic;
+use hm::inference::{Inference, Scheme};
+use im::HashMap;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::fmt::Display;
+use std::io::{self, BufRead, IsTerminal};
+use stlc::symbol::Symbol;
+use stlc::typechecker::TypeChecker;
+use yansi::Paint;
+
+const HELP: &str = "\
+Commands:
+    <expression>              Print the type of the expression.
+    <declaration>             Check a `coc` definition or theorem, declaring it
+                              for the rest of the session.
+    :type <expression>        Same as above.
+    :let <name> = <expr>      Bind a name for the rest of the session.
+    :load <file>              Print the type of the program in <file>, keeping
+                              the declarations of a `coc` one.
+    :constraints              Print the constraints and substitutions of the
+                              last `hm` inference.
+    :mode <stlc|hm|systemf|coc>
+                              Switch the type system.
+    :help                     Print this message.
+    :quit                     Leave the REPL.";
+
+const FILENAME: &str = "<repl>";
+
+pub struct Repl {
+    system: System,
+    stlc: TypeChecker,
+    systemf: systemf::typechecker::TypeChecker,
+    coc: coc::typechecker::TypeChecker,
+    hm_env: HashMap<String, Scheme>,
+    /// The last `hm` inference, whose constraints `:constraints` prints.
+    last_inference: Inference,
+}
+
+impl Repl {
+    pub fn new(system: System) -> Self {
+        Self {
+            system,
+            stlc: TypeChecker::new(),
+            systemf: systemf::typechecker::TypeChecker::new(),
+            coc: coc::typechecker::TypeChecker::new(),
+            hm_env: HashMap::new(),
+            last_inference: Inference::new(),
+        }
+    }
+
+    /// Runs the session on the standard input, reading lines with an editor
+    /// when it is a terminal.
+    pub fn run(&mut self) -> rustyline::Result<()> {
+        let stdin = io::stdin();
+        if !stdin.is_terminal() {
+            return Ok(self.run_script(stdin.lock())?);
+        }
+        let mut editor = DefaultEditor::new()?;
+        println!("Type `:help` for the list of commands.");
+        loop {
+            let prompt = match self.system {
+                System::Stlc => "stlc> ",
+                System::Hm => "hm> ",
+                System::SystemF => "systemf> ",
+                System::Coc => "coc> ",
+            };
+            match editor.readline(prompt) {
+                Ok(line) if line.trim().is_empty() => (),
+                Ok(line) => {
+                    editor.add_history_entry(line.as_str())?;
+                    if !self.execute(&line) {
+                        return Ok(());
+                    }
+                }
+                Err(ReadlineError::Interrupted) => (),
+                Err(ReadlineError::Eof) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Runs every line of `input` as if typed in, without prompts or
+    /// history.
+    pub fn run_script(&mut self, input: impl BufRead) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if !line.trim().is_empty() && !self.execute(&line) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a single line of input, returning `false` when the session
+    /// should end.
+    fn execute(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let (command, argument) = match line.strip_prefix(':') {
+            Some(command) => command
+                .split_once(char::is_whitespace)
+                .map(|(command, argument)| (command, argument.trim()))
+                .unwrap_or((command, "")),
+            None => ("type", line),
+        };
+        match command {
+            "type" | "t" => self.typecheck(FILENAME, argument),
+            "let" | "l" => self.define(argument),
+            "load" => match std::fs::read_to_string(argument) {
+                Ok(source) => self.typecheck(argument, &source),
+                Err(e) => error(format!("cannot read `{}`: {}", argument, e)),
+            },
+            "constraints" | "c" => self.last_inference.debug(),
+            "mode" | "m" => match argument.parse() {
+                Ok(system) => self.system = system,
+                Err(e) => error(e),
+            },
+            "help" | "h" | "?" => println!("{}", HELP),
+            "quit" | "q" => return false,
+            _ => error(format!("unknown command `:{}`, try `:help`", command)),
+        }
+        true
+    }
+
+    fn typecheck(&mut self, filename: &str, source: &str) {
+        let typ = match self.system {
+            System::Stlc => self.infer_stlc(filename, source).map(|t| t.to_string()),
+            System::Hm => self.infer_hm(filename, source).map(|s| s.to_string()),
+            System::SystemF => self.infer_systemf(filename, source).map(|t| t.to_string()),
+            System::Coc if declares(source) => return self.declare_coc(filename, source),
+            System::Coc => self.infer_coc(filename, source).map(|t| t.to_string()),
+        };
+        if let Some(typ) = typ {
+            println!("{}", typ.green().bold());
+        }
+    }
+
+    fn define(&mut self, argument: &str) {
+        let Some((name, source)) = argument.split_once('=') else {
+            return error("expected `:let <name> = <expression>`");
+        };
+        let name = name.trim();
+        match self.system {
+            System::Stlc => {
+                if !stlc::parser::is_variable(name) {
+                    return error(format!("`{}` is not a valid `stlc` variable", name));
+                }
+                if let Some(typ) = self.infer_stlc(FILENAME, source) {
+                    println!("{} : {}", name.bold(), typ.to_string().green().bold());
+                    self.stlc.define(Symbol::intern(name), typ);
+                }
+            }
+            System::Hm => {
+                if !hm::parser::is_variable(name) {
+                    return error(format!("`{}` is not a valid `hm` variable", name));
+                }
+                if let Some(scheme) = self.infer_hm(FILENAME, source) {
+                    println!("{} : {}", name.bold(), scheme.to_string().green().bold());
+                    self.hm_env.insert(name.to_string(), scheme);
+                }
+            }
+            System::SystemF => {
+                if !systemf::parser::is_variable(name) {
+                    return error(format!("`{}` is not a valid `systemf` variable", name));
+                }
+                if let Some(typ) = self.infer_systemf(FILENAME, source) {
+                    println!("{} : {}", name.bold(), typ.to_string().green().bold());
+                    self.systemf.define(name.to_string(), typ);
+                }
+            }
+            System::Coc => {
+                if !coc::parser::is_variable(name) {
+                    return error(format!("`{}` is not a valid `coc` variable", name));
+                }
+                let definition = format!("definition {} := {}", name, source.trim());
+                self.declare_coc(FILENAME, &definition);
+            }
+        }
+    }
+
+    fn infer_stlc(&self, filename: &str, source: &str) -> Option<stlc::ast::Type> {
+        match stlc::parser::parse(source) {
+            // Inference runs on a copy so that a failure leaves the session
+            // untouched.
+            Ok(ast) => self
+                .stlc
+                .clone()
+                .infer(ast)
+                .map_err(|e| e.report(filename, source))
+                .ok(),
+            Err(parse_error) => {
+                report_parse_errors(filename, source, parse_error);
+                None
+            }
+        }
+    }
+
+    fn infer_systemf(&self, filename: &str, source: &str) -> Option<systemf::ast::Type> {
+        match systemf::parser::parse(source) {
+            Ok(ast) => self
+                .systemf
+                .clone()
+                .infer(ast)
+                .map_err(|e| e.report(filename, source))
+                .ok(),
+            Err(parse_error) => {
+                report_parse_errors(filename, source, parse_error);
+                None
+            }
+        }
+    }
+
+    fn infer_coc(&self, filename: &str, source: &str) -> Option<coc::ast::Term> {
+        match coc::parser::parse_term(source) {
+            Ok(term) => self
+                .coc
+                .clone()
+                .infer(term)
+                .map_err(|e| e.report(filename, source))
+                .ok(),
+            Err(parse_error) => {
+                report_parse_errors(filename, source, parse_error);
+                None
+            }
+        }
+    }
+
+    /// Checks the `coc` declarations of `source`, keeping them only if every
+    /// one of them is accepted.
+    fn declare_coc(&mut self, filename: &str, source: &str) {
+        let program = match coc::parser::parse(source) {
+            Ok(program) => program,
+            Err(parse_error) => return report_parse_errors(filename, source, parse_error),
+        };
+        let mut tc = self.coc.clone();
+        for declaration in program {
+            let name = declaration.name().name.clone();
+            match tc.declare(declaration) {
+                Ok(typ) => println!("{} : {}", name.bold(), typ.to_string().green().bold()),
+                Err(e) => return e.report(filename, source),
+            }
+        }
+        self.coc = tc;
+    }
+
+    fn infer_hm(&mut self, filename: &str, source: &str) -> Option<Scheme> {
+        match hm::parser::parse(source) {
+            Ok(ast) => {
+                // The schemes bound in the session are closed, so every
+                // inference starts afresh.
+                let mut inference = Inference::new();
+                let result = inference.infer_scheme(ast, self.hm_env.clone());
+                self.last_inference = inference;
+                match result {
+                    Ok(scheme) => Some(scheme),
+                    Err(e) => {
+                        e.report(filename, source);
+                        None
+                    }
+                }
+            }
+            Err(parse_error) => {
+                report_parse_errors(filename, source, parse_error);
+                None
+            }
+        }
+    }
+}
+
+/// Whether `source` holds `coc` declarations rather than a term, its first
+/// line that is not a comment starting with a declaration.
+fn declares(source: &str) -> bool {
+    source
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("--"))
+        .is_some_and(|line| line.starts_with("definition") || line.starts_with("theorem"))
+}
+
+fn report_parse_errors(filename: &str, source: &str, errors: Vec<Simple<char>>) {
+    for e in errors {
+        Diagnostic::from(e).report(filename, source);
+    }
+}
+
+fn error(message: impl Display) {
+    eprintln!("{} {}", "error:".red().bold(), message);
+}
@@ -1,42 +1,120 @@
 /*
 ⟨program⟩ ⩴  ⟨expression⟩
 
+⟨proof-script⟩ ⩴  ⟨theorem⟩*
+
+⟨theorem⟩ ⩴  'theorem' ⟨variable⟩ ':' ⟨type⟩ ':=' ⟨expression⟩
+
+A theorem is proved by checking its proof against its statement, read as a
+type. Its name is bound to the statement in the theorems after it.
+
 ⟨expression⟩ ⩴  ⟨variable⟩
               | ⟨abstraction⟩
               | ⟨application⟩
-              | ⟨addition⟩
+              | ⟨binary⟩
+              | ⟨conditional⟩
+              | ⟨pair⟩
+              | ⟨projection⟩
+              | ⟨injection⟩
+              | ⟨case⟩
+              | ⟨absurd⟩
+              | ⟨fixpoint⟩
+              | ⟨letrec⟩
               | ⟨int⟩
+              | ⟨bool⟩
               | ⟨unit⟩
+              | ⟨ascription⟩
               | '(' ⟨expression⟩ ')'
 
-⟨variable⟩ ⩴  ⟨letter⟩
+⟨variable⟩ ⩴  ⟨start⟩ ⟨continue⟩*    (other than a keyword)
+
+⟨start⟩ ⩴  'a' | 'b' | ... | 'z' | '_'
 
-⟨letter⟩ ⩴  'a' | 'b' | 'c' | ... | 'z'
+⟨continue⟩ ⩴  ⟨start⟩ | 'A' | 'B' | ... | 'Z' | ⟨digit⟩ | '\''
 
 ⟨abstraction⟩ ⩴  'λ' ⟨variable⟩ ':' ⟨type⟩ '.' ⟨expression⟩
+                 | 'λ' ⟨variable⟩ '.' ⟨expression⟩
+
+An abstraction without an annotation only checks against a function type,
+given by an ascription or by the position it appears in.
+
+⟨ascription⟩ ⩴  '(' ⟨expression⟩ ':' ⟨type⟩ ')'
 
 ⟨type⟩ ⩴  ⟨base-type⟩
         | ⟨function-type⟩
+        | ⟨product-type⟩
+        | ⟨sum-type⟩
+        | ⟨negation⟩
+        | '(' ⟨type⟩ ')'
 
 ⟨base-type⟩ ⩴  'Int'
+             | 'Bool'
              | 'Unit'
+             | 'False'
+             | ⟨atom⟩
+
+⟨atom⟩ ⩴  ⟨upper⟩ ⟨continue⟩*    (other than a base type)
+
+⟨upper⟩ ⩴  'A' | 'B' | ... | 'Z'
+
+An atom is an uninterpreted proposition, which no value inhabits.
+
+⟨negation⟩ ⩴  '~' ⟨type⟩
+
+`~A` binds tighter than any connective, and stands for `A -> False`.
 
 ⟨function-type⟩ ⩴  ⟨type⟩ ' -> ' ⟨type⟩
 
+⟨product-type⟩ ⩴  ⟨type⟩ '×' ⟨type⟩
+                 | ⟨type⟩ '*' ⟨type⟩
+                 | ⟨type⟩ '/\' ⟨type⟩
+
+⟨sum-type⟩ ⩴  ⟨type⟩ '+' ⟨type⟩
+             | ⟨type⟩ '\/' ⟨type⟩
+
+⟨pair⟩ ⩴  '(' ⟨expression⟩ ',' ⟨expression⟩ ')'
+
+⟨projection⟩ ⩴  'fst' ⟨expression⟩
+               | 'snd' ⟨expression⟩
+
+⟨injection⟩ ⩴  'inl' ⟨expression⟩ 'as' ⟨type⟩
+              | 'inr' ⟨expression⟩ 'as' ⟨type⟩
+
+⟨case⟩ ⩴  'case' ⟨expression⟩ 'of' 'inl' ⟨variable⟩ '=>' ⟨expression⟩
+                              '|' 'inr' ⟨variable⟩ '=>' ⟨expression⟩
+
+⟨absurd⟩ ⩴  'absurd' ⟨expression⟩ 'as' ⟨type⟩
+
+⟨fixpoint⟩ ⩴  'fix' ⟨expression⟩
+
+⟨letrec⟩ ⩴  'letrec' ⟨variable⟩ ':' ⟨type⟩ '=' ⟨expression⟩ 'in' ⟨expression⟩
+
+`letrec f : T = e in b` is sugar for `(λf:T. b) (fix (λf:T. e))`.
+
+⟨conditional⟩ ⩴  'if' ⟨expression⟩ 'then' ⟨expression⟩ 'else' ⟨expression⟩
+
 ⟨unit⟩ ⩴  '()'
 
+⟨bool⟩ ⩴  'true' | 'false'
+
 ⟨int⟩ ⩴  ⟨integer⟩
 
 ⟨integer⟩ ⩴  ⟨digit⟩+
            | '-' ⟨digit⟩+
 
-⟨addition⟩ ⩴  ⟨digit⟩ '+' ⟨digit⟩
+⟨binary⟩ ⩴  ⟨expression⟩ ⟨operator⟩ ⟨expression⟩
+
+⟨operator⟩ ⩴  '*'             (tightest, left associative)
+             | '+' | '-'       (left associative)
+             | '==' | '<'      (loosest, non associative)
 
 ⟨digit⟩ ⩴  '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9'
 
 ⟨application⟩ ⩴  ⟨expression⟩ ⟨expression⟩
 */
 
+use crate::symbol::Symbol;
+use std::fmt;
 use std::ops::Range;
 
 type Span = Range<usize>;
@@ -46,7 +124,9 @@ pub enum Expression {
     Variable(Variable),
     Abstraction {
         variable: Variable,
-        typ: Type,
+        /// The type of the parameter, left out when the abstraction is
+        /// checked against a function type.
+        typ: Option<Type>,
         expression: Box<Expression>,
         span: Span,
     },
@@ -55,61 +135,493 @@ pub enum Expression {
         arg: Box<Expression>,
         span: Span,
     },
-    Addition {
+    Binary {
+        operator: Operator,
         lhs: Box<Expression>,
         rhs: Box<Expression>,
         span: Span,
     },
+    If {
+        condition: Box<Expression>,
+        then_branch: Box<Expression>,
+        else_branch: Box<Expression>,
+        span: Span,
+    },
+    Pair {
+        first: Box<Expression>,
+        second: Box<Expression>,
+        span: Span,
+    },
+    First {
+        pair: Box<Expression>,
+        span: Span,
+    },
+    Second {
+        pair: Box<Expression>,
+        span: Span,
+    },
+    Fix {
+        expression: Box<Expression>,
+        span: Span,
+    },
+    Left {
+        expression: Box<Expression>,
+        typ: Type,
+        span: Span,
+    },
+    Right {
+        expression: Box<Expression>,
+        typ: Type,
+        span: Span,
+    },
+    Case {
+        expression: Box<Expression>,
+        left: Box<Branch>,
+        right: Box<Branch>,
+        span: Span,
+    },
+    /// `absurd e as T`, eliminating a proof `e` of `False` into any `T`.
+    Absurd {
+        expression: Box<Expression>,
+        typ: Type,
+        span: Span,
+    },
     Int {
-        _n: i32,
+        n: i32,
+        span: Span,
+    },
+    Bool {
+        value: bool,
         span: Span,
     },
     Unit {
         span: Span,
     },
+    /// `(e : T)`, checking `e` against `T`.
+    Ascription {
+        expression: Box<Expression>,
+        typ: Type,
+        span: Span,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Eq,
+    Lt,
+}
+
+impl Operator {
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Operator::Eq | Operator::Lt => 1,
+            Operator::Add | Operator::Sub => 2,
+            Operator::Mul => 3,
+        }
+    }
+    /// Whether the operator builds a `Bool` out of its `Int` operands.
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, Operator::Eq | Operator::Lt)
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operator::Add => write!(f, "+"),
+            Operator::Sub => write!(f, "-"),
+            Operator::Mul => write!(f, "*"),
+            Operator::Eq => write!(f, "=="),
+            Operator::Lt => write!(f, "<"),
+        }
+    }
+}
+
+/// An arm of a `case`, binding the injected value to `variable`.
+#[derive(Debug, Clone)]
+pub struct Branch {
+    pub variable: Variable,
+    pub expression: Expression,
 }
 
 impl Expression {
-    pub fn get_span(self) -> Span {
+    pub fn get_span(&self) -> Span {
+        match self {
+            Expression::Variable(Variable { span, .. }) => span.clone(),
+            Expression::Abstraction { span, .. } => span.clone(),
+            Expression::Application { span, .. } => span.clone(),
+            Expression::Binary { span, .. } => span.clone(),
+            Expression::If { span, .. } => span.clone(),
+            Expression::Pair { span, .. } => span.clone(),
+            Expression::First { span, .. } => span.clone(),
+            Expression::Second { span, .. } => span.clone(),
+            Expression::Fix { span, .. } => span.clone(),
+            Expression::Left { span, .. } => span.clone(),
+            Expression::Right { span, .. } => span.clone(),
+            Expression::Case { span, .. } => span.clone(),
+            Expression::Absurd { span, .. } => span.clone(),
+            Expression::Int { span, .. } => span.clone(),
+            Expression::Bool { span, .. } => span.clone(),
+            Expression::Unit { span } => span.clone(),
+            Expression::Ascription { span, .. } => span.clone(),
+        }
+    }
+    /// How tightly the expression binds, from 0 for the constructs
+    /// extending as far right as possible to 5 for atoms.
+    pub fn precedence(&self) -> u8 {
         match self {
-            Expression::Variable(Variable { span, .. }) => span,
-            Expression::Abstraction { span, .. } => span,
-            Expression::Application { span, .. } => span,
-            Expression::Addition { span, .. } => span,
-            Expression::Int { span, .. } => span,
-            Expression::Unit { span } => span,
+            Expression::Abstraction { .. }
+            | Expression::Left { .. }
+            | Expression::Right { .. }
+            | Expression::Case { .. }
+            | Expression::Absurd { .. }
+            | Expression::If { .. } => 0,
+            Expression::Binary { operator, .. } => operator.precedence(),
+            // Printed as a subtraction.
+            Expression::Int { n, .. } if *n < 0 => Operator::Sub.precedence(),
+            Expression::Application { .. }
+            | Expression::First { .. }
+            | Expression::Second { .. }
+            | Expression::Fix { .. } => 4,
+            Expression::Variable(_)
+            | Expression::Pair { .. }
+            | Expression::Int { .. }
+            | Expression::Bool { .. }
+            | Expression::Unit { .. }
+            | Expression::Ascription { .. } => 5,
+        }
+    }
+    /// The span of the binder of the variable at `offset`, or of the binder
+    /// itself when `offset` falls on one.
+    pub fn definition(&self, offset: usize) -> Option<Span> {
+        self.resolve(offset, &mut Vec::new())
+    }
+    fn resolve<'a>(&'a self, offset: usize, scope: &mut Vec<&'a Variable>) -> Option<Span> {
+        match self {
+            Expression::Variable(variable) if variable.span.contains(&offset) => scope
+                .iter()
+                .rev()
+                .find(|binder| binder.name == variable.name)
+                .map(|binder| binder.span.clone()),
+            Expression::Abstraction {
+                variable,
+                expression,
+                ..
+            } => expression.resolve_under(variable, offset, scope),
+            Expression::Application {
+                callee: lhs,
+                arg: rhs,
+                ..
+            }
+            | Expression::Binary { lhs, rhs, .. }
+            | Expression::Pair {
+                first: lhs,
+                second: rhs,
+                ..
+            } => lhs
+                .resolve(offset, scope)
+                .or_else(|| rhs.resolve(offset, scope)),
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => condition
+                .resolve(offset, scope)
+                .or_else(|| then_branch.resolve(offset, scope))
+                .or_else(|| else_branch.resolve(offset, scope)),
+            Expression::First { pair, .. } | Expression::Second { pair, .. } => {
+                pair.resolve(offset, scope)
+            }
+            Expression::Fix { expression, .. }
+            | Expression::Left { expression, .. }
+            | Expression::Right { expression, .. }
+            | Expression::Absurd { expression, .. }
+            | Expression::Ascription { expression, .. } => expression.resolve(offset, scope),
+            Expression::Case {
+                expression,
+                left,
+                right,
+                ..
+            } => expression
+                .resolve(offset, scope)
+                .or_else(|| left.expression.resolve_under(&left.variable, offset, scope))
+                .or_else(|| {
+                    right
+                        .expression
+                        .resolve_under(&right.variable, offset, scope)
+                }),
+            Expression::Variable(_)
+            | Expression::Int { .. }
+            | Expression::Bool { .. }
+            | Expression::Unit { .. } => None,
+        }
+    }
+    /// Resolves `offset` in the expression, in the scope of `binder`.
+    fn resolve_under<'a>(
+        &'a self,
+        binder: &'a Variable,
+        offset: usize,
+        scope: &mut Vec<&'a Variable>,
+    ) -> Option<Span> {
+        if binder.span.contains(&offset) {
+            return Some(binder.span.clone());
+        }
+        scope.push(binder);
+        let definition = self.resolve(offset, scope);
+        scope.pop();
+        definition
+    }
+    /// Writes the expression, parenthesized if it binds looser than `level`.
+    fn fmt_at(&self, f: &mut fmt::Formatter, level: u8) -> fmt::Result {
+        if self.precedence() < level {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Variable(Variable { name, .. }) => write!(f, "{}", name),
+            Expression::Abstraction {
+                variable,
+                typ: Some(typ),
+                expression,
+                ..
+            } => write!(f, "λ{}:{}. {}", variable.name, typ, expression),
+            Expression::Abstraction {
+                variable,
+                typ: None,
+                expression,
+                ..
+            } => write!(f, "λ{}. {}", variable.name, expression),
+            Expression::Application { callee, arg, .. } => {
+                callee.fmt_at(f, 4)?;
+                write!(f, " ")?;
+                arg.fmt_at(f, 5)
+            }
+            Expression::Binary {
+                operator, lhs, rhs, ..
+            } => {
+                let precedence = operator.precedence();
+                if operator.is_comparison() {
+                    lhs.fmt_at(f, precedence + 1)?;
+                } else {
+                    lhs.fmt_at(f, precedence)?;
+                }
+                write!(f, " {} ", operator)?;
+                rhs.fmt_at(f, precedence + 1)
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => write!(
+                f,
+                "if {} then {} else {}",
+                condition, then_branch, else_branch
+            ),
+            Expression::Pair { first, second, .. } => write!(f, "({}, {})", first, second),
+            Expression::First { pair, .. } => {
+                write!(f, "fst ")?;
+                pair.fmt_at(f, 5)
+            }
+            Expression::Second { pair, .. } => {
+                write!(f, "snd ")?;
+                pair.fmt_at(f, 5)
+            }
+            Expression::Fix { expression, .. } => {
+                write!(f, "fix ")?;
+                expression.fmt_at(f, 5)
+            }
+            Expression::Left {
+                expression, typ, ..
+            } => {
+                write!(f, "inl ")?;
+                expression.fmt_at(f, 5)?;
+                write!(f, " as {}", typ)
+            }
+            Expression::Right {
+                expression, typ, ..
+            } => {
+                write!(f, "inr ")?;
+                expression.fmt_at(f, 5)?;
+                write!(f, " as {}", typ)
+            }
+            Expression::Case {
+                expression,
+                left,
+                right,
+                ..
+            } => write!(
+                f,
+                "case {} of inl {} => {} | inr {} => {}",
+                expression,
+                left.variable.name,
+                left.expression,
+                right.variable.name,
+                right.expression
+            ),
+            Expression::Absurd {
+                expression, typ, ..
+            } => {
+                write!(f, "absurd ")?;
+                expression.fmt_at(f, 5)?;
+                write!(f, " as {}", typ)
+            }
+            // There are no negative literals, and the magnitude of
+            // `i32::MIN` is not one either.
+            Expression::Int { n: i32::MIN, .. } => write!(f, "0 - {} - 1", i32::MAX),
+            Expression::Int { n, .. } if *n < 0 => write!(f, "0 - {}", n.unsigned_abs()),
+            Expression::Int { n, .. } => write!(f, "{}", n),
+            Expression::Bool { value, .. } => write!(f, "{}", value),
+            Expression::Unit { .. } => write!(f, "()"),
+            Expression::Ascription {
+                expression, typ, ..
+            } => write!(f, "({} : {})", expression, typ),
         }
     }
 }
 
+/// `theorem name : statement := proof`, claiming that `proof` has the type
+/// `statement` read as a proposition.
+#[derive(Debug, Clone)]
+pub struct Theorem {
+    pub name: Variable,
+    pub statement: Type,
+    pub proof: Expression,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct Variable {
-    pub name: char,
+    pub name: Symbol,
     pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub enum Type {
     Function(Box<Type>, Box<Type>),
+    Product(Box<Type>, Box<Type>),
+    Sum(Box<Type>, Box<Type>),
     Unit,
     Int,
+    Bool,
+    /// The empty type, written `False` as the proposition with no proof.
+    Empty,
+    /// An uninterpreted proposition.
+    Atom(Symbol),
 }
 
 impl Type {
-    pub fn to_string(&self) -> String {
+    /// Whether the type reads as a proposition, built out of atoms and
+    /// `False` by `->`, `×` and `+`.
+    pub fn is_proposition(&self) -> bool {
+        match self {
+            Type::Function(lhs, rhs) | Type::Product(lhs, rhs) | Type::Sum(lhs, rhs) => {
+                lhs.is_proposition() && rhs.is_proposition()
+            }
+            Type::Empty | Type::Atom(_) => true,
+            Type::Unit | Type::Int | Type::Bool => false,
+        }
+    }
+    /// The type written as a proposition, with `/\`, `\/` and `~`.
+    pub fn as_proposition(&self) -> Proposition<'_> {
+        Proposition(self)
+    }
+}
+
+/// A type displayed as the proposition it reads as.
+pub struct Proposition<'a>(&'a Type);
+
+impl Proposition<'_> {
+    /// How tightly the connective binds, from 0 for `->` to 3 for `~` and
+    /// atoms.
+    fn precedence(&self) -> u8 {
+        match self.0 {
+            Type::Function(_, ret) if **ret == Type::Empty => 3,
+            Type::Function(..) => 0,
+            Type::Sum(..) => 1,
+            Type::Product(..) => 2,
+            _ => 3,
+        }
+    }
+    /// Writes `typ`, parenthesized if it binds looser than `level`.
+    fn fmt_at(f: &mut fmt::Formatter, typ: &Type, level: u8) -> fmt::Result {
+        let proposition = typ.as_proposition();
+        if proposition.precedence() < level {
+            write!(f, "({})", proposition)
+        } else {
+            write!(f, "{}", proposition)
+        }
+    }
+}
+
+impl fmt::Display for Proposition<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Every binary connective associates to the right.
+        let (lhs, connective, rhs, level) = match self.0 {
+            Type::Function(negated, ret) if **ret == Type::Empty => {
+                write!(f, "~")?;
+                return Self::fmt_at(f, negated, 3);
+            }
+            Type::Function(lhs, rhs) => (lhs, "->", rhs, 0),
+            Type::Sum(lhs, rhs) => (lhs, "\\/", rhs, 1),
+            Type::Product(lhs, rhs) => (lhs, "/\\", rhs, 2),
+            typ => return write!(f, "{}", typ),
+        };
+        Self::fmt_at(f, lhs, level + 1)?;
+        write!(f, " {} ", connective)?;
+        Self::fmt_at(f, rhs, level)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Type::Function(param1, ret1) => {
-                let param_str = param1.to_string();
-                let ret_str = ret1.to_string();
-                let param_parenthesized = if let Type::Function(..) = **param1 {
-                    format!("({})", param_str)
+                if let Type::Function(..) = **param1 {
+                    write!(f, "({}) -> {}", param1, ret1)
                 } else {
-                    param_str
-                };
-                format!("{} -> {}", param_parenthesized, ret_str)
+                    write!(f, "{} -> {}", param1, ret1)
+                }
+            }
+            // `×` binds tighter than `+`, which binds tighter than `->`; both
+            // associate to the right.
+            Type::Product(lhs, rhs) => {
+                match **lhs {
+                    Type::Function(..) | Type::Sum(..) | Type::Product(..) => {
+                        write!(f, "({})", lhs)?
+                    }
+                    _ => write!(f, "{}", lhs)?,
+                }
+                match **rhs {
+                    Type::Function(..) | Type::Sum(..) => write!(f, " × ({})", rhs),
+                    _ => write!(f, " × {}", rhs),
+                }
+            }
+            Type::Sum(lhs, rhs) => {
+                match **lhs {
+                    Type::Function(..) | Type::Sum(..) => write!(f, "({})", lhs)?,
+                    _ => write!(f, "{}", lhs)?,
+                }
+                match **rhs {
+                    Type::Function(..) => write!(f, " + ({})", rhs),
+                    _ => write!(f, " + {}", rhs),
+                }
             }
-            Type::Unit => "Unit".to_string(),
-            Type::Int => "Int".to_string(),
+            Type::Unit => write!(f, "Unit"),
+            Type::Int => write!(f, "Int"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Empty => write!(f, "False"),
+            Type::Atom(name) => write!(f, "{}", name),
         }
     }
 }
@@ -120,8 +632,13 @@ impl PartialEq for Type {
             (Type::Function(param1, ret1), Type::Function(param2, ret2)) => {
                 param1 == param2 && ret1 == ret2
             }
+            (Type::Product(lhs1, rhs1), Type::Product(lhs2, rhs2)) => lhs1 == lhs2 && rhs1 == rhs2,
+            (Type::Sum(lhs1, rhs1), Type::Sum(lhs2, rhs2)) => lhs1 == lhs2 && rhs1 == rhs2,
             (Type::Unit, Type::Unit) => true,
             (Type::Int, Type::Int) => true,
+            (Type::Bool, Type::Bool) => true,
+            (Type::Empty, Type::Empty) => true,
+            (Type::Atom(name1), Type::Atom(name2)) => name1 == name2,
             _ => false,
         }
     }
@@ -0,0 +1,353 @@
+use crate::ast::*;
+use crate::symbol::Symbol;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rule {
+    Var,
+    Abs,
+    App,
+    Add,
+    Sub,
+    Mul,
+    Eq,
+    Lt,
+    If,
+    Pair,
+    Fst,
+    Snd,
+    Fix,
+    Inl,
+    Inr,
+    Case,
+    Absurd,
+    Int,
+    True,
+    False,
+    Unit,
+    Ascribe,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::Var => write!(f, "T-Var"),
+            Rule::Abs => write!(f, "T-Abs"),
+            Rule::App => write!(f, "T-App"),
+            Rule::Add => write!(f, "T-Add"),
+            Rule::Sub => write!(f, "T-Sub"),
+            Rule::Mul => write!(f, "T-Mul"),
+            Rule::Eq => write!(f, "T-Eq"),
+            Rule::Lt => write!(f, "T-Lt"),
+            Rule::If => write!(f, "T-If"),
+            Rule::Pair => write!(f, "T-Pair"),
+            Rule::Fst => write!(f, "T-Fst"),
+            Rule::Snd => write!(f, "T-Snd"),
+            Rule::Fix => write!(f, "T-Fix"),
+            Rule::Inl => write!(f, "T-Inl"),
+            Rule::Inr => write!(f, "T-Inr"),
+            Rule::Case => write!(f, "T-Case"),
+            Rule::Absurd => write!(f, "T-Absurd"),
+            Rule::Int => write!(f, "T-Int"),
+            Rule::True => write!(f, "T-True"),
+            Rule::False => write!(f, "T-False"),
+            Rule::Unit => write!(f, "T-Unit"),
+            Rule::Ascribe => write!(f, "T-Ascribe"),
+        }
+    }
+}
+
+/// A typing judgement `Γ ⊢ e : T` concluded by `rule` from its premises.
+#[derive(Debug, Clone)]
+pub struct Derivation {
+    pub rule: Rule,
+    pub context: Vec<(Symbol, Type)>,
+    pub expression: Expression,
+    pub typ: Type,
+    pub premises: Vec<Derivation>,
+}
+
+impl Derivation {
+    /// Renders the derivation as an indented tree, the conclusion first and
+    /// each premise below it.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        self.write_text(&mut out, "", "");
+        out
+    }
+
+    fn write_text(&self, out: &mut String, first: &str, rest: &str) {
+        let context = self
+            .context
+            .iter()
+            .map(|(name, typ)| format!("{} : {}", name, typ))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let turnstile = if context.is_empty() { "⊢" } else { " ⊢" };
+        out.push_str(&format!(
+            "{}{}{} {} : {}   ({})\n",
+            first, context, turnstile, self.expression, self.typ, self.rule
+        ));
+        for (i, premise) in self.premises.iter().enumerate() {
+            if i + 1 == self.premises.len() {
+                premise.write_text(out, &format!("{}`-- ", rest), &format!("{}    ", rest));
+            } else {
+                premise.write_text(out, &format!("{}|-- ", rest), &format!("{}|   ", rest));
+            }
+        }
+    }
+
+    /// Renders the derivation as a `prooftree` of the `bussproofs` package.
+    pub fn to_bussproofs(&self) -> String {
+        let mut out = String::from("\\begin{prooftree}\n");
+        self.write_bussproofs(&mut out);
+        out.push_str("\\end{prooftree}\n");
+        out
+    }
+
+    fn write_bussproofs(&self, out: &mut String) {
+        if self.premises.is_empty() {
+            out.push_str("\\AxiomC{}\n");
+        }
+        for premise in &self.premises {
+            premise.write_bussproofs(out);
+        }
+        let inference = match self.premises.len() {
+            0 | 1 => "UnaryInfC",
+            2 => "BinaryInfC",
+            3 => "TrinaryInfC",
+            4 => "QuaternaryInfC",
+            _ => "QuinaryInfC",
+        };
+        out.push_str(&format!(
+            "\\RightLabel{{\\scriptsize {}}}\n\\{}{{${}$}}\n",
+            self.rule,
+            inference,
+            self.latex_judgement()
+        ));
+    }
+
+    /// Renders the derivation as nested `\inferrule*` of the `mathpartir`
+    /// package.
+    pub fn to_mathpartir(&self) -> String {
+        let mut out = String::from("\\begin{mathpar}\n");
+        self.write_mathpartir(&mut out, 1);
+        out.push_str("\n\\end{mathpar}\n");
+        out
+    }
+
+    fn write_mathpartir(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        out.push_str(&format!(
+            "{}\\inferrule*[right={}]\n{}{{",
+            indent, self.rule, indent
+        ));
+        if !self.premises.is_empty() {
+            out.push('\n');
+            for (i, premise) in self.premises.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(" \\\\\n");
+                }
+                premise.write_mathpartir(out, depth + 1);
+            }
+            out.push_str(&format!("\n{}", indent));
+        }
+        out.push_str(&format!("}}\n{}{{{}}}", indent, self.latex_judgement()));
+    }
+
+    fn latex_judgement(&self) -> String {
+        let context = self
+            .context
+            .iter()
+            .map(|(name, typ)| format!("{} : {}", latex_variable(name), latex_type(typ)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let turnstile = if context.is_empty() {
+            "\\vdash"
+        } else {
+            " \\vdash"
+        };
+        format!(
+            "{}{} {} : {}",
+            context,
+            turnstile,
+            latex_expression(&self.expression),
+            latex_type(&self.typ)
+        )
+    }
+}
+
+pub fn latex_type(typ: &Type) -> String {
+    match typ {
+        Type::Function(param, ret) => {
+            if let Type::Function(..) = **param {
+                format!("({}) \\to {}", latex_type(param), latex_type(ret))
+            } else {
+                format!("{} \\to {}", latex_type(param), latex_type(ret))
+            }
+        }
+        Type::Product(lhs, rhs) => {
+            let lhs = match **lhs {
+                Type::Function(..) | Type::Sum(..) | Type::Product(..) => {
+                    format!("({})", latex_type(lhs))
+                }
+                _ => latex_type(lhs),
+            };
+            let rhs = match **rhs {
+                Type::Function(..) | Type::Sum(..) => format!("({})", latex_type(rhs)),
+                _ => latex_type(rhs),
+            };
+            format!("{} \\times {}", lhs, rhs)
+        }
+        Type::Sum(lhs, rhs) => {
+            let lhs = match **lhs {
+                Type::Function(..) | Type::Sum(..) => format!("({})", latex_type(lhs)),
+                _ => latex_type(lhs),
+            };
+            let rhs = match **rhs {
+                Type::Function(..) => format!("({})", latex_type(rhs)),
+                _ => latex_type(rhs),
+            };
+            format!("{} + {}", lhs, rhs)
+        }
+        Type::Unit => "\\mathsf{Unit}".to_string(),
+        Type::Int => "\\mathsf{Int}".to_string(),
+        Type::Bool => "\\mathsf{Bool}".to_string(),
+        Type::Empty => "\\mathsf{False}".to_string(),
+        Type::Atom(name) => latex_variable(name),
+    }
+}
+
+/// Single letters are left as is, longer names are set as one word so that
+/// they do not read as a product of variables.
+pub fn latex_variable(name: &Symbol) -> String {
+    let name = name.as_str();
+    let base = name.trim_end_matches('\'');
+    let primes = &name[base.len()..];
+    if base.chars().count() == 1 && base != "_" {
+        format!("{}{}", base, primes)
+    } else {
+        format!("\\mathit{{{}}}{}", base.replace('_', "\\_"), primes)
+    }
+}
+
+pub fn latex_expression(expr: &Expression) -> String {
+    // Same parenthesization as the `Display` of expressions.
+    let at = |expr: &Expression, level: u8| {
+        if expr.precedence() < level {
+            format!("({})", latex_expression(expr))
+        } else {
+            latex_expression(expr)
+        }
+    };
+    let atom = |expr: &Expression| at(expr, 5);
+    match expr {
+        Expression::Variable(Variable { name, .. }) => latex_variable(name),
+        Expression::Abstraction {
+            variable,
+            typ: Some(typ),
+            expression,
+            ..
+        } => format!(
+            "\\lambda {}{{:}}{}.\\, {}",
+            latex_variable(&variable.name),
+            latex_type(typ),
+            latex_expression(expression)
+        ),
+        Expression::Abstraction {
+            variable,
+            typ: None,
+            expression,
+            ..
+        } => format!(
+            "\\lambda {}.\\, {}",
+            latex_variable(&variable.name),
+            latex_expression(expression)
+        ),
+        Expression::Application { callee, arg, .. } => {
+            format!("{}\\; {}", at(callee, 4), atom(arg))
+        }
+        Expression::Binary {
+            operator, lhs, rhs, ..
+        } => {
+            let precedence = operator.precedence();
+            let lhs_level = if operator.is_comparison() {
+                precedence + 1
+            } else {
+                precedence
+            };
+            let symbol = match operator {
+                Operator::Mul => "\\times".to_string(),
+                Operator::Eq => "=".to_string(),
+                operator => operator.to_string(),
+            };
+            format!(
+                "{} {} {}",
+                at(lhs, lhs_level),
+                symbol,
+                at(rhs, precedence + 1)
+            )
+        }
+        Expression::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => format!(
+            "\\mathsf{{if}}\\; {}\\; \\mathsf{{then}}\\; {}\\; \\mathsf{{else}}\\; {}",
+            latex_expression(condition),
+            latex_expression(then_branch),
+            latex_expression(else_branch)
+        ),
+        Expression::Pair { first, second, .. } => format!(
+            "({}, {})",
+            latex_expression(first),
+            latex_expression(second)
+        ),
+        Expression::First { pair, .. } => format!("\\mathsf{{fst}}\\; {}", atom(pair)),
+        Expression::Second { pair, .. } => format!("\\mathsf{{snd}}\\; {}", atom(pair)),
+        Expression::Fix { expression, .. } => {
+            format!("\\mathsf{{fix}}\\; {}", atom(expression))
+        }
+        Expression::Left {
+            expression, typ, ..
+        } => format!(
+            "\\mathsf{{inl}}\\; {}\\; \\mathsf{{as}}\\; {}",
+            atom(expression),
+            latex_type(typ)
+        ),
+        Expression::Right {
+            expression, typ, ..
+        } => format!(
+            "\\mathsf{{inr}}\\; {}\\; \\mathsf{{as}}\\; {}",
+            atom(expression),
+            latex_type(typ)
+        ),
+        Expression::Case {
+            expression,
+            left,
+            right,
+            ..
+        } => format!(
+            "\\mathsf{{case}}\\; {}\\; \\mathsf{{of}}\\; \\mathsf{{inl}}\\; {} \\Rightarrow {} \\mid \\mathsf{{inr}}\\; {} \\Rightarrow {}",
+            latex_expression(expression),
+            latex_variable(&left.variable.name),
+            latex_expression(&left.expression),
+            latex_variable(&right.variable.name),
+            latex_expression(&right.expression)
+        ),
+        Expression::Absurd {
+            expression, typ, ..
+        } => format!(
+            "\\mathsf{{absurd}}\\; {}\\; \\mathsf{{as}}\\; {}",
+            atom(expression),
+            latex_type(typ)
+        ),
+        Expression::Int { n, .. } => n.to_string(),
+        Expression::Bool { value, .. } => format!("\\mathsf{{{}}}", value),
+        Expression::Unit { .. } => "()".to_string(),
+        Expression::Ascription {
+            expression, typ, ..
+        } => format!("({} : {})", latex_expression(expression), latex_type(typ)),
+    }
+}
@@ -1,7 +1,10 @@
-use ariadne::{Color, Label, Report, ReportKind, Source};
+use crate::ast::{Type, Variable};
+use crate::symbol::Symbol;
+use diagnostic::Diagnostic;
+use std::io;
 use std::ops::Range;
-use yansi::Paint;
 
+#[derive(Debug)]
 pub enum Error {
     TypeMismatch {
         expected: String,
@@ -9,50 +12,108 @@ pub enum Error {
         span: Range<usize>,
     },
     UndefinedSymbol {
-        name: char,
+        name: Symbol,
+        span: Range<usize>,
+    },
+    BranchMismatch {
+        then_type: String,
+        else_type: String,
+        then_span: Range<usize>,
+        else_span: Range<usize>,
+    },
+    /// A theorem stating a type that mentions `Int`, `Bool` or `Unit`.
+    NotAProposition {
+        statement: String,
+        span: Range<usize>,
+    },
+    /// An abstraction without an annotation, in a position where its type
+    /// is synthesized rather than checked.
+    UnannotatedLambda {
+        span: Range<usize>,
+    },
+    /// A `fix` or `letrec` in a proof, where it would prove any proposition.
+    GeneralRecursion {
         span: Range<usize>,
     },
 }
 
 impl Error {
-    pub fn report(&self, filename: &str) {
-        let source = &std::fs::read_to_string(filename).unwrap();
+    pub fn report(&self, filename: &str, source: &str) {
+        self.diagnostic().report(filename, source)
+    }
+
+    /// Renders the report into `out` rather than the standard output.
+    pub fn write(&self, filename: &str, source: &str, out: impl io::Write) -> io::Result<()> {
+        self.diagnostic().write(filename, source, out)
+    }
 
-        let mut report = Report::build(ReportKind::Error, filename, 1);
+    /// The diagnostic of the error as a step of the proof of the theorem
+    /// `name`, which also points at the theorem.
+    pub fn proof_diagnostic(&self, name: &Variable, statement: &Type) -> Diagnostic {
+        match self {
+            Error::NotAProposition { .. } => self.diagnostic(),
+            _ => self.diagnostic().with_secondary(
+                name.span.clone(),
+                format!("`{}` states `{}`.", name.name, statement.as_proposition()),
+            ),
+        }
+    }
 
+    pub fn diagnostic(&self) -> Diagnostic {
         match &self {
             Error::TypeMismatch {
                 expected,
                 found,
                 span,
-            } => {
-                report = report
-                    .with_code("type-mismatch")
-                    .with_message(format!(
-                        "Expected type `{}`, but found `{}`.",
-                        expected.cyan().bold(),
-                        found.cyan().bold()
-                    ))
-                    .with_label(
-                        Label::new((filename, span.start..span.end))
-                            .with_message(format!("Found type `{}`.", found.cyan().bold()))
-                            .with_color(Color::Magenta),
-                    )
-            }
+            } => Diagnostic::new(
+                "type-mismatch",
+                format!("Expected type `{}`, but found `{}`.", expected, found),
+            )
+            .with_primary(span.clone(), format!("Found type `{}`.", found)),
             Error::UndefinedSymbol { name, span } => {
-                report = report
-                    .with_code("undefined-symbol")
-                    .with_message(format!("Undefined symbol `{}`.", name.cyan().bold()))
-                    .with_label(
-                        Label::new((filename, span.start..span.end))
-                            .with_message(format!("`{}` is not defined.", name.cyan().bold()))
-                            .with_color(Color::Magenta),
-                    )
+                Diagnostic::new("undefined-symbol", format!("Undefined symbol `{}`.", name))
+                    .with_primary(span.clone(), format!("`{}` is not defined.", name))
             }
+            Error::BranchMismatch {
+                then_type,
+                else_type,
+                then_span,
+                else_span,
+            } => Diagnostic::new(
+                "branch-mismatch",
+                format!(
+                    "Branches of `if` have types `{}` and `{}`.",
+                    then_type, else_type
+                ),
+            )
+            .with_secondary(
+                then_span.clone(),
+                format!("This branch has type `{}`.", then_type),
+            )
+            .with_primary(
+                else_span.clone(),
+                format!("But this one has type `{}`.", else_type),
+            ),
+            Error::NotAProposition { statement, span } => Diagnostic::new(
+                "not-a-proposition",
+                format!("`{}` is not a proposition.", statement),
+            )
+            .with_primary(
+                span.clone(),
+                "A theorem states a proposition, built out of atoms and `False`.",
+            ),
+            Error::UnannotatedLambda { span } => Diagnostic::new(
+                "unannotated-lambda",
+                "Cannot infer type of lambda; add an annotation.",
+            )
+            .with_primary(span.clone(), "Nothing here gives the type of its parameter.")
+            .with_note("Annotate the parameter as in `λx:T. e`, or ascribe a type as in `(λx. e : T -> U)`."),
+            Error::GeneralRecursion { span } => Diagnostic::new(
+                "general-recursion",
+                "General recursion is not a valid proof step.",
+            )
+            .with_primary(span.clone(), "This recursion would prove any proposition.")
+            .with_note("Proofs may not use `fix` or `letrec`."),
         }
-        report
-            .finish()
-            .print((filename, Source::from(source)))
-            .unwrap()
     }
 }
@@ -0,0 +1,822 @@
+use crate::ast::*;
+use crate::symbol::Symbol;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+
+// Both evaluators follow a call-by-value strategy and expect a closed,
+// well-typed expression: a stuck term is a bug of the type checker, not an
+// error of the program.
+
+pub type Environment = HashMap<Symbol, Value>;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Closure {
+        variable: Variable,
+        typ: Option<Type>,
+        body: Expression,
+        env: Environment,
+    },
+    Pair(Box<Value>, Box<Value>),
+    /// `fix v`, unfolded every time the variable bound to it is looked up.
+    Fixpoint(Rc<Value>),
+    Left(Box<Value>, Type),
+    Right(Box<Value>, Type),
+    Int(i32),
+    Bool(bool),
+    Unit,
+}
+
+impl Value {
+    /// Turns the value back into a closed expression by substituting the
+    /// captured environment into closure bodies.
+    pub fn into_expression(self) -> Expression {
+        match self {
+            Value::Closure {
+                variable,
+                typ,
+                body,
+                env,
+            } => {
+                let body = env
+                    .into_iter()
+                    .filter(|(name, _)| *name != variable.name)
+                    .fold(body, |body, (name, value)| {
+                        substitute(body, &name, &value.into_expression())
+                    });
+                let span = variable.span.start..body.clone().get_span().end;
+                Expression::Abstraction {
+                    variable,
+                    typ,
+                    expression: Box::new(body),
+                    span,
+                }
+            }
+            Value::Pair(first, second) => Expression::Pair {
+                first: Box::new(first.into_expression()),
+                second: Box::new(second.into_expression()),
+                span: 0..0,
+            },
+            Value::Fixpoint(function) => Expression::Fix {
+                expression: Box::new(Rc::unwrap_or_clone(function).into_expression()),
+                span: 0..0,
+            },
+            Value::Left(value, typ) => Expression::Left {
+                expression: Box::new(value.into_expression()),
+                typ,
+                span: 0..0,
+            },
+            Value::Right(value, typ) => Expression::Right {
+                expression: Box::new(value.into_expression()),
+                typ,
+                span: 0..0,
+            },
+            Value::Int(n) => Expression::Int { n, span: 0..0 },
+            Value::Bool(value) => Expression::Bool { value, span: 0..0 },
+            Value::Unit => Expression::Unit { span: 0..0 },
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Unit => write!(f, "()"),
+            value => write!(f, "{}", value.clone().into_expression()),
+        }
+    }
+}
+
+/// A fixpoint whose unfolding needs its own value, as `fix (λx:Int. x)`
+/// does, so that call-by-value evaluation never ends.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// The function `fix` was applied to.
+    pub function: Rc<Value>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fixpoint = Value::Fixpoint(self.function.clone());
+        write!(f, "`{}` diverges", fixpoint)
+    }
+}
+
+/// Big-step semantics: evaluates `expr` to a value in `env`.
+///
+/// `tc eval` reduces programs with [`Reduction`] instead, which can stop
+/// after a number of steps; this evaluator is the oracle the tests compare
+/// the values reached by the small-step semantics with. It only notices the
+/// divergence of a fixpoint unfolding into itself, and never returns on the
+/// other diverging programs.
+pub fn eval(expr: Expression, env: &Environment) -> Result<Value, Divergence> {
+    evaluate(expr, env, &mut Vec::new())
+}
+
+/// Evaluates `expr` in `env` while the fixpoints of `forcing` are unfolded.
+fn evaluate(
+    expr: Expression,
+    env: &Environment,
+    forcing: &mut Vec<Rc<Value>>,
+) -> Result<Value, Divergence> {
+    let value = match expr {
+        Expression::Variable(Variable { name, .. }) => {
+            let value = env
+                .get(&name)
+                .cloned()
+                .unwrap_or_else(|| unreachable!("unbound variable `{}`", name));
+            force(value, forcing)?
+        }
+        Expression::Abstraction {
+            variable,
+            typ,
+            expression,
+            ..
+        } => Value::Closure {
+            variable,
+            typ,
+            body: *expression,
+            env: env.clone(),
+        },
+        Expression::Application { callee, arg, .. } => {
+            let callee = evaluate(*callee, env, forcing)?;
+            let arg = evaluate(*arg, env, forcing)?;
+            if let Value::Closure {
+                variable,
+                body,
+                env: mut closure_env,
+                ..
+            } = callee
+            {
+                closure_env.insert(variable.name, arg);
+                evaluate(body, &closure_env, forcing)?
+            } else {
+                unreachable!("application of a non-function value `{}`", callee)
+            }
+        }
+        Expression::Binary {
+            operator, lhs, rhs, ..
+        } => match (evaluate(*lhs, env, forcing)?, evaluate(*rhs, env, forcing)?) {
+            (Value::Int(n1), Value::Int(n2)) => apply(operator, n1, n2),
+            (v1, v2) => unreachable!(
+                "`{}` applied to non-integer values `{}` and `{}`",
+                operator, v1, v2
+            ),
+        },
+        Expression::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => match evaluate(*condition, env, forcing)? {
+            Value::Bool(true) => evaluate(*then_branch, env, forcing)?,
+            Value::Bool(false) => evaluate(*else_branch, env, forcing)?,
+            value => unreachable!("condition on a non-boolean value `{}`", value),
+        },
+        Expression::Pair { first, second, .. } => Value::Pair(
+            Box::new(evaluate(*first, env, forcing)?),
+            Box::new(evaluate(*second, env, forcing)?),
+        ),
+        Expression::First { pair, .. } => match evaluate(*pair, env, forcing)? {
+            Value::Pair(first, _) => *first,
+            value => unreachable!("projection of a non-pair value `{}`", value),
+        },
+        Expression::Second { pair, .. } => match evaluate(*pair, env, forcing)? {
+            Value::Pair(_, second) => *second,
+            value => unreachable!("projection of a non-pair value `{}`", value),
+        },
+        Expression::Fix { expression, .. } => {
+            let function = evaluate(*expression, env, forcing)?;
+            force(Value::Fixpoint(Rc::new(function)), forcing)?
+        }
+        Expression::Left {
+            expression, typ, ..
+        } => Value::Left(Box::new(evaluate(*expression, env, forcing)?), typ),
+        Expression::Right {
+            expression, typ, ..
+        } => Value::Right(Box::new(evaluate(*expression, env, forcing)?), typ),
+        Expression::Case {
+            expression,
+            left,
+            right,
+            ..
+        } => {
+            let (branch, value) = match evaluate(*expression, env, forcing)? {
+                Value::Left(value, _) => (left, value),
+                Value::Right(value, _) => (right, value),
+                value => unreachable!("case analysis of a non-injected value `{}`", value),
+            };
+            let mut env = env.clone();
+            env.insert(branch.variable.name, *value);
+            evaluate(branch.expression, &env, forcing)?
+        }
+        Expression::Absurd { expression, .. } => {
+            let value = evaluate(*expression, env, forcing)?;
+            unreachable!("proof of `False` evaluated to `{}`", value)
+        }
+        Expression::Int { n, .. } => Value::Int(n),
+        Expression::Bool { value, .. } => Value::Bool(value),
+        Expression::Unit { .. } => Value::Unit,
+        Expression::Ascription { expression, .. } => evaluate(*expression, env, forcing)?,
+    };
+    Ok(value)
+}
+
+/// Unfolds `fix (λf:T. e)` into `e` with `f` bound to the fixpoint itself,
+/// leaving any other value untouched.
+///
+/// A fixpoint looked up again while it is unfolded, which is one of
+/// `forcing`, would be unfolded forever.
+fn force(value: Value, forcing: &mut Vec<Rc<Value>>) -> Result<Value, Divergence> {
+    let Value::Fixpoint(function) = value else {
+        return Ok(value);
+    };
+    if forcing
+        .iter()
+        .any(|fixpoint| Rc::ptr_eq(fixpoint, &function))
+    {
+        return Err(Divergence { function });
+    }
+    match (*function).clone() {
+        Value::Closure {
+            variable,
+            body,
+            mut env,
+            ..
+        } => {
+            env.insert(variable.name, Value::Fixpoint(function.clone()));
+            forcing.push(function);
+            let value = evaluate(body, &env, forcing);
+            forcing.pop();
+            value
+        }
+        value => unreachable!("fixpoint of a non-function value `{}`", value),
+    }
+}
+
+/// Computes `n1 operator n2`, integer operations wrapping on overflow.
+fn apply(operator: Operator, n1: i32, n2: i32) -> Value {
+    match operator {
+        Operator::Add => Value::Int(n1.wrapping_add(n2)),
+        Operator::Sub => Value::Int(n1.wrapping_sub(n2)),
+        Operator::Mul => Value::Int(n1.wrapping_mul(n2)),
+        Operator::Eq => Value::Bool(n1 == n2),
+        Operator::Lt => Value::Bool(n1 < n2),
+    }
+}
+
+pub fn is_value(expr: &Expression) -> bool {
+    match expr {
+        Expression::Abstraction { .. }
+        | Expression::Int { .. }
+        | Expression::Bool { .. }
+        | Expression::Unit { .. } => true,
+        Expression::Pair { first, second, .. } => is_value(first) && is_value(second),
+        Expression::Left { expression, .. } | Expression::Right { expression, .. } => {
+            is_value(expression)
+        }
+        _ => false,
+    }
+}
+
+/// Small-step semantics: performs a single reduction, or returns `None` if
+/// `expr` is a value.
+pub fn step(expr: &Expression) -> Option<Expression> {
+    let mut reduction = Reduction::new(expr.clone());
+    reduction.step().then(|| reduction.into_expression())
+}
+
+/// A program being reduced one step at a time, split into the evaluation
+/// context and the subexpression in focus.
+///
+/// A step only rewrites the redex under the focus, so neither the time it
+/// takes nor the stack it needs grow with the context around it.
+pub struct Reduction {
+    /// The frames around the focus, the innermost last.
+    context: Vec<Frame>,
+    /// Either a value with no frame around it, or an expression that is not
+    /// a value.
+    focus: Expression,
+}
+
+impl Reduction {
+    pub fn new(expr: Expression) -> Self {
+        Self {
+            context: Vec::new(),
+            focus: expr,
+        }
+    }
+
+    pub fn is_value(&self) -> bool {
+        self.context.is_empty() && is_value(&self.focus)
+    }
+
+    /// Performs a single reduction, or returns `false` if the program is a
+    /// value.
+    pub fn step(&mut self) -> bool {
+        if self.is_value() {
+            return false;
+        }
+        let mut focus = std::mem::replace(&mut self.focus, Expression::Unit { span: 0..0 });
+        loop {
+            match split(focus) {
+                Ok((frame, hole)) => {
+                    self.context.push(frame);
+                    focus = hole;
+                }
+                Err(redex) => {
+                    focus = contract(redex);
+                    break;
+                }
+            }
+        }
+        while is_value(&focus) {
+            let Some(frame) = self.context.pop() else {
+                break;
+            };
+            focus = frame.plug(focus);
+        }
+        self.focus = focus;
+        true
+    }
+
+    /// The program reached so far.
+    pub fn expression(&self) -> Expression {
+        self.context
+            .iter()
+            .rev()
+            .fold(self.focus.clone(), |expr, frame| frame.clone().plug(expr))
+    }
+
+    pub fn into_expression(self) -> Expression {
+        self.context
+            .into_iter()
+            .rev()
+            .fold(self.focus, |expr, frame| frame.plug(expr))
+    }
+}
+
+/// An expression whose subexpression in evaluation position is a hole, to
+/// be plugged with that subexpression once reduced.
+#[derive(Debug, Clone)]
+enum Frame {
+    Callee {
+        arg: Box<Expression>,
+        span: Range<usize>,
+    },
+    Argument {
+        callee: Box<Expression>,
+        span: Range<usize>,
+    },
+    Lhs {
+        operator: Operator,
+        rhs: Box<Expression>,
+        span: Range<usize>,
+    },
+    Rhs {
+        operator: Operator,
+        lhs: Box<Expression>,
+        span: Range<usize>,
+    },
+    Condition {
+        then_branch: Box<Expression>,
+        else_branch: Box<Expression>,
+        span: Range<usize>,
+    },
+    PairFirst {
+        second: Box<Expression>,
+        span: Range<usize>,
+    },
+    PairSecond {
+        first: Box<Expression>,
+        span: Range<usize>,
+    },
+    First {
+        span: Range<usize>,
+    },
+    Second {
+        span: Range<usize>,
+    },
+    Fix {
+        span: Range<usize>,
+    },
+    Left {
+        typ: Type,
+        span: Range<usize>,
+    },
+    Right {
+        typ: Type,
+        span: Range<usize>,
+    },
+    Case {
+        left: Box<Branch>,
+        right: Box<Branch>,
+        span: Range<usize>,
+    },
+    Absurd {
+        typ: Type,
+        span: Range<usize>,
+    },
+    Ascription {
+        typ: Type,
+        span: Range<usize>,
+    },
+}
+
+impl Frame {
+    /// Fills the hole with `expr`.
+    fn plug(self, expr: Expression) -> Expression {
+        let hole = Box::new(expr);
+        match self {
+            Frame::Callee { arg, span } => Expression::Application {
+                callee: hole,
+                arg,
+                span,
+            },
+            Frame::Argument { callee, span } => Expression::Application {
+                callee,
+                arg: hole,
+                span,
+            },
+            Frame::Lhs {
+                operator,
+                rhs,
+                span,
+            } => Expression::Binary {
+                operator,
+                lhs: hole,
+                rhs,
+                span,
+            },
+            Frame::Rhs {
+                operator,
+                lhs,
+                span,
+            } => Expression::Binary {
+                operator,
+                lhs,
+                rhs: hole,
+                span,
+            },
+            Frame::Condition {
+                then_branch,
+                else_branch,
+                span,
+            } => Expression::If {
+                condition: hole,
+                then_branch,
+                else_branch,
+                span,
+            },
+            Frame::PairFirst { second, span } => Expression::Pair {
+                first: hole,
+                second,
+                span,
+            },
+            Frame::PairSecond { first, span } => Expression::Pair {
+                first,
+                second: hole,
+                span,
+            },
+            Frame::First { span } => Expression::First { pair: hole, span },
+            Frame::Second { span } => Expression::Second { pair: hole, span },
+            Frame::Fix { span } => Expression::Fix {
+                expression: hole,
+                span,
+            },
+            Frame::Left { typ, span } => Expression::Left {
+                expression: hole,
+                typ,
+                span,
+            },
+            Frame::Right { typ, span } => Expression::Right {
+                expression: hole,
+                typ,
+                span,
+            },
+            Frame::Case { left, right, span } => Expression::Case {
+                expression: hole,
+                left,
+                right,
+                span,
+            },
+            Frame::Absurd { typ, span } => Expression::Absurd {
+                expression: hole,
+                typ,
+                span,
+            },
+            Frame::Ascription { typ, span } => Expression::Ascription {
+                expression: hole,
+                typ,
+                span,
+            },
+        }
+    }
+}
+
+/// Splits `expr` into a frame and the subexpression in evaluation position
+/// that is not yet a value, or gives `expr` back if there is none: it is
+/// then a value or a redex.
+fn split(expr: Expression) -> Result<(Frame, Expression), Expression> {
+    let (frame, hole) = match expr {
+        Expression::Application { callee, arg, span } if !is_value(&callee) => {
+            (Frame::Callee { arg, span }, callee)
+        }
+        Expression::Application { callee, arg, span } if !is_value(&arg) => {
+            (Frame::Argument { callee, span }, arg)
+        }
+        Expression::Binary {
+            operator,
+            lhs,
+            rhs,
+            span,
+        } if !is_value(&lhs) => (
+            Frame::Lhs {
+                operator,
+                rhs,
+                span,
+            },
+            lhs,
+        ),
+        Expression::Binary {
+            operator,
+            lhs,
+            rhs,
+            span,
+        } if !is_value(&rhs) => (
+            Frame::Rhs {
+                operator,
+                lhs,
+                span,
+            },
+            rhs,
+        ),
+        Expression::If {
+            condition,
+            then_branch,
+            else_branch,
+            span,
+        } if !is_value(&condition) => (
+            Frame::Condition {
+                then_branch,
+                else_branch,
+                span,
+            },
+            condition,
+        ),
+        Expression::Pair {
+            first,
+            second,
+            span,
+        } if !is_value(&first) => (Frame::PairFirst { second, span }, first),
+        Expression::Pair {
+            first,
+            second,
+            span,
+        } if !is_value(&second) => (Frame::PairSecond { first, span }, second),
+        Expression::First { pair, span } if !is_value(&pair) => (Frame::First { span }, pair),
+        Expression::Second { pair, span } if !is_value(&pair) => (Frame::Second { span }, pair),
+        Expression::Fix { expression, span } if !is_value(&expression) => {
+            (Frame::Fix { span }, expression)
+        }
+        Expression::Left {
+            expression,
+            typ,
+            span,
+        } if !is_value(&expression) => (Frame::Left { typ, span }, expression),
+        Expression::Right {
+            expression,
+            typ,
+            span,
+        } if !is_value(&expression) => (Frame::Right { typ, span }, expression),
+        Expression::Case {
+            expression,
+            left,
+            right,
+            span,
+        } if !is_value(&expression) => (Frame::Case { left, right, span }, expression),
+        Expression::Absurd {
+            expression,
+            typ,
+            span,
+        } if !is_value(&expression) => (Frame::Absurd { typ, span }, expression),
+        Expression::Ascription {
+            expression,
+            typ,
+            span,
+        } if !is_value(&expression) => (Frame::Ascription { typ, span }, expression),
+        expr => return Err(expr),
+    };
+    Ok((frame, *hole))
+}
+
+/// Reduces the redex `expr`, whose subexpressions in evaluation position
+/// are all values.
+fn contract(expr: Expression) -> Expression {
+    match expr {
+        Expression::Application { callee, arg, .. } => match *callee {
+            Expression::Abstraction {
+                variable,
+                expression,
+                ..
+            } => substitute(*expression, &variable.name, &arg),
+            callee => unreachable!("application of a non-function value `{}`", callee),
+        },
+        Expression::Binary {
+            operator, lhs, rhs, ..
+        } => match (*lhs, *rhs) {
+            (Expression::Int { n: n1, .. }, Expression::Int { n: n2, .. }) => {
+                apply(operator, n1, n2).into_expression()
+            }
+            (lhs, rhs) => unreachable!(
+                "`{}` applied to non-integer values `{}` and `{}`",
+                operator, lhs, rhs
+            ),
+        },
+        Expression::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => match *condition {
+            Expression::Bool { value: true, .. } => *then_branch,
+            Expression::Bool { value: false, .. } => *else_branch,
+            condition => unreachable!("condition on a non-boolean value `{}`", condition),
+        },
+        Expression::First { pair, .. } => match *pair {
+            Expression::Pair { first, .. } => *first,
+            pair => unreachable!("projection of a non-pair value `{}`", pair),
+        },
+        Expression::Second { pair, .. } => match *pair {
+            Expression::Pair { second, .. } => *second,
+            pair => unreachable!("projection of a non-pair value `{}`", pair),
+        },
+        Expression::Fix { ref expression, .. } => match &**expression {
+            Expression::Abstraction {
+                variable,
+                expression: body,
+                ..
+            } => substitute(*body.clone(), &variable.name, &expr),
+            function => unreachable!("fixpoint of a non-function value `{}`", function),
+        },
+        Expression::Case {
+            expression,
+            left,
+            right,
+            ..
+        } => match *expression {
+            Expression::Left { expression, .. } => {
+                substitute(left.expression, &left.variable.name, &expression)
+            }
+            Expression::Right { expression, .. } => {
+                substitute(right.expression, &right.variable.name, &expression)
+            }
+            value => unreachable!("case analysis of a non-injected value `{}`", value),
+        },
+        Expression::Absurd { expression, .. } => {
+            unreachable!("proof of `False` reduced to `{}`", expression)
+        }
+        Expression::Ascription { expression, .. } => *expression,
+        Expression::Variable(Variable { name, .. }) => unreachable!("unbound variable `{}`", name),
+        value => unreachable!("reduction of the value `{}`", value),
+    }
+}
+
+/// Replaces the free occurrences of `name` in `expr` with `value`.
+///
+/// Values substituted during the evaluation of a closed program are
+/// themselves closed, so no variable capture can happen.
+pub fn substitute(expr: Expression, name: &Symbol, value: &Expression) -> Expression {
+    match expr {
+        Expression::Variable(Variable { name: n, .. }) if n == *name => value.clone(),
+        Expression::Abstraction {
+            variable,
+            typ,
+            expression,
+            span,
+        } => {
+            let expression = if variable.name == *name {
+                expression
+            } else {
+                Box::new(substitute(*expression, name, value))
+            };
+            Expression::Abstraction {
+                variable,
+                typ,
+                expression,
+                span,
+            }
+        }
+        Expression::Application { callee, arg, span } => Expression::Application {
+            callee: Box::new(substitute(*callee, name, value)),
+            arg: Box::new(substitute(*arg, name, value)),
+            span,
+        },
+        Expression::Binary {
+            operator,
+            lhs,
+            rhs,
+            span,
+        } => Expression::Binary {
+            operator,
+            lhs: Box::new(substitute(*lhs, name, value)),
+            rhs: Box::new(substitute(*rhs, name, value)),
+            span,
+        },
+        Expression::If {
+            condition,
+            then_branch,
+            else_branch,
+            span,
+        } => Expression::If {
+            condition: Box::new(substitute(*condition, name, value)),
+            then_branch: Box::new(substitute(*then_branch, name, value)),
+            else_branch: Box::new(substitute(*else_branch, name, value)),
+            span,
+        },
+        Expression::Pair {
+            first,
+            second,
+            span,
+        } => Expression::Pair {
+            first: Box::new(substitute(*first, name, value)),
+            second: Box::new(substitute(*second, name, value)),
+            span,
+        },
+        Expression::First { pair, span } => Expression::First {
+            pair: Box::new(substitute(*pair, name, value)),
+            span,
+        },
+        Expression::Second { pair, span } => Expression::Second {
+            pair: Box::new(substitute(*pair, name, value)),
+            span,
+        },
+        Expression::Fix { expression, span } => Expression::Fix {
+            expression: Box::new(substitute(*expression, name, value)),
+            span,
+        },
+        Expression::Left {
+            expression,
+            typ,
+            span,
+        } => Expression::Left {
+            expression: Box::new(substitute(*expression, name, value)),
+            typ,
+            span,
+        },
+        Expression::Right {
+            expression,
+            typ,
+            span,
+        } => Expression::Right {
+            expression: Box::new(substitute(*expression, name, value)),
+            typ,
+            span,
+        },
+        Expression::Case {
+            expression,
+            left,
+            right,
+            span,
+        } => {
+            let substitute_branch = |branch: Box<Branch>| {
+                if branch.variable.name == *name {
+                    branch
+                } else {
+                    Box::new(Branch {
+                        expression: substitute(branch.expression, name, value),
+                        variable: branch.variable,
+                    })
+                }
+            };
+            Expression::Case {
+                expression: Box::new(substitute(*expression, name, value)),
+                left: substitute_branch(left),
+                right: substitute_branch(right),
+                span,
+            }
+        }
+        Expression::Absurd {
+            expression,
+            typ,
+            span,
+        } => Expression::Absurd {
+            expression: Box::new(substitute(*expression, name, value)),
+            typ,
+            span,
+        },
+        Expression::Ascription {
+            expression,
+            typ,
+            span,
+        } => Expression::Ascription {
+            expression: Box::new(substitute(*expression, name, value)),
+            typ,
+            span,
+        },
+        expr => expr,
+    }
+}
@@ -0,0 +1,8 @@
+#![feature(trait_alias)]
+pub mod ast;
+pub mod derivation;
+pub mod error;
+pub mod evaluator;
+pub mod parser;
+pub mod symbol;
+pub mod typechecker;
@@ -1,62 +1,404 @@
 use crate::ast::*;
+use crate::symbol::Symbol;
+use chumsky::error::SimpleReason;
 use chumsky::prelude::*;
+use std::ops::Range;
 
-pub trait STLCParser = Parser<char, Expression, Error = Simple<char>>;
+pub trait STLCParser<T = Expression> = Parser<char, T, Error = Simple<char>> + Clone;
 
-pub fn parser() -> impl STLCParser {
-    let typ = recursive(|typ| {
-        let base_type = choice((
-            just("Unit").to(Type::Unit),
-            just("Int").to(Type::Int),
-            typ.clone().delimited_by(just('('), just(')')),
-        ))
-        .padded();
+/// Parses `source`, recovering from syntax errors so that every one of them
+/// is reported rather than only the first.
+pub fn parse(source: &str) -> Result<Expression, Vec<Simple<char>>> {
+    match parser().parse_recovery(source) {
+        (Some(ast), errors) if errors.is_empty() => Ok(ast),
+        (_, errors) => Err(errors),
+    }
+}
+
+/// Parses the theorems of a proof script, recovering from syntax errors as
+/// [`parse`] does.
+pub fn parse_theorems(source: &str) -> Result<Vec<Theorem>, Vec<Simple<char>>> {
+    match theorem()
+        .repeated()
+        .then_ignore(end())
+        .parse_recovery(source)
+    {
+        (Some(theorems), errors) if errors.is_empty() => Ok(theorems),
+        (_, errors) => Err(errors),
+    }
+}
+
+/// Words reserved by the syntax, which cannot be used as variables.
+pub const KEYWORDS: &[&str] = &[
+    "absurd", "as", "case", "else", "false", "fix", "fst", "if", "in", "inl", "inr", "letrec",
+    "of", "snd", "theorem", "then", "true",
+];
+
+/// Whether `name` can be bound as a variable.
+pub fn is_variable(name: &str) -> bool {
+    identifier().then_ignore(end()).parse(name).is_ok() && !KEYWORDS.contains(&name)
+}
+
+/// A lowercase letter or `_`, followed by letters, digits, `_` and `'`.
+fn identifier() -> impl STLCParser<String> {
+    filter(|c: &char| c.is_ascii_lowercase() || *c == '_')
+        .chain(filter(|c: &char| c.is_ascii_alphanumeric() || *c == '_' || *c == '\'').repeated())
+        .collect()
+}
+
+/// An uppercase letter, followed by letters, digits, `_` and `'`: a base
+/// type or an atom.
+fn type_name() -> impl STLCParser<Type> {
+    filter(|c: &char| c.is_ascii_uppercase())
+        .chain(filter(|c: &char| c.is_ascii_alphanumeric() || *c == '_' || *c == '\'').repeated())
+        .collect::<String>()
+        .map(|name| match name.as_str() {
+            "Unit" => Type::Unit,
+            "Int" => Type::Int,
+            "Bool" => Type::Bool,
+            "False" => Type::Empty,
+            _ => Type::Atom(Symbol::intern(&name)),
+        })
+        .labelled("a type name")
+}
+
+/// The keyword `k`, which must not run into the letters after it.
+fn word(k: &'static str) -> impl STLCParser<()> {
+    debug_assert!(KEYWORDS.contains(&k), "`{}` is not a keyword", k);
+    identifier()
+        .try_map(move |name, span| {
+            if name == k {
+                Ok(())
+            } else {
+                Err(diagnostic::expected_keyword(span, k))
+            }
+        })
+        .map_err(move |error| diagnostic::expected_keyword(error.span(), k))
+}
+
+fn keyword(k: &'static str) -> impl STLCParser<()> {
+    word(k).padded()
+}
+
+fn variable() -> impl STLCParser<Variable> {
+    identifier()
+        .try_map(|name, span| {
+            if KEYWORDS.contains(&name.as_str()) {
+                Err(Simple::custom(
+                    span,
+                    format!("`{}` is a keyword, not a variable", name),
+                ))
+            } else {
+                Ok(Variable {
+                    name: Symbol::intern(&name),
+                    span,
+                })
+            }
+        })
+        .labelled("a variable")
+        .padded()
+}
+
+/// Folds `[t1, t2, t3]` into `t1 op (t2 op t3)`.
+fn fold_right(types: Vec<Type>, op: fn(Box<Type>, Box<Type>) -> Type) -> Type {
+    types
+        .into_iter()
+        .rev()
+        .reduce(|rhs, lhs| op(Box::new(lhs), Box::new(rhs)))
+        .unwrap()
+}
 
-        let function_type = base_type
+/// Builds `lhs operator rhs`, spanning both operands.
+fn binary(lhs: Expression, (operator, rhs): (Operator, Expression)) -> Expression {
+    Expression::Binary {
+        span: lhs.get_span().start..rhs.get_span().end,
+        operator,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    }
+}
+
+/// Reports the end of the input within parentheses, `span` starting at the
+/// opening one, as this parenthesis being left unclosed.
+fn unclosed(error: Simple<char>, span: Range<usize>) -> Simple<char> {
+    let within = span.start < error.span().start;
+    if !within
+        || error.found().is_some()
+        || matches!(
+            error.reason(),
+            SimpleReason::Unclosed { .. } | SimpleReason::Custom(_)
+        )
+    {
+        return error;
+    }
+    Simple::unclosed_delimiter(span.start..span.start + 1, '(', error.span(), ')', None)
+        .merge(error)
+}
+
+/// Stands for every alternative of an expression failing at its start, as
+/// only some of them announce the token they start with, with the label
+/// `an expression` rather than a partial list of tokens.
+fn expected_expression(error: Simple<char>, span: Range<usize>) -> Simple<char> {
+    let unexpected = *error.reason() == SimpleReason::Unexpected
+        || diagnostic::missing_keyword(&error).is_some();
+    if error.span().start != span.start || !unexpected {
+        return error;
+    }
+    Simple::expected_input_found(error.span(), [], error.found().copied())
+        .with_label("an expression")
+}
+
+/// What follows the first expression within parentheses.
+enum Parenthesized {
+    /// `(e1, e2)`
+    Pair(Expression),
+    /// `(e : T)`
+    Ascription(Type),
+}
+
+/// Turns `letrec f : T = e in b` into `(λf:T. b) (fix (λf:T. e))`.
+fn desugar_letrec(
+    variable: Variable,
+    typ: Type,
+    definition: Expression,
+    body: Expression,
+    span: Range<usize>,
+) -> Expression {
+    let definition_span = definition.get_span();
+    let body_span = body.get_span();
+    Expression::Application {
+        callee: Box::new(Expression::Abstraction {
+            variable: variable.clone(),
+            typ: Some(typ.clone()),
+            expression: Box::new(body),
+            span: body_span,
+        }),
+        arg: Box::new(Expression::Fix {
+            expression: Box::new(Expression::Abstraction {
+                variable,
+                typ: Some(typ),
+                expression: Box::new(definition),
+                span: definition_span.clone(),
+            }),
+            span: definition_span,
+        }),
+        span,
+    }
+}
+
+pub fn typ() -> impl STLCParser<Type> {
+    recursive(|typ| {
+        let base_type = recursive(|base_type| {
+            choice((
+                type_name(),
+                typ.clone().delimited_by(just('('), just(')')),
+                just('~')
+                    .padded()
+                    .ignore_then(base_type)
+                    .map(|negated| Type::Function(Box::new(negated), Box::new(Type::Empty))),
+            ))
+            .padded()
+        });
+
+        let product_type = base_type
+            .separated_by(choice((just("×"), just("*"), just("/\\"))).padded())
+            .at_least(1)
+            .map(|types| fold_right(types, Type::Product));
+
+        let sum_type = product_type
+            .separated_by(just("+").or(just("\\/")).padded())
+            .at_least(1)
+            .map(|types| fold_right(types, Type::Sum));
+
+        let function_type = sum_type
             .clone()
             .then_ignore(just("->").padded())
             .then(typ)
             .map(|(lhs, rhs)| Type::Function(Box::new(lhs), Box::new(rhs)));
 
-        function_type.or(base_type)
-    });
+        function_type.or(sum_type)
+    })
+}
+
+pub fn parser() -> impl STLCParser {
+    expression().then_ignore(end())
+}
 
-    let variable = filter(|l: &char| l.is_ascii_lowercase())
-        .map_with_span(|l, span| Variable { name: l, span })
-        .padded();
+/// An expression, along with the blanks around it.
+pub fn expression() -> impl STLCParser {
+    let typ = typ();
+    let variable = variable();
 
-    let expr = recursive(|expr| {
-        let atom = choice((
-            // Unit
-            just("()").map_with_span(|_, span| Expression::Unit { span }),
-            // Int
-            text::int(10).map_with_span(|n: String, span| Expression::Int {
-                _n: n.parse::<i32>().unwrap(),
+    recursive(|expr| {
+        let atom = recursive(|atom| {
+            choice((
+                // Unit
+                just("()").map_with_span(|_, span| Expression::Unit { span }),
+                // Int
+                text::int(10).try_map(|n: String, span: Range<usize>| match n.parse::<i32>() {
+                    Ok(n) => Ok(Expression::Int { n, span }),
+                    Err(_) => Err(Simple::custom(span, "integer literal out of range")),
+                }),
+                // Bool
+                word("true").map_with_span(|_, span| Expression::Bool { value: true, span }),
+                word("false").map_with_span(|_, span| Expression::Bool { value: false, span }),
+                // Fst
+                keyword("fst")
+                    .ignore_then(atom.clone())
+                    .map_with_span(|pair, span| Expression::First {
+                        pair: Box::new(pair),
+                        span,
+                    }),
+                // Snd
+                keyword("snd")
+                    .ignore_then(atom.clone())
+                    .map_with_span(|pair, span| Expression::Second {
+                        pair: Box::new(pair),
+                        span,
+                    }),
+                // Fix
+                keyword("fix")
+                    .ignore_then(atom)
+                    .map_with_span(|expression, span| Expression::Fix {
+                        expression: Box::new(expression),
+                        span,
+                    }),
+                // Variable
+                variable.clone().map(Expression::Variable),
+                // Pair, Ascription and Priority, sharing the parse of the
+                // first expression
+                expr.clone()
+                    .then(
+                        choice((
+                            just(',').ignore_then(expr.clone()).map(Parenthesized::Pair),
+                            just(':')
+                                .ignore_then(typ.clone())
+                                .map(Parenthesized::Ascription),
+                        ))
+                        .or_not(),
+                    )
+                    .delimited_by(just('('), just(')'))
+                    .map_err_with_span(unclosed)
+                    .map_with_span(|(first, rest), span| match rest {
+                        Some(Parenthesized::Pair(second)) => Expression::Pair {
+                            first: Box::new(first),
+                            second: Box::new(second),
+                            span,
+                        },
+                        Some(Parenthesized::Ascription(typ)) => Expression::Ascription {
+                            expression: Box::new(first),
+                            typ,
+                            span,
+                        },
+                        None => first,
+                    }),
+            ))
+            // A parenthesized expression that does not parse is skipped up to
+            // its closing parenthesis. The unit standing for it is never
+            // checked since the parse failed.
+            .map_err_with_span(expected_expression)
+            .recover_with(nested_delimiters('(', ')', [], |span| Expression::Unit {
                 span,
-            }),
-            // Variable
-            variable.map(|var| Expression::Variable(var)),
-            // Priority
-            expr.clone().delimited_by(just('('), just(')')),
-        ))
-        .padded();
+            }))
+            .padded()
+        });
+
+        let branch = |side| {
+            keyword(side)
+                .ignore_then(variable.clone())
+                .then_ignore(just("=>").padded())
+                .then(expr.clone())
+                .map(|(variable, expression)| Branch {
+                    variable,
+                    expression,
+                })
+        };
 
-        let op = choice((
-            // Sum
+        let application =
             atom.clone()
-                .then_ignore(just('+').padded())
+                .then(atom.clone().repeated())
+                .foldl(|e1, e2| Expression::Application {
+                    span: {
+                        let first_span = e1.get_span();
+                        let second_span = e2.get_span();
+
+                        first_span.start..second_span.end
+                    },
+                    callee: Box::new(e1),
+                    arg: Box::new(e2),
+                });
+
+        let product = application
+            .clone()
+            .then(
+                just('*')
+                    .padded()
+                    .to(Operator::Mul)
+                    .then(application)
+                    .repeated(),
+            )
+            .foldl(binary);
+
+        let sum = product
+            .clone()
+            .then(
+                choice((
+                    just('+').padded().to(Operator::Add),
+                    just('-').padded().to(Operator::Sub),
+                ))
+                .then(product)
+                .repeated(),
+            )
+            .foldl(binary);
+
+        let comparison = sum
+            .clone()
+            .then(
+                choice((
+                    just("==").padded().to(Operator::Eq),
+                    just('<').padded().to(Operator::Lt),
+                ))
+                .then(sum)
+                .or_not(),
+            )
+            .map(|(lhs, rhs)| match rhs {
+                Some(rhs) => binary(lhs, rhs),
+                None => lhs,
+            });
+
+        choice((
+            // If
+            keyword("if")
+                .ignore_then(expr.clone())
+                .then_ignore(keyword("then"))
                 .then(expr.clone())
-                .map_with_span(|(lhs, rhs), span| Expression::Addition {
-                    lhs: Box::new(lhs),
-                    rhs: Box::new(rhs),
-                    span,
+                .then_ignore(keyword("else"))
+                .then(expr.clone())
+                .map_with_span(
+                    |((condition, then_branch), else_branch), span| Expression::If {
+                        condition: Box::new(condition),
+                        then_branch: Box::new(then_branch),
+                        else_branch: Box::new(else_branch),
+                        span,
+                    },
+                ),
+            // Letrec
+            keyword("letrec")
+                .ignore_then(variable.clone())
+                .then_ignore(just(':'))
+                .then(typ.clone())
+                .then_ignore(just('='))
+                .then(expr.clone())
+                .then_ignore(keyword("in"))
+                .then(expr.clone())
+                .map_with_span(|(((variable, typ), definition), body), span| {
+                    desugar_letrec(variable, typ, definition, body, span)
                 }),
             // Abs
             just('λ')
                 .or(just('\\'))
-                .ignore_then(variable)
-                .then_ignore(just(':'))
-                .then(typ)
+                .ignore_then(variable.clone())
+                .then(just(':').ignore_then(typ.clone()).or_not())
                 .then_ignore(just('.'))
                 .then(expr.clone())
                 .map_with_span(|((var, r#type), expr), span| Expression::Abstraction {
@@ -65,25 +407,68 @@ pub fn parser() -> impl STLCParser {
                     expression: Box::new(expr),
                     span,
                 }),
-            // App
-            atom.clone()
-                .then(atom.clone().repeated())
-                .foldl(|e1, e2| Expression::Application {
-                    callee: Box::new(e1.clone()),
-                    arg: Box::new(e2.clone()),
-                    span: {
-                        let first_span = e1.get_span();
-                        let second_span = e2.get_span();
-
-                        first_span.start..second_span.end
-                    },
+            // Inl
+            keyword("inl")
+                .ignore_then(atom.clone())
+                .then_ignore(keyword("as"))
+                .then(typ.clone())
+                .map_with_span(|(expression, typ), span| Expression::Left {
+                    expression: Box::new(expression),
+                    typ,
+                    span,
+                }),
+            // Inr
+            keyword("inr")
+                .ignore_then(atom.clone())
+                .then_ignore(keyword("as"))
+                .then(typ.clone())
+                .map_with_span(|(expression, typ), span| Expression::Right {
+                    expression: Box::new(expression),
+                    typ,
+                    span,
+                }),
+            // Case
+            keyword("case")
+                .ignore_then(expr.clone())
+                .then_ignore(keyword("of"))
+                .then(branch("inl"))
+                .then_ignore(just('|').padded())
+                .then(branch("inr"))
+                .map_with_span(|((expression, left), right), span| Expression::Case {
+                    expression: Box::new(expression),
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    span,
+                }),
+            // Absurd
+            keyword("absurd")
+                .ignore_then(atom.clone())
+                .then_ignore(keyword("as"))
+                .then(typ.clone())
+                .map_with_span(|(expression, typ), span| Expression::Absurd {
+                    expression: Box::new(expression),
+                    typ,
+                    span,
                 }),
+            // Operators and applications
+            comparison,
         ))
-        .padded();
-
-        op.or(atom)
+        .map_err_with_span(expected_expression)
+        .padded()
     })
-    .then_ignore(end());
+}
 
-    expr
+pub fn theorem() -> impl STLCParser<Theorem> {
+    keyword("theorem")
+        .ignore_then(variable())
+        .then_ignore(just(':'))
+        .then(typ())
+        .then_ignore(just(":="))
+        .then(expression())
+        .map_with_span(|((name, statement), proof), span| Theorem {
+            name,
+            statement,
+            proof,
+            span,
+        })
 }
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+
+/// An interned identifier, cheap to clone, hash and compare.
+///
+/// Two symbols of the same name share their string, so they compare by
+/// address. The string is freed once the last symbol naming it is dropped.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+#[derive(Default)]
+struct Interner {
+    symbols: HashMap<Box<str>, Weak<str>>,
+    /// The number of entries after the last sweep of the dropped names.
+    swept: usize,
+}
+
+// The names of dropped symbols are swept once the table doubles since the
+// last sweep, so that the language server, which interns every identifier
+// of every edit, only keeps the names still in use.
+static INTERNER: LazyLock<Mutex<Interner>> = LazyLock::new(Default::default);
+
+impl Symbol {
+    pub fn intern(name: &str) -> Self {
+        let mut interner = INTERNER.lock().unwrap();
+        if let Some(symbol) = interner.symbols.get(name).and_then(Weak::upgrade) {
+            return Symbol(symbol);
+        }
+        if interner.symbols.len() >= 2 * interner.swept.max(32) {
+            interner
+                .symbols
+                .retain(|_, symbol| symbol.strong_count() > 0);
+            interner.swept = interner.symbols.len();
+        }
+        let symbol: Arc<str> = name.into();
+        interner
+            .symbols
+            .insert(name.into(), Arc::downgrade(&symbol));
+        Symbol(symbol)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<u8>().hash(state)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
@@ -1,77 +1,502 @@
 use crate::ast::*;
+use crate::derivation::{Derivation, Rule};
 use crate::error::Error;
-use std::collections::HashMap;
+use crate::symbol::Symbol;
+use std::ops::Range;
 
-pub struct TypeChecker(HashMap<char, Type>);
+#[derive(Default, Clone)]
+pub struct TypeChecker {
+    /// The typing context, a stack of bindings where the innermost binding
+    /// of a name shadows the outer ones.
+    context: Vec<(Symbol, Type)>,
+    /// The type of every subexpression and binder checked so far.
+    types: Vec<(Range<usize>, Type)>,
+    /// Whether errors show types as propositions, as they do while a
+    /// theorem is proved.
+    propositional: bool,
+    /// Whether the judgements build their derivations, as they do while
+    /// [`TypeChecker::derive`] runs.
+    derivations: bool,
+}
+
+/// The type an expression was found to have, with the derivation
+/// concluding it when derivations are built.
+struct Judgement {
+    typ: Type,
+    derivation: Option<Derivation>,
+}
+
+impl Judgement {
+    /// Concludes `typ` by `rule` from `premises`, in the context and about
+    /// the expression of `conclusion`.
+    fn new(
+        conclusion: Option<(Vec<(Symbol, Type)>, Expression)>,
+        rule: Rule,
+        typ: Type,
+        premises: Vec<Judgement>,
+    ) -> Self {
+        let derivation = conclusion.map(|(context, expression)| Derivation {
+            rule,
+            context,
+            expression,
+            typ: typ.clone(),
+            premises: premises
+                .into_iter()
+                .filter_map(|premise| premise.derivation)
+                .collect(),
+        });
+        Self { typ, derivation }
+    }
+}
 
 impl TypeChecker {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self::default()
     }
 
+    /// Binds `name` to `typ` for every subsequent inference.
+    pub fn define(&mut self, name: Symbol, typ: Type) {
+        self.context.push((name, typ));
+    }
+
+    /// The type of the innermost subexpression or binder spanning `offset`.
+    ///
+    /// Only the subexpressions checked before an error are known.
+    pub fn type_at(&self, offset: usize) -> Option<&Type> {
+        self.types
+            .iter()
+            .filter(|(span, _)| span.contains(&offset))
+            .min_by_key(|(span, _)| span.len())
+            .map(|(_, typ)| typ)
+    }
+
+    /// Synthesizes the type of `expr`, which must not be an abstraction
+    /// without an annotation.
     pub fn infer(&mut self, expr: Expression) -> Result<Type, Error> {
-        match expr {
-            Expression::Variable(Variable { name, span }) => {
-                if let Some(typ) = self.0.get(&name) {
-                    Ok(typ.clone())
-                } else {
-                    Err(Error::UndefinedSymbol { name, span })
-                }
-            }
+        self.synthesize(expr).map(|judgement| judgement.typ)
+    }
+
+    /// Checks that `expr` has type `typ`, which gives their parameter types
+    /// to the abstractions without an annotation.
+    pub fn check(&mut self, expr: Expression, typ: &Type) -> Result<(), Error> {
+        self.inherit(expr, typ).map(|_| ())
+    }
+
+    /// Checks that the proof of `theorem` has its statement as type, then
+    /// binds its name to the statement for the proofs checked afterwards.
+    pub fn prove(&mut self, theorem: Theorem) -> Result<(), Error> {
+        if !theorem.statement.is_proposition() {
+            return Err(Error::NotAProposition {
+                statement: theorem.statement.to_string(),
+                span: theorem.name.span,
+            });
+        }
+        self.propositional = true;
+        let result = self.check(theorem.proof, &theorem.statement);
+        self.propositional = false;
+        result?;
+        self.define(theorem.name.name, theorem.statement);
+        Ok(())
+    }
+
+    /// Builds the typing derivation of `expr`, whose conclusion is the type
+    /// returned by [`TypeChecker::infer`].
+    pub fn derive(&mut self, expr: Expression) -> Result<Derivation, Error> {
+        self.deriving(|tc| tc.synthesize(expr))
+    }
+
+    /// Builds the typing derivation of `expr` against the type `expected`,
+    /// whose conclusion is the type checked by [`TypeChecker::check`].
+    ///
+    /// The expected type flows into abstractions, conditionals, pairs,
+    /// fixpoints and case analyses; the type of any other expression is
+    /// synthesized and compared with it.
+    pub fn derive_checked(
+        &mut self,
+        expr: Expression,
+        expected: &Type,
+    ) -> Result<Derivation, Error> {
+        self.deriving(|tc| tc.inherit(expr, expected))
+    }
+
+    /// Runs `f` building derivations, and returns the one it concluded.
+    fn deriving(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<Judgement, Error>,
+    ) -> Result<Derivation, Error> {
+        let derivations = std::mem::replace(&mut self.derivations, true);
+        let result = f(self);
+        self.derivations = derivations;
+        Ok(result?
+            .derivation
+            .expect("derivations are built while deriving"))
+    }
+
+    /// The judgement synthesizing the type of `expr`, behind both
+    /// [`TypeChecker::infer`] and [`TypeChecker::derive`].
+    fn synthesize(&mut self, expr: Expression) -> Result<Judgement, Error> {
+        let expr_span = expr.get_span();
+        let conclusion = self.conclusion(&expr);
+        let (rule, typ, premises) = match expr {
+            Expression::Variable(Variable { name, span }) => match self.lookup(&name) {
+                Some(typ) => (Rule::Var, typ.clone(), vec![]),
+                None => return Err(Error::UndefinedSymbol { name, span }),
+            },
+            Expression::Abstraction {
+                typ: None, span, ..
+            } => return Err(Error::UnannotatedLambda { span }),
             Expression::Abstraction {
                 variable,
-                typ,
+                typ: Some(typ),
                 expression,
                 ..
             } => {
-                self.0.insert(variable.name, typ.clone());
-                let ret_type = self.infer(*expression)?;
-                Ok(Type::Function(Box::new(typ), Box::new(ret_type)))
+                self.types.push((variable.span, typ.clone()));
+                let body =
+                    self.bound(variable.name, typ.clone(), |tc| tc.synthesize(*expression))?;
+                let ret_type = body.typ.clone();
+                (
+                    Rule::Abs,
+                    Type::Function(Box::new(typ), Box::new(ret_type)),
+                    vec![body],
+                )
             }
             Expression::Application { callee, arg, .. } => {
-                let callee_type = self.infer(*callee)?;
-                if let Type::Function(head_type, subsequent_type) = callee_type.clone() {
-                    let arg_type = self.infer(*arg.clone())?;
-                    if *head_type == arg_type {
-                        return Ok(*subsequent_type);
-                    } else {
+                let callee_span = callee.get_span();
+                let callee = self.synthesize(*callee)?;
+                match callee.typ.clone() {
+                    Type::Function(head_type, subsequent_type) => {
+                        let arg = self.inherit(*arg, &head_type)?;
+                        (Rule::App, *subsequent_type, vec![callee, arg])
+                    }
+                    typ => {
                         return Err(Error::TypeMismatch {
-                            expected: head_type.to_string(),
-                            found: arg_type.to_string(),
-                            span: arg.get_span(),
-                        });
+                            expected: "Function".to_string(),
+                            found: self.show(&typ),
+                            span: callee_span,
+                        })
                     }
-                } else {
-                    return Err(Error::TypeMismatch {
-                        expected: "Function".to_string(),
-                        found: callee_type.to_string(),
-                        span: arg.get_span(),
+                }
+            }
+            Expression::Binary {
+                operator, lhs, rhs, ..
+            } => {
+                let lhs = self.inherit(*lhs, &Type::Int)?;
+                let rhs = self.inherit(*rhs, &Type::Int)?;
+                let (rule, typ) = match operator {
+                    Operator::Add => (Rule::Add, Type::Int),
+                    Operator::Sub => (Rule::Sub, Type::Int),
+                    Operator::Mul => (Rule::Mul, Type::Int),
+                    Operator::Eq => (Rule::Eq, Type::Bool),
+                    Operator::Lt => (Rule::Lt, Type::Bool),
+                };
+                (rule, typ, vec![lhs, rhs])
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let condition = self.inherit(*condition, &Type::Bool)?;
+                let then_span = then_branch.get_span();
+                let then_branch = self.synthesize(*then_branch)?;
+                let else_span = else_branch.get_span();
+                let else_branch = self.synthesize(*else_branch)?;
+                if then_branch.typ != else_branch.typ {
+                    return Err(Error::BranchMismatch {
+                        then_type: self.show(&then_branch.typ),
+                        else_type: self.show(&else_branch.typ),
+                        then_span,
+                        else_span,
                     });
                 }
+                (
+                    Rule::If,
+                    then_branch.typ.clone(),
+                    vec![condition, then_branch, else_branch],
+                )
+            }
+            Expression::Pair { first, second, .. } => {
+                let first = self.synthesize(*first)?;
+                let second = self.synthesize(*second)?;
+                (
+                    Rule::Pair,
+                    Type::Product(Box::new(first.typ.clone()), Box::new(second.typ.clone())),
+                    vec![first, second],
+                )
+            }
+            Expression::First { pair, .. } => {
+                let (pair, lhs, _) = self.components(*pair)?;
+                (Rule::Fst, lhs, vec![pair])
             }
-            Expression::Addition { lhs, rhs, .. } => {
-                let lhs_type = self.infer(*lhs.clone())?;
-                if Type::Int == lhs_type {
-                    let rhs_type = self.infer(*rhs.clone())?;
-                    if Type::Int == rhs_type {
-                        return Ok(Type::Int);
-                    } else {
+            Expression::Second { pair, .. } => {
+                let (pair, _, rhs) = self.components(*pair)?;
+                (Rule::Snd, rhs, vec![pair])
+            }
+            Expression::Fix { span, .. } if self.propositional => {
+                return Err(Error::GeneralRecursion { span });
+            }
+            Expression::Fix { expression, .. } => {
+                let span = expression.get_span();
+                let function = self.synthesize(*expression)?;
+                match function.typ.clone() {
+                    Type::Function(param, ret) if param == ret => {
+                        (Rule::Fix, *param, vec![function])
+                    }
+                    Type::Function(param, _) => {
                         return Err(Error::TypeMismatch {
-                            expected: Type::Int.to_string(),
-                            found: rhs_type.to_string(),
-                            span: rhs.get_span(),
-                        });
+                            expected: self.show(&Type::Function(param.clone(), param)),
+                            found: self.show(&function.typ),
+                            span,
+                        })
                     }
-                } else {
+                    _ => {
+                        return Err(Error::TypeMismatch {
+                            expected: "Function".to_string(),
+                            found: self.show(&function.typ),
+                            span,
+                        })
+                    }
+                }
+            }
+            Expression::Left {
+                expression,
+                typ,
+                span,
+            } => {
+                let (lhs, _) = self.alternatives(&typ, span)?;
+                let inner = self.inherit(*expression, &lhs)?;
+                (Rule::Inl, typ, vec![inner])
+            }
+            Expression::Right {
+                expression,
+                typ,
+                span,
+            } => {
+                let (_, rhs) = self.alternatives(&typ, span)?;
+                let inner = self.inherit(*expression, &rhs)?;
+                (Rule::Inr, typ, vec![inner])
+            }
+            Expression::Case {
+                expression,
+                left,
+                right,
+                ..
+            } => {
+                let span = expression.get_span();
+                let scrutinee = self.synthesize(*expression)?;
+                let (lhs, rhs) = self.branches(&scrutinee.typ, span, &left, &right)?;
+                let left = self.bound(left.variable.name.clone(), lhs, |tc| {
+                    tc.synthesize(left.expression)
+                })?;
+                let right = self.bound(right.variable.name.clone(), rhs, |tc| {
+                    tc.inherit(right.expression, &left.typ)
+                })?;
+                (Rule::Case, left.typ.clone(), vec![scrutinee, left, right])
+            }
+            Expression::Absurd {
+                expression, typ, ..
+            } => {
+                let proof = self.inherit(*expression, &Type::Empty)?;
+                (Rule::Absurd, typ, vec![proof])
+            }
+            Expression::Int { .. } => (Rule::Int, Type::Int, vec![]),
+            Expression::Bool { value: true, .. } => (Rule::True, Type::Bool, vec![]),
+            Expression::Bool { value: false, .. } => (Rule::False, Type::Bool, vec![]),
+            Expression::Unit { .. } => (Rule::Unit, Type::Unit, vec![]),
+            Expression::Ascription {
+                expression, typ, ..
+            } => {
+                let inner = self.inherit(*expression, &typ)?;
+                (Rule::Ascribe, typ, vec![inner])
+            }
+        };
+        self.types.push((expr_span, typ.clone()));
+        Ok(Judgement::new(conclusion, rule, typ, premises))
+    }
+
+    /// The judgement checking `expr` against the type `expected`, behind
+    /// both [`TypeChecker::check`] and [`TypeChecker::derive_checked`].
+    fn inherit(&mut self, expr: Expression, expected: &Type) -> Result<Judgement, Error> {
+        let expr_span = expr.get_span();
+        let conclusion = self.conclusion(&expr);
+        let (rule, premises) = match (expr, expected) {
+            (
+                Expression::Abstraction {
+                    variable,
+                    typ,
+                    expression,
+                    ..
+                },
+                Type::Function(param, ret),
+            ) if typ.as_ref().is_none_or(|typ| typ == &**param) => {
+                self.types.push((variable.span, (**param).clone()));
+                let body = self.bound(variable.name, (**param).clone(), |tc| {
+                    tc.inherit(*expression, ret)
+                })?;
+                (Rule::Abs, vec![body])
+            }
+            (
+                Expression::Abstraction {
+                    typ: None, span, ..
+                },
+                _,
+            ) => {
+                return Err(Error::TypeMismatch {
+                    expected: self.show(expected),
+                    found: "Function".to_string(),
+                    span,
+                })
+            }
+            (
+                Expression::If {
+                    condition,
+                    then_branch,
+                    else_branch,
+                    ..
+                },
+                _,
+            ) => {
+                let condition = self.inherit(*condition, &Type::Bool)?;
+                let then_branch = self.inherit(*then_branch, expected)?;
+                let else_branch = self.inherit(*else_branch, expected)?;
+                (Rule::If, vec![condition, then_branch, else_branch])
+            }
+            (Expression::Pair { first, second, .. }, Type::Product(lhs, rhs)) => {
+                let first = self.inherit(*first, lhs)?;
+                let second = self.inherit(*second, rhs)?;
+                (Rule::Pair, vec![first, second])
+            }
+            (Expression::Fix { span, .. }, _) if self.propositional => {
+                return Err(Error::GeneralRecursion { span });
+            }
+            (Expression::Fix { expression, .. }, _) => {
+                let function =
+                    Type::Function(Box::new(expected.clone()), Box::new(expected.clone()));
+                let function = self.inherit(*expression, &function)?;
+                (Rule::Fix, vec![function])
+            }
+            (
+                Expression::Case {
+                    expression,
+                    left,
+                    right,
+                    ..
+                },
+                _,
+            ) => {
+                let span = expression.get_span();
+                let scrutinee = self.synthesize(*expression)?;
+                let (lhs, rhs) = self.branches(&scrutinee.typ, span, &left, &right)?;
+                let left = self.bound(left.variable.name.clone(), lhs, |tc| {
+                    tc.inherit(left.expression, expected)
+                })?;
+                let right = self.bound(right.variable.name.clone(), rhs, |tc| {
+                    tc.inherit(right.expression, expected)
+                })?;
+                (Rule::Case, vec![scrutinee, left, right])
+            }
+            (expr, _) => {
+                let judgement = self.synthesize(expr)?;
+                if judgement.typ != *expected {
                     return Err(Error::TypeMismatch {
-                        expected: Type::Int.to_string(),
-                        found: lhs_type.to_string(),
-                        span: lhs.get_span(),
+                        expected: self.show(expected),
+                        found: self.show(&judgement.typ),
+                        span: expr_span,
                     });
                 }
+                return Ok(judgement);
+            }
+        };
+        self.types.push((expr_span, expected.clone()));
+        Ok(Judgement::new(conclusion, rule, expected.clone(), premises))
+    }
+
+    /// The context and expression a derivation of `expr` concludes with,
+    /// when derivations are built.
+    fn conclusion(&self, expr: &Expression) -> Option<(Vec<(Symbol, Type)>, Expression)> {
+        self.derivations.then(|| (self.context(), expr.clone()))
+    }
+
+    /// The judgement synthesizing the type of `pair`, with the components of
+    /// the product it must be.
+    fn components(&mut self, pair: Expression) -> Result<(Judgement, Type, Type), Error> {
+        let span = pair.get_span();
+        let judgement = self.synthesize(pair)?;
+        match judgement.typ.clone() {
+            Type::Product(lhs, rhs) => Ok((judgement, *lhs, *rhs)),
+            typ => Err(Error::TypeMismatch {
+                expected: "Product".to_string(),
+                found: self.show(&typ),
+                span,
+            }),
+        }
+    }
+
+    /// The alternatives of `typ`, which must be a sum as the injection at
+    /// `span` annotates it.
+    fn alternatives(&self, typ: &Type, span: Range<usize>) -> Result<(Type, Type), Error> {
+        match typ {
+            Type::Sum(lhs, rhs) => Ok(((**lhs).clone(), (**rhs).clone())),
+            typ => Err(Error::TypeMismatch {
+                expected: "Sum".to_string(),
+                found: self.show(typ),
+                span,
+            }),
+        }
+    }
+
+    /// The types bound by the branches `left` and `right` of a `case`
+    /// analysis whose scrutinee, at `span`, has type `scrutinee`.
+    fn branches(
+        &mut self,
+        scrutinee: &Type,
+        span: Range<usize>,
+        left: &Branch,
+        right: &Branch,
+    ) -> Result<(Type, Type), Error> {
+        let (lhs, rhs) = self.alternatives(scrutinee, span)?;
+        self.types.push((left.variable.span.clone(), lhs.clone()));
+        self.types.push((right.variable.span.clone(), rhs.clone()));
+        Ok((lhs, rhs))
+    }
+
+    /// Runs `f` with `name` bound to `typ`, the binding going out of scope
+    /// afterwards whether or not `f` succeeded.
+    fn bound<T>(&mut self, name: Symbol, typ: Type, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.context.push((name, typ));
+        let result = f(self);
+        self.context.pop();
+        result
+    }
+
+    /// How an error shows `typ`.
+    fn show(&self, typ: &Type) -> String {
+        if self.propositional {
+            typ.as_proposition().to_string()
+        } else {
+            typ.to_string()
+        }
+    }
+
+    fn lookup(&self, name: &Symbol) -> Option<&Type> {
+        self.context
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, typ)| typ)
+    }
+
+    /// The bindings currently visible, sorted by name.
+    fn context(&self) -> Vec<(Symbol, Type)> {
+        let mut context: Vec<(Symbol, Type)> = Vec::new();
+        for (name, typ) in self.context.iter().rev() {
+            if !context.iter().any(|(n, _)| n == name) {
+                context.push((name.clone(), typ.clone()));
             }
-            Expression::Int { .. } => Ok(Type::Int),
-            Expression::Unit { .. } => Ok(Type::Unit),
         }
+        context.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+        context
     }
 }
@@ -0,0 +1,71 @@
+use chumsky::Parser;
+use stlc::ast::{Expression, Type};
+use stlc::derivation::Rule;
+use stlc::error::Error;
+use stlc::evaluator::{eval, Environment};
+use stlc::typechecker::TypeChecker;
+
+fn parse(source: &str) -> Expression {
+    stlc::parser::parser().parse(source).unwrap()
+}
+
+fn function(param: Type, ret: Type) -> Type {
+    Type::Function(Box::new(param), Box::new(ret))
+}
+
+#[test]
+fn unannotated_lambda_checks_against_a_function_type() {
+    let typ = function(Type::Int, Type::Int);
+    assert!(TypeChecker::new().check(parse("λx. x + 1"), &typ).is_ok());
+}
+
+#[test]
+fn unannotated_lambda_cannot_be_inferred() {
+    match TypeChecker::new().infer(parse("λx. x")) {
+        Err(Error::UnannotatedLambda { span }) => assert_eq!(span, 0..5),
+        result => panic!("expected an unannotated lambda error, got {:?}", result),
+    }
+}
+
+#[test]
+fn unannotated_lambda_does_not_check_against_a_base_type() {
+    match TypeChecker::new().check(parse("λx. x"), &Type::Int) {
+        Err(Error::TypeMismatch {
+            expected, found, ..
+        }) => assert_eq!((expected.as_str(), found.as_str()), ("Int", "Function")),
+        result => panic!("expected a type mismatch, got {:?}", result),
+    }
+}
+
+#[test]
+fn argument_is_checked_against_the_parameter_type() {
+    let source = "(\\f:Int -> Bool. f 1) (\\n. n < 2)";
+    let mut tc = TypeChecker::new();
+    assert_eq!(tc.infer(parse(source)).unwrap(), Type::Bool);
+    // The binder of the unannotated lambda gets the parameter type.
+    let binder = source.find("\\n").unwrap() + 1;
+    assert_eq!(tc.type_at(binder), Some(&Type::Int));
+}
+
+#[test]
+fn ascription_concludes_with_its_own_rule() {
+    let derivation = TypeChecker::new()
+        .derive(parse("(λx. x : Bool -> Bool)"))
+        .unwrap();
+    assert_eq!(derivation.rule, Rule::Ascribe);
+    assert_eq!(derivation.typ, function(Type::Bool, Type::Bool));
+    assert_eq!(derivation.premises[0].rule, Rule::Abs);
+}
+
+#[test]
+fn ascription_is_printed_back_in_parentheses() {
+    let expr = parse("(λx. x : Int -> Int) 1");
+    assert_eq!(expr.to_string(), "(λx. x : Int -> Int) 1");
+    assert_eq!(parse(&expr.to_string()).to_string(), expr.to_string());
+}
+
+#[test]
+fn ascription_is_erased_by_evaluation() {
+    let value = eval(parse("(λx. x * 2 : Int -> Int) 21"), &Environment::new()).unwrap();
+    assert_eq!(value.to_string(), "42");
+}
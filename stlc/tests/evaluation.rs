@@ -0,0 +1,50 @@
+use chumsky::Parser;
+use stlc::ast::Expression;
+use stlc::evaluator::{eval, step, Environment};
+
+fn parse(source: &str) -> Expression {
+    stlc::parser::parser().parse(source).unwrap()
+}
+
+#[test]
+fn fixpoint_unfolding_into_itself_diverges() {
+    let divergence = eval(parse("fix (λx:Int. x + 1)"), &Environment::new()).unwrap_err();
+    assert_eq!(divergence.to_string(), "`fix (λx:Int. x + 1)` diverges");
+}
+
+#[test]
+fn fixpoint_ignoring_itself_has_a_value() {
+    let value = eval(parse("fix (λx:Int. 3)"), &Environment::new()).unwrap();
+    assert_eq!(value.to_string(), "3");
+}
+
+#[test]
+fn recursive_function_is_unfolded_at_every_call() {
+    let source = "fix (λf:Int -> Int. λn:Int. if n < 1 then 0 else n + f (n - 1)) 4";
+    let value = eval(parse(source), &Environment::new()).unwrap();
+    assert_eq!(value.to_string(), "10");
+}
+
+#[test]
+fn negative_values_are_printed_back_as_subtractions() {
+    for (source, printed) in [
+        ("0 - 3", "0 - 3"),
+        ("(0 - 3, 2 * (0 - 3))", "(0 - 3, 0 - 6)"),
+        ("0 - 2147483647 - 1", "0 - 2147483647 - 1"),
+    ] {
+        let value = eval(parse(source), &Environment::new()).unwrap();
+        assert_eq!(value.to_string(), printed);
+        let reparsed = eval(parse(&value.to_string()), &Environment::new()).unwrap();
+        assert_eq!(reparsed.to_string(), printed);
+    }
+}
+
+#[test]
+fn negative_operands_are_parenthesized() {
+    let mut expr = parse("(λx:Int. x * 2 - x) (0 - 3)");
+    while let Some(next) = step(&expr) {
+        assert_eq!(parse(&next.to_string()).to_string(), next.to_string());
+        expr = next;
+    }
+    assert_eq!(expr.to_string(), "0 - 3");
+}
@@ -0,0 +1,95 @@
+use chumsky::Parser;
+use stlc::ast::Type;
+use stlc::derivation::Rule;
+use stlc::error::Error;
+use stlc::symbol::Symbol;
+use stlc::typechecker::TypeChecker;
+
+fn proposition(source: &str) -> Type {
+    stlc::parser::typ()
+        .then_ignore(chumsky::primitive::end())
+        .parse(source)
+        .unwrap()
+}
+
+fn prove(source: &str) -> Result<(), Error> {
+    let mut tc = TypeChecker::new();
+    for theorem in stlc::parser::parse_theorems(source).unwrap() {
+        tc.prove(theorem)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn negation_is_an_implication_of_false() {
+    let a = Type::Atom(Symbol::intern("A"));
+    assert_eq!(
+        proposition("~A"),
+        Type::Function(Box::new(a), Box::new(Type::Empty))
+    );
+}
+
+#[test]
+fn connectives_are_the_product_and_the_sum() {
+    assert_eq!(proposition("A /\\ B \\/ C"), proposition("A × B + C"));
+}
+
+#[test]
+fn propositions_are_printed_back_with_connectives() {
+    for source in [
+        "~(A /\\ ~A)",
+        "(A -> B) -> ~B -> ~A",
+        "A \\/ B /\\ C -> ~(A -> B)",
+    ] {
+        assert_eq!(proposition(source).as_proposition().to_string(), source);
+    }
+}
+
+#[test]
+fn only_atoms_and_false_make_propositions() {
+    assert!(proposition("~A \\/ (B -> False)").is_proposition());
+    assert!(!proposition("A -> Int").is_proposition());
+}
+
+#[test]
+fn theorem_is_usable_by_the_next_ones() {
+    let source = "
+        theorem and_commutes : A /\\ B -> B /\\ A := λp. (snd p, fst p)
+        theorem and_commutes_pair : A -> B -> B /\\ A := λa. λb. and_commutes (a, b)
+    ";
+    assert!(prove(source).is_ok());
+}
+
+#[test]
+fn failed_step_is_a_type_mismatch_in_propositions() {
+    match prove("theorem wrong : A /\\ B -> A := λp. snd p") {
+        Err(Error::TypeMismatch {
+            expected, found, ..
+        }) => assert_eq!((expected.as_str(), found.as_str()), ("A", "B")),
+        result => panic!("expected a type mismatch, got {:?}", result),
+    }
+}
+
+#[test]
+fn absurd_eliminates_false() {
+    let expr = stlc::parser::parser()
+        .parse("(λbottom:False. absurd bottom as Int)")
+        .unwrap();
+    let derivation = TypeChecker::new().derive(expr).unwrap();
+    assert_eq!(derivation.premises[0].rule, Rule::Absurd);
+    assert_eq!(derivation.typ.to_string(), "False -> Int");
+}
+
+#[test]
+fn general_recursion_proves_nothing() {
+    for source in [
+        "theorem bad : False := fix (λx:False. x)",
+        "theorem bad : A := fix (λx. x)",
+        "theorem bad : A -> B := letrec f : A -> B = λa. f a in f",
+    ] {
+        match prove(source) {
+            Err(Error::GeneralRecursion { .. }) => {}
+            result => panic!("expected {} to be rejected, got {:?}", source, result),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use chumsky::Parser;
+use stlc::ast::{Expression, Type};
+use stlc::error::Error;
+use stlc::symbol::Symbol;
+use stlc::typechecker::TypeChecker;
+
+fn parse(source: &str) -> Expression {
+    stlc::parser::parser().parse(source).unwrap()
+}
+
+fn infer(source: &str) -> Result<Type, Error> {
+    TypeChecker::new().infer(parse(source))
+}
+
+fn assert_undefined(result: Result<Type, Error>, expected: &str) {
+    match result {
+        Err(Error::UndefinedSymbol { name, .. }) => assert_eq!(name.as_str(), expected),
+        Err(e) => panic!("expected `{}` to be undefined, got {:?}", expected, e),
+        Ok(typ) => panic!("expected `{}` to be undefined, got type {}", expected, typ),
+    }
+}
+
+fn function(param: Type, ret: Type) -> Type {
+    Type::Function(Box::new(param), Box::new(ret))
+}
+
+#[test]
+fn binder_does_not_leak_out_of_its_abstraction() {
+    assert_undefined(infer("(λx:Int. x) x"), "x");
+}
+
+#[test]
+fn binder_does_not_leak_into_a_sibling() {
+    assert_undefined(infer("((λx:Int. x) 1, x)"), "x");
+}
+
+#[test]
+fn binder_is_visible_in_its_body() {
+    assert_eq!(infer("(λx:Int. x + 1) 1").unwrap(), Type::Int);
+}
+
+#[test]
+fn inner_binder_shadows_outer_binder() {
+    assert_eq!(
+        infer("λx:Int. (λx:Bool. x) true").unwrap(),
+        function(Type::Int, Type::Bool)
+    );
+}
+
+#[test]
+fn shadowed_binding_is_restored() {
+    assert_eq!(
+        infer("λx:Int. ((λx:Bool. x) true, x)").unwrap(),
+        function(
+            Type::Int,
+            Type::Product(Box::new(Type::Bool), Box::new(Type::Int))
+        )
+    );
+}
+
+#[test]
+fn case_binders_do_not_leak() {
+    assert_undefined(
+        infer("(case inl 1 as Int + Bool of inl y => y | inr z => 0) + y"),
+        "y",
+    );
+    assert_undefined(
+        infer("(case inl 1 as Int + Bool of inl y => y | inr z => 0, z)"),
+        "z",
+    );
+}
+
+#[test]
+fn case_binder_is_not_visible_in_the_other_branch() {
+    assert_undefined(
+        infer("case inl 1 as Int + Bool of inl y => 0 | inr z => y"),
+        "y",
+    );
+}
+
+#[test]
+fn letrec_binder_does_not_leak() {
+    assert_undefined(infer("(letrec f : Int -> Int = λn:Int. f n in 1, f)"), "f");
+}
+
+#[test]
+fn failed_inference_does_not_leak() {
+    let mut tc = TypeChecker::new();
+    assert!(tc.infer(parse("λx:Int. x + true")).is_err());
+    assert_undefined(tc.infer(parse("x")), "x");
+}
+
+#[test]
+fn binder_shadows_definition() {
+    let mut tc = TypeChecker::new();
+    tc.define(Symbol::intern("x"), Type::Int);
+    assert_eq!(tc.infer(parse("(λx:Bool. x) true")).unwrap(), Type::Bool);
+    assert_eq!(tc.infer(parse("x")).unwrap(), Type::Int);
+}
+
+#[test]
+fn derivation_context_only_lists_visible_bindings() {
+    let derivation = TypeChecker::new()
+        .derive(parse("λx:Int. λx:Bool. x"))
+        .unwrap();
+    let variable = &derivation.premises[0].premises[0];
+    assert_eq!(variable.context.len(), 1);
+    assert_eq!(variable.context[0].0, Symbol::intern("x"));
+    assert_eq!(variable.context[0].1, Type::Bool);
+}
+
+#[test]
+fn multi_character_names_are_distinct() {
+    assert_undefined(infer("(λsucc:Int -> Int. succ) s"), "s");
+    assert_undefined(infer("(λx:Int. x') 1"), "x'");
+    assert_eq!(
+        infer("(λcompose_2:Int. λx1:Bool. compose_2) 1 true").unwrap(),
+        Type::Int
+    );
+}
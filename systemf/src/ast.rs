@@ -0,0 +1,512 @@
+/*
+⟨program⟩ ⩴  ⟨expression⟩
+
+⟨expression⟩ ⩴  ⟨variable⟩
+              | ⟨abstraction⟩
+              | ⟨application⟩
+              | ⟨type-abstraction⟩
+              | ⟨type-application⟩
+              | ⟨binary⟩
+              | ⟨conditional⟩
+              | ⟨let⟩
+              | ⟨fixpoint⟩
+              | ⟨int⟩
+              | ⟨bool⟩
+              | ⟨unit⟩
+              | '(' ⟨expression⟩ ')'
+
+⟨variable⟩ ⩴  ⟨start⟩ ⟨continue⟩*    (other than a keyword)
+
+⟨start⟩ ⩴  'a' | 'b' | ... | 'z' | '_'
+
+⟨continue⟩ ⩴  ⟨start⟩ | 'A' | 'B' | ... | 'Z' | ⟨digit⟩ | '\''
+
+⟨type-variable⟩ ⩴  ⟨type-start⟩ ⟨continue⟩*    (other than a base type)
+
+⟨type-start⟩ ⩴  'A' | 'B' | ... | 'Z' | 'α' | 'β' | ... | 'ω'    (other than 'λ')
+
+⟨abstraction⟩ ⩴  'λ' ⟨variable⟩ ':' ⟨type⟩ '.' ⟨expression⟩
+                | '\' ⟨variable⟩ ':' ⟨type⟩ '.' ⟨expression⟩
+
+⟨type-abstraction⟩ ⩴  'Λ' ⟨type-variable⟩ '.' ⟨expression⟩
+                     | '/\' ⟨type-variable⟩ '.' ⟨expression⟩
+
+⟨type-application⟩ ⩴  ⟨expression⟩ '[' ⟨type⟩ ']'
+
+⟨type⟩ ⩴  ⟨base-type⟩
+        | ⟨type-variable⟩
+        | ⟨function-type⟩
+        | ⟨universal-type⟩
+        | '(' ⟨type⟩ ')'
+
+⟨base-type⟩ ⩴  'Int'
+             | 'Bool'
+             | 'Unit'
+
+⟨function-type⟩ ⩴  ⟨type⟩ ' -> ' ⟨type⟩
+
+⟨universal-type⟩ ⩴  '∀' ⟨type-variable⟩ '.' ⟨type⟩
+                   | 'forall' ⟨type-variable⟩ '.' ⟨type⟩
+
+⟨let⟩ ⩴  'let' ⟨variable⟩ '=' ⟨expression⟩ 'in' ⟨expression⟩
+
+⟨fixpoint⟩ ⩴  'fix' ⟨expression⟩
+
+⟨conditional⟩ ⩴  'if' ⟨expression⟩ 'then' ⟨expression⟩ 'else' ⟨expression⟩
+
+⟨unit⟩ ⩴  '()'
+
+⟨bool⟩ ⩴  'true' | 'false'
+
+⟨int⟩ ⩴  ⟨digit⟩+
+
+⟨binary⟩ ⩴  ⟨expression⟩ ⟨operator⟩ ⟨expression⟩
+
+⟨operator⟩ ⩴  '*'             (tightest, left associative)
+             | '+' | '-'       (left associative)
+             | '==' | '<'      (loosest, non associative)
+
+⟨digit⟩ ⩴  '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9'
+
+⟨application⟩ ⩴  ⟨expression⟩ ⟨expression⟩
+*/
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+type Span = Range<usize>;
+
+#[derive(Debug, Clone)]
+pub enum Expression {
+    Variable(Variable),
+    Abstraction {
+        variable: Variable,
+        annotation: Annotation,
+        expression: Box<Expression>,
+        span: Span,
+    },
+    Application {
+        callee: Box<Expression>,
+        arg: Box<Expression>,
+        span: Span,
+    },
+    TypeAbstraction {
+        variable: Variable,
+        expression: Box<Expression>,
+        span: Span,
+    },
+    TypeApplication {
+        expression: Box<Expression>,
+        annotation: Annotation,
+        span: Span,
+    },
+    Binary {
+        operator: Operator,
+        lhs: Box<Expression>,
+        rhs: Box<Expression>,
+        span: Span,
+    },
+    If {
+        condition: Box<Expression>,
+        then_branch: Box<Expression>,
+        else_branch: Box<Expression>,
+        span: Span,
+    },
+    Let {
+        variable: Variable,
+        definition: Box<Expression>,
+        body: Box<Expression>,
+        span: Span,
+    },
+    Fix {
+        expression: Box<Expression>,
+        span: Span,
+    },
+    Int {
+        n: i32,
+        span: Span,
+    },
+    Bool {
+        value: bool,
+        span: Span,
+    },
+    Unit {
+        span: Span,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Eq,
+    Lt,
+}
+
+impl Operator {
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Operator::Eq | Operator::Lt => 1,
+            Operator::Add | Operator::Sub => 2,
+            Operator::Mul => 3,
+        }
+    }
+    /// Whether the operator builds a `Bool` out of its `Int` operands.
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, Operator::Eq | Operator::Lt)
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operator::Add => write!(f, "+"),
+            Operator::Sub => write!(f, "-"),
+            Operator::Mul => write!(f, "*"),
+            Operator::Eq => write!(f, "=="),
+            Operator::Lt => write!(f, "<"),
+        }
+    }
+}
+
+/// A type written in the program, along with where it was written so that
+/// its undefined type variables can be reported.
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    pub typ: Type,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn get_span(&self) -> Span {
+        match self {
+            Expression::Variable(Variable { span, .. }) => span.clone(),
+            Expression::Abstraction { span, .. } => span.clone(),
+            Expression::Application { span, .. } => span.clone(),
+            Expression::TypeAbstraction { span, .. } => span.clone(),
+            Expression::TypeApplication { span, .. } => span.clone(),
+            Expression::Binary { span, .. } => span.clone(),
+            Expression::If { span, .. } => span.clone(),
+            Expression::Let { span, .. } => span.clone(),
+            Expression::Fix { span, .. } => span.clone(),
+            Expression::Int { span, .. } => span.clone(),
+            Expression::Bool { span, .. } => span.clone(),
+            Expression::Unit { span } => span.clone(),
+        }
+    }
+    /// How tightly the expression binds, from 0 for the constructs
+    /// extending as far right as possible to 5 for atoms.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Expression::Abstraction { .. }
+            | Expression::TypeAbstraction { .. }
+            | Expression::If { .. }
+            | Expression::Let { .. } => 0,
+            Expression::Binary { operator, .. } => operator.precedence(),
+            Expression::Application { .. }
+            | Expression::TypeApplication { .. }
+            | Expression::Fix { .. } => 4,
+            Expression::Variable(_)
+            | Expression::Int { .. }
+            | Expression::Bool { .. }
+            | Expression::Unit { .. } => 5,
+        }
+    }
+    /// The span of the binder of the variable at `offset`, or of the binder
+    /// itself when `offset` falls on one.
+    pub fn definition(&self, offset: usize) -> Option<Span> {
+        self.resolve(offset, &mut Vec::new())
+    }
+    fn resolve<'a>(&'a self, offset: usize, scope: &mut Vec<&'a Variable>) -> Option<Span> {
+        match self {
+            Expression::Variable(variable) if variable.span.contains(&offset) => scope
+                .iter()
+                .rev()
+                .find(|binder| binder.name == variable.name)
+                .map(|binder| binder.span.clone()),
+            Expression::Abstraction {
+                variable,
+                expression,
+                ..
+            } => expression.resolve_under(variable, offset, scope),
+            Expression::Application {
+                callee: lhs,
+                arg: rhs,
+                ..
+            }
+            | Expression::Binary { lhs, rhs, .. } => lhs
+                .resolve(offset, scope)
+                .or_else(|| rhs.resolve(offset, scope)),
+            Expression::TypeAbstraction { expression, .. }
+            | Expression::TypeApplication { expression, .. }
+            | Expression::Fix { expression, .. } => expression.resolve(offset, scope),
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => condition
+                .resolve(offset, scope)
+                .or_else(|| then_branch.resolve(offset, scope))
+                .or_else(|| else_branch.resolve(offset, scope)),
+            Expression::Let {
+                variable,
+                definition,
+                body,
+                ..
+            } => definition
+                .resolve(offset, scope)
+                .or_else(|| body.resolve_under(variable, offset, scope)),
+            Expression::Variable(_)
+            | Expression::Int { .. }
+            | Expression::Bool { .. }
+            | Expression::Unit { .. } => None,
+        }
+    }
+    /// Resolves `offset` in the expression, in the scope of `binder`.
+    fn resolve_under<'a>(
+        &'a self,
+        binder: &'a Variable,
+        offset: usize,
+        scope: &mut Vec<&'a Variable>,
+    ) -> Option<Span> {
+        if binder.span.contains(&offset) {
+            return Some(binder.span.clone());
+        }
+        scope.push(binder);
+        let definition = self.resolve(offset, scope);
+        scope.pop();
+        definition
+    }
+    /// Writes the expression, parenthesized if it binds looser than `level`.
+    fn fmt_at(&self, f: &mut fmt::Formatter, level: u8) -> fmt::Result {
+        if self.precedence() < level {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Variable(Variable { name, .. }) => write!(f, "{}", name),
+            Expression::Abstraction {
+                variable,
+                annotation,
+                expression,
+                ..
+            } => write!(f, "λ{}:{}. {}", variable.name, annotation.typ, expression),
+            Expression::Application { callee, arg, .. } => {
+                callee.fmt_at(f, 4)?;
+                write!(f, " ")?;
+                arg.fmt_at(f, 5)
+            }
+            Expression::TypeAbstraction {
+                variable,
+                expression,
+                ..
+            } => write!(f, "Λ{}. {}", variable.name, expression),
+            Expression::TypeApplication {
+                expression,
+                annotation,
+                ..
+            } => {
+                expression.fmt_at(f, 4)?;
+                write!(f, " [{}]", annotation.typ)
+            }
+            Expression::Binary {
+                operator, lhs, rhs, ..
+            } => {
+                let precedence = operator.precedence();
+                if operator.is_comparison() {
+                    lhs.fmt_at(f, precedence + 1)?;
+                } else {
+                    lhs.fmt_at(f, precedence)?;
+                }
+                write!(f, " {} ", operator)?;
+                rhs.fmt_at(f, precedence + 1)
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => write!(
+                f,
+                "if {} then {} else {}",
+                condition, then_branch, else_branch
+            ),
+            Expression::Let {
+                variable,
+                definition,
+                body,
+                ..
+            } => write!(f, "let {} = {} in {}", variable.name, definition, body),
+            Expression::Fix { expression, .. } => {
+                write!(f, "fix ")?;
+                expression.fmt_at(f, 5)
+            }
+            Expression::Int { n, .. } => write!(f, "{}", n),
+            Expression::Bool { value, .. } => write!(f, "{}", value),
+            Expression::Unit { .. } => write!(f, "()"),
+        }
+    }
+}
+
+/// A term or type variable, named where it is bound or used.
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub name: String,
+    pub span: Span,
+}
+
+/// A type. Types equal each other up to the renaming of their bound type
+/// variables, so that `∀X. X -> X` and `∀Y. Y -> Y` are the same type.
+#[derive(Debug, Clone)]
+pub enum Type {
+    Function(Box<Type>, Box<Type>),
+    Forall(String, Box<Type>),
+    Variable(String),
+    Unit,
+    Int,
+    Bool,
+}
+
+impl Type {
+    pub fn function(param: Type, ret: Type) -> Type {
+        Type::Function(Box::new(param), Box::new(ret))
+    }
+    pub fn forall(variable: impl Into<String>, typ: Type) -> Type {
+        Type::Forall(variable.into(), Box::new(typ))
+    }
+    /// The type variables of the type not bound by a `∀` of its own, in order
+    /// of appearance.
+    pub fn free_variables(&self) -> Vec<String> {
+        let mut variables = Vec::new();
+        self.collect_free_variables(&mut Vec::new(), &mut variables);
+        variables
+    }
+    fn collect_free_variables(&self, bound: &mut Vec<String>, free: &mut Vec<String>) {
+        match self {
+            Type::Variable(name) => {
+                if !bound.contains(name) && !free.contains(name) {
+                    free.push(name.clone());
+                }
+            }
+            Type::Function(param, ret) => {
+                param.collect_free_variables(bound, free);
+                ret.collect_free_variables(bound, free);
+            }
+            Type::Forall(variable, typ) => {
+                bound.push(variable.clone());
+                typ.collect_free_variables(bound, free);
+                bound.pop();
+            }
+            Type::Unit | Type::Int | Type::Bool => (),
+        }
+    }
+    /// The type with `replacement` in place of the free occurrences of the
+    /// type variable `name`.
+    pub fn substitute(&self, name: &str, replacement: &Type) -> Type {
+        self.substitute_all(&HashMap::from([(name.to_string(), replacement.clone())]))
+    }
+    /// The type with every free type variable mapped by `substitution`
+    /// replaced at once. A `∀` binding a variable free in a replacement is
+    /// renamed so that the replacement does not get captured.
+    pub fn substitute_all(&self, substitution: &HashMap<String, Type>) -> Type {
+        match self {
+            Type::Variable(name) => substitution
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| self.clone()),
+            Type::Function(param, ret) => Type::function(
+                param.substitute_all(substitution),
+                ret.substitute_all(substitution),
+            ),
+            Type::Forall(variable, typ) => {
+                let mut inner = substitution.clone();
+                inner.remove(variable);
+                let free = typ.free_variables();
+                let captured = inner.iter().any(|(name, replacement)| {
+                    free.contains(name) && replacement.free_variables().contains(variable)
+                });
+                if !captured {
+                    return Type::forall(variable.clone(), typ.substitute_all(&inner));
+                }
+                let mut avoid = free;
+                for (name, replacement) in &inner {
+                    avoid.push(name.clone());
+                    avoid.extend(replacement.free_variables());
+                }
+                let renamed = fresh(variable, &avoid);
+                inner.insert(variable.clone(), Type::Variable(renamed.clone()));
+                Type::forall(renamed, typ.substitute_all(&inner))
+            }
+            Type::Unit | Type::Int | Type::Bool => self.clone(),
+        }
+    }
+    /// Whether the types are the same up to the renaming of their bound
+    /// variables, `bound` pairing the binders met so far in either type.
+    fn equivalent(&self, other: &Type, bound: &mut Vec<(String, String)>) -> bool {
+        match (self, other) {
+            (Type::Variable(name1), Type::Variable(name2)) => {
+                match bound
+                    .iter()
+                    .rev()
+                    .find(|(binder1, binder2)| binder1 == name1 || binder2 == name2)
+                {
+                    Some((binder1, binder2)) => binder1 == name1 && binder2 == name2,
+                    None => name1 == name2,
+                }
+            }
+            (Type::Function(param1, ret1), Type::Function(param2, ret2)) => {
+                param1.equivalent(param2, bound) && ret1.equivalent(ret2, bound)
+            }
+            (Type::Forall(variable1, typ1), Type::Forall(variable2, typ2)) => {
+                bound.push((variable1.clone(), variable2.clone()));
+                let equivalent = typ1.equivalent(typ2, bound);
+                bound.pop();
+                equivalent
+            }
+            (Type::Unit, Type::Unit) | (Type::Int, Type::Int) | (Type::Bool, Type::Bool) => true,
+            _ => false,
+        }
+    }
+}
+
+/// `name` primed as many times as needed not to be one of `avoid`.
+pub fn fresh(name: &str, avoid: &[String]) -> String {
+    let mut fresh = format!("{}'", name);
+    while avoid.contains(&fresh) {
+        fresh.push('\'');
+    }
+    fresh
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            // `->` associates to the right, and a `∀` extends as far right as
+            // possible.
+            Type::Function(param, ret) => match **param {
+                Type::Function(..) | Type::Forall(..) => write!(f, "({}) -> {}", param, ret),
+                _ => write!(f, "{} -> {}", param, ret),
+            },
+            Type::Forall(variable, typ) => write!(f, "∀{}. {}", variable, typ),
+            Type::Variable(name) => write!(f, "{}", name),
+            Type::Unit => write!(f, "Unit"),
+            Type::Int => write!(f, "Int"),
+            Type::Bool => write!(f, "Bool"),
+        }
+    }
+}
+
+impl PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        self.equivalent(other, &mut Vec::new())
+    }
+}
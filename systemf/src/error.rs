@@ -0,0 +1,83 @@
+use diagnostic::Diagnostic;
+use std::io;
+use std::ops::Range;
+
+#[derive(Debug)]
+pub enum Error {
+    TypeMismatch {
+        expected: String,
+        found: String,
+        span: Range<usize>,
+    },
+    UndefinedSymbol {
+        name: String,
+        span: Range<usize>,
+    },
+    UndefinedTypeVariable {
+        name: String,
+        span: Range<usize>,
+    },
+    BranchMismatch {
+        then_type: String,
+        else_type: String,
+        then_span: Range<usize>,
+        else_span: Range<usize>,
+    },
+}
+
+impl Error {
+    pub fn report(&self, filename: &str, source: &str) {
+        self.diagnostic().report(filename, source)
+    }
+
+    /// Renders the report into `out` rather than the standard output.
+    pub fn write(&self, filename: &str, source: &str, out: impl io::Write) -> io::Result<()> {
+        self.diagnostic().write(filename, source, out)
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        match &self {
+            Error::TypeMismatch {
+                expected,
+                found,
+                span,
+            } => Diagnostic::new(
+                "type-mismatch",
+                format!("Expected type `{}`, but found `{}`.", expected, found),
+            )
+            .with_primary(span.clone(), format!("Found type `{}`.", found)),
+            Error::UndefinedSymbol { name, span } => {
+                Diagnostic::new("undefined-symbol", format!("Undefined symbol `{}`.", name))
+                    .with_primary(span.clone(), format!("`{}` is not defined.", name))
+            }
+            Error::UndefinedTypeVariable { name, span } => Diagnostic::new(
+                "undefined-type-variable",
+                format!("Undefined type variable `{}`.", name),
+            )
+            .with_primary(
+                span.clone(),
+                format!("`{}` is not bound by an enclosing `Λ`.", name),
+            ),
+            Error::BranchMismatch {
+                then_type,
+                else_type,
+                then_span,
+                else_span,
+            } => Diagnostic::new(
+                "branch-mismatch",
+                format!(
+                    "Branches of `if` have types `{}` and `{}`.",
+                    then_type, else_type
+                ),
+            )
+            .with_secondary(
+                then_span.clone(),
+                format!("This branch has type `{}`.", then_type),
+            )
+            .with_primary(
+                else_span.clone(),
+                format!("But this one has type `{}`.", else_type),
+            ),
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use crate::ast::*;
+use chumsky::error::SimpleReason;
+use chumsky::prelude::*;
+use std::ops::Range;
+
+pub trait SystemFParser = Parser<char, Expression, Error = Simple<char>>;
+
+/// Parses `source`, recovering from syntax errors so that every one of them
+/// is reported rather than only the first.
+pub fn parse(source: &str) -> Result<Expression, Vec<Simple<char>>> {
+    match parser().parse_recovery(source) {
+        (Some(ast), errors) if errors.is_empty() => Ok(ast),
+        (_, errors) => Err(errors),
+    }
+}
+
+/// Words reserved by the syntax, which cannot be used as variables.
+pub const KEYWORDS: &[&str] = &[
+    "else", "false", "fix", "forall", "if", "in", "let", "then", "true",
+];
+
+/// Names of the base types, which cannot be used as type variables.
+pub const BASE_TYPES: &[&str] = &["Bool", "Int", "Unit"];
+
+/// Whether `name` can be bound as a variable.
+pub fn is_variable(name: &str) -> bool {
+    identifier().then_ignore(end()).parse(name).is_ok() && !KEYWORDS.contains(&name)
+}
+
+/// A lowercase letter or `_`, followed by letters, digits, `_` and `'`.
+fn identifier() -> impl Parser<char, String, Error = Simple<char>> + Clone {
+    filter(|c: &char| c.is_ascii_lowercase() || *c == '_')
+        .chain(filter(|c: &char| c.is_ascii_alphanumeric() || *c == '_' || *c == '\'').repeated())
+        .collect()
+}
+
+/// An uppercase letter or a lowercase Greek one other than `λ`, followed by
+/// letters, digits, `_` and `'`.
+fn type_identifier() -> impl Parser<char, String, Error = Simple<char>> + Clone {
+    filter(|c: &char| c.is_ascii_uppercase() || (('α'..='ω').contains(c) && *c != 'λ'))
+        .chain(filter(|c: &char| c.is_ascii_alphanumeric() || *c == '_' || *c == '\'').repeated())
+        .collect()
+}
+
+/// Builds `lhs operator rhs`, spanning both operands.
+fn binary(lhs: Expression, (operator, rhs): (Operator, Expression)) -> Expression {
+    Expression::Binary {
+        span: lhs.get_span().start..rhs.get_span().end,
+        operator,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    }
+}
+
+/// Reports the end of the input within parentheses, `span` starting at the
+/// opening one, as this parenthesis being left unclosed.
+fn unclosed(error: Simple<char>, span: Range<usize>) -> Simple<char> {
+    let within = span.start < error.span().start;
+    if !within
+        || error.found().is_some()
+        || matches!(
+            error.reason(),
+            SimpleReason::Unclosed { .. } | SimpleReason::Custom(_)
+        )
+    {
+        return error;
+    }
+    Simple::unclosed_delimiter(span.start..span.start + 1, '(', error.span(), ')', None)
+        .merge(error)
+}
+
+/// What an expression is applied to: another expression, or a type between
+/// brackets.
+enum Argument {
+    Term(Expression),
+    Type(Annotation, Range<usize>),
+}
+
+/// Builds the application of `callee` to `arg`, spanning both.
+fn apply(callee: Expression, arg: Argument) -> Expression {
+    match arg {
+        Argument::Term(arg) => Expression::Application {
+            span: callee.get_span().start..arg.get_span().end,
+            callee: Box::new(callee),
+            arg: Box::new(arg),
+        },
+        Argument::Type(annotation, span) => Expression::TypeApplication {
+            span: callee.get_span().start..span.end,
+            expression: Box::new(callee),
+            annotation,
+        },
+    }
+}
+
+pub fn parser() -> impl SystemFParser {
+    let word = |k: &'static str| {
+        debug_assert!(KEYWORDS.contains(&k), "`{}` is not a keyword", k);
+        identifier().try_map(move |name, span| {
+            if name == k {
+                Ok(())
+            } else {
+                Err(diagnostic::expected_keyword(span, k))
+            }
+        })
+    };
+    let keyword = |k| word(k).padded();
+
+    let type_variable = type_identifier()
+        .try_map(|name, span| {
+            if BASE_TYPES.contains(&name.as_str()) {
+                Err(Simple::custom(
+                    span,
+                    format!("`{}` is a base type, not a type variable", name),
+                ))
+            } else {
+                Ok(Variable { name, span })
+            }
+        })
+        .labelled("a type variable");
+
+    // Types do not take the whitespace around them, so that the span of an
+    // annotation is the one of the type alone.
+    let typ = recursive(|typ| {
+        let base_type = choice((
+            type_identifier().map(|name| match name.as_str() {
+                "Unit" => Type::Unit,
+                "Int" => Type::Int,
+                "Bool" => Type::Bool,
+                _ => Type::Variable(name),
+            }),
+            typ.clone().padded().delimited_by(just('('), just(')')),
+        ));
+
+        let universal_type = just('∀')
+            .ignored()
+            .or(word("forall"))
+            .ignore_then(type_variable.clone().padded())
+            .then_ignore(just('.').then(text::whitespace()))
+            .then(typ.clone())
+            .map(|(variable, typ)| Type::forall(variable.name, typ));
+
+        let function_type = base_type
+            .then(just("->").padded().ignore_then(typ).or_not())
+            .map(|(lhs, rhs)| match rhs {
+                Some(rhs) => Type::function(lhs, rhs),
+                None => lhs,
+            });
+
+        universal_type.or(function_type)
+    });
+
+    let annotation = typ
+        .map_with_span(|typ, span| Annotation { typ, span })
+        .padded();
+
+    let variable = identifier()
+        .try_map(|name, span| {
+            if KEYWORDS.contains(&name.as_str()) {
+                Err(Simple::custom(
+                    span,
+                    format!("`{}` is a keyword, not a variable", name),
+                ))
+            } else {
+                Ok(Variable { name, span })
+            }
+        })
+        .labelled("a variable")
+        .padded();
+
+    let expr = recursive(|expr| {
+        let atom = recursive(|atom| {
+            choice((
+                // Unit
+                just("()").map_with_span(|_, span| Expression::Unit { span }),
+                // Int
+                text::int(10).try_map(|n: String, span: Range<usize>| match n.parse::<i32>() {
+                    Ok(n) => Ok(Expression::Int { n, span }),
+                    Err(_) => Err(Simple::custom(span, "integer literal out of range")),
+                }),
+                // Bool
+                word("true").map_with_span(|_, span| Expression::Bool { value: true, span }),
+                word("false").map_with_span(|_, span| Expression::Bool { value: false, span }),
+                // Fix
+                keyword("fix")
+                    .ignore_then(atom)
+                    .map_with_span(|expression, span| Expression::Fix {
+                        expression: Box::new(expression),
+                        span,
+                    }),
+                // Variable
+                variable.clone().map(Expression::Variable),
+                // Priority
+                expr.clone()
+                    .delimited_by(just('('), just(')'))
+                    .map_err_with_span(unclosed),
+            ))
+            // A parenthesized expression that does not parse is skipped up
+            // to its closing parenthesis. The unit standing for it is never
+            // checked since the parse failed.
+            .recover_with(nested_delimiters('(', ')', [('[', ']')], |span| {
+                Expression::Unit { span }
+            }))
+            .padded()
+        });
+
+        let argument = choice((
+            atom.clone().map(Argument::Term),
+            annotation
+                .clone()
+                .delimited_by(just('['), just(']'))
+                .map_with_span(Argument::Type)
+                .padded(),
+        ));
+
+        let application = atom.then(argument.repeated()).foldl(apply);
+
+        let product = application
+            .clone()
+            .then(
+                just('*')
+                    .padded()
+                    .to(Operator::Mul)
+                    .then(application)
+                    .repeated(),
+            )
+            .foldl(binary);
+
+        let sum = product
+            .clone()
+            .then(
+                choice((
+                    just('+').padded().to(Operator::Add),
+                    just('-').padded().to(Operator::Sub),
+                ))
+                .then(product)
+                .repeated(),
+            )
+            .foldl(binary);
+
+        let comparison = sum
+            .clone()
+            .then(
+                choice((
+                    just("==").padded().to(Operator::Eq),
+                    just('<').padded().to(Operator::Lt),
+                ))
+                .then(sum)
+                .or_not(),
+            )
+            .map(|(lhs, rhs)| match rhs {
+                Some(rhs) => binary(lhs, rhs),
+                None => lhs,
+            });
+
+        choice((
+            // If
+            keyword("if")
+                .ignore_then(expr.clone())
+                .then_ignore(keyword("then"))
+                .then(expr.clone())
+                .then_ignore(keyword("else"))
+                .then(expr.clone())
+                .map_with_span(
+                    |((condition, then_branch), else_branch), span| Expression::If {
+                        condition: Box::new(condition),
+                        then_branch: Box::new(then_branch),
+                        else_branch: Box::new(else_branch),
+                        span,
+                    },
+                ),
+            // Let
+            keyword("let")
+                .ignore_then(variable.clone())
+                .then_ignore(just('='))
+                .then(expr.clone())
+                .then_ignore(keyword("in"))
+                .then(expr.clone())
+                .map_with_span(|((variable, definition), body), span| Expression::Let {
+                    variable,
+                    definition: Box::new(definition),
+                    body: Box::new(body),
+                    span,
+                }),
+            // Abs
+            just('λ')
+                .or(just('\\'))
+                .ignore_then(variable)
+                .then_ignore(just(':'))
+                .then(annotation)
+                .then_ignore(just('.'))
+                .then(expr.clone())
+                .map_with_span(|((variable, annotation), expression), span| {
+                    Expression::Abstraction {
+                        variable,
+                        annotation,
+                        expression: Box::new(expression),
+                        span,
+                    }
+                }),
+            // Type abs
+            just('Λ')
+                .ignored()
+                .or(just("/\\").ignored())
+                .ignore_then(type_variable.padded())
+                .then_ignore(just('.'))
+                .then(expr)
+                .map_with_span(|(variable, expression), span| Expression::TypeAbstraction {
+                    variable,
+                    expression: Box::new(expression),
+                    span,
+                }),
+            // Operators and applications
+            comparison,
+        ))
+        .padded()
+    })
+    .then_ignore(end());
+
+    expr
+}
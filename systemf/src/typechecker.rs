@@ -0,0 +1,274 @@
+use crate::ast::*;
+use crate::error::Error;
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[derive(Debug, Clone)]
+enum Binding {
+    Term(String, Type),
+    /// A type variable bound by a `Λ`, under the name it has in the types the
+    /// checker builds. It differs from the name in the program when the `Λ`
+    /// shadows another type variable, so that the two stay apart.
+    Type {
+        name: String,
+        renamed: String,
+    },
+}
+
+#[derive(Default, Clone)]
+pub struct TypeChecker {
+    /// The typing context, a stack of bindings where the innermost binding
+    /// of a name shadows the outer ones.
+    context: Vec<Binding>,
+    /// The type of every subexpression and binder checked so far.
+    types: Vec<(Range<usize>, Type)>,
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds `name` to `typ` for every subsequent inference.
+    pub fn define(&mut self, name: String, typ: Type) {
+        self.context.push(Binding::Term(name, typ));
+    }
+
+    /// The type of the innermost subexpression or binder spanning `offset`.
+    ///
+    /// Only the subexpressions checked before an error are known.
+    pub fn type_at(&self, offset: usize) -> Option<&Type> {
+        self.types
+            .iter()
+            .filter(|(span, _)| span.contains(&offset))
+            .min_by_key(|(span, _)| span.len())
+            .map(|(_, typ)| typ)
+    }
+
+    pub fn infer(&mut self, expr: Expression) -> Result<Type, Error> {
+        let span = expr.get_span();
+        let typ = match expr {
+            Expression::Variable(Variable { name, span }) => match self.lookup(&name) {
+                Some(typ) => typ.clone(),
+                None => return Err(Error::UndefinedSymbol { name, span }),
+            },
+            Expression::Abstraction {
+                variable,
+                annotation,
+                expression,
+                ..
+            } => {
+                let typ = self.resolve(&annotation)?;
+                self.types.push((variable.span, typ.clone()));
+                let ret =
+                    self.infer_with(Binding::Term(variable.name, typ.clone()), *expression)?;
+                Type::function(typ, ret)
+            }
+            Expression::Application { callee, arg, .. } => {
+                let callee_span = callee.get_span();
+                let arg_span = arg.get_span();
+                match self.infer(*callee)? {
+                    Type::Function(param, ret) => {
+                        let arg = self.infer(*arg)?;
+                        if *param != arg {
+                            return Err(Error::TypeMismatch {
+                                expected: param.to_string(),
+                                found: arg.to_string(),
+                                span: arg_span,
+                            });
+                        }
+                        *ret
+                    }
+                    typ => {
+                        return Err(Error::TypeMismatch {
+                            expected: "Function".to_string(),
+                            found: typ.to_string(),
+                            span: callee_span,
+                        })
+                    }
+                }
+            }
+            Expression::TypeAbstraction {
+                variable,
+                expression,
+                ..
+            } => {
+                let renamed = if self.binds_type(&variable.name) {
+                    fresh(&variable.name, &self.type_variables())
+                } else {
+                    variable.name.clone()
+                };
+                let binding = Binding::Type {
+                    name: variable.name,
+                    renamed: renamed.clone(),
+                };
+                let typ = self.infer_with(binding, *expression)?;
+                Type::forall(renamed, typ)
+            }
+            Expression::TypeApplication {
+                expression,
+                annotation,
+                ..
+            } => {
+                let expression_span = expression.get_span();
+                let argument = self.resolve(&annotation)?;
+                match self.infer(*expression)? {
+                    Type::Forall(variable, typ) => typ.substitute(&variable, &argument),
+                    typ => {
+                        return Err(Error::TypeMismatch {
+                            expected: "Forall".to_string(),
+                            found: typ.to_string(),
+                            span: expression_span,
+                        })
+                    }
+                }
+            }
+            Expression::Binary {
+                operator, lhs, rhs, ..
+            } => {
+                for operand in [*lhs, *rhs] {
+                    let span = operand.get_span();
+                    let typ = self.infer(operand)?;
+                    if typ != Type::Int {
+                        return Err(Error::TypeMismatch {
+                            expected: Type::Int.to_string(),
+                            found: typ.to_string(),
+                            span,
+                        });
+                    }
+                }
+                if operator.is_comparison() {
+                    Type::Bool
+                } else {
+                    Type::Int
+                }
+            }
+            Expression::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let condition_span = condition.get_span();
+                let condition = self.infer(*condition)?;
+                if condition != Type::Bool {
+                    return Err(Error::TypeMismatch {
+                        expected: Type::Bool.to_string(),
+                        found: condition.to_string(),
+                        span: condition_span,
+                    });
+                }
+                let then_span = then_branch.get_span();
+                let then_type = self.infer(*then_branch)?;
+                let else_span = else_branch.get_span();
+                let else_type = self.infer(*else_branch)?;
+                if then_type != else_type {
+                    return Err(Error::BranchMismatch {
+                        then_type: then_type.to_string(),
+                        else_type: else_type.to_string(),
+                        then_span,
+                        else_span,
+                    });
+                }
+                then_type
+            }
+            Expression::Let {
+                variable,
+                definition,
+                body,
+                ..
+            } => {
+                let typ = self.infer(*definition)?;
+                self.types.push((variable.span, typ.clone()));
+                self.infer_with(Binding::Term(variable.name, typ), *body)?
+            }
+            Expression::Fix { expression, .. } => {
+                let span = expression.get_span();
+                let typ = self.infer(*expression)?;
+                let Type::Function(param, ret) = typ.clone() else {
+                    return Err(Error::TypeMismatch {
+                        expected: "Function".to_string(),
+                        found: typ.to_string(),
+                        span,
+                    });
+                };
+                if param != ret {
+                    return Err(Error::TypeMismatch {
+                        expected: Type::Function(param.clone(), param).to_string(),
+                        found: typ.to_string(),
+                        span,
+                    });
+                }
+                *param
+            }
+            Expression::Int { .. } => Type::Int,
+            Expression::Bool { .. } => Type::Bool,
+            Expression::Unit { .. } => Type::Unit,
+        };
+        self.types.push((span, typ.clone()));
+        Ok(typ)
+    }
+
+    /// Infers the type of `expr` under `binding`, the binding going out of
+    /// scope afterwards whether or not the inference succeeded.
+    fn infer_with(&mut self, binding: Binding, expr: Expression) -> Result<Type, Error> {
+        self.context.push(binding);
+        let typ = self.infer(expr);
+        self.context.pop();
+        typ
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Type> {
+        self.context.iter().rev().find_map(|binding| match binding {
+            Binding::Term(n, typ) if n == name => Some(typ),
+            _ => None,
+        })
+    }
+
+    /// The name the type variable `name` of the program has in the types
+    /// the checker builds, if it is in scope.
+    fn lookup_type(&self, name: &str) -> Option<&String> {
+        self.context.iter().rev().find_map(|binding| match binding {
+            Binding::Type { name: n, renamed } if n == name => Some(renamed),
+            _ => None,
+        })
+    }
+
+    /// The names of every type variable in scope, both in the program and in
+    /// the types the checker builds.
+    fn type_variables(&self) -> Vec<String> {
+        self.context
+            .iter()
+            .flat_map(|binding| match binding {
+                Binding::Type { name, renamed } => vec![name.clone(), renamed.clone()],
+                Binding::Term(..) => vec![],
+            })
+            .collect()
+    }
+
+    /// Whether binding the type variable `name` would shadow one in scope.
+    fn binds_type(&self, name: &str) -> bool {
+        self.type_variables().iter().any(|n| n == name)
+    }
+
+    /// The type written by `annotation`, its type variables renamed as their
+    /// binders were.
+    fn resolve(&self, annotation: &Annotation) -> Result<Type, Error> {
+        let mut renaming = HashMap::new();
+        for name in annotation.typ.free_variables() {
+            match self.lookup_type(&name) {
+                Some(renamed) if *renamed == name => (),
+                Some(renamed) => {
+                    renaming.insert(name, Type::Variable(renamed.clone()));
+                }
+                None => {
+                    return Err(Error::UndefinedTypeVariable {
+                        name,
+                        span: annotation.span.clone(),
+                    })
+                }
+            }
+        }
+        Ok(annotation.typ.substitute_all(&renaming))
+    }
+}
@@ -0,0 +1,113 @@
+use systemf::ast::Type;
+use systemf::error::Error;
+use systemf::typechecker::TypeChecker;
+
+fn variable(name: &str) -> Type {
+    Type::Variable(name.to_string())
+}
+
+fn infer(source: &str) -> Result<Type, Error> {
+    let Ok(ast) = systemf::parser::parse(source) else {
+        panic!("`{}` does not parse", source);
+    };
+    TypeChecker::new().infer(ast)
+}
+
+#[test]
+fn types_equal_up_to_the_names_of_their_binders() {
+    let identity = Type::forall("X", Type::function(variable("X"), variable("X")));
+    let renamed = Type::forall("Y", Type::function(variable("Y"), variable("Y")));
+    assert_eq!(identity, renamed);
+}
+
+#[test]
+fn free_variables_are_not_renamed() {
+    let free = Type::forall("X", Type::function(variable("X"), variable("Y")));
+    let other = Type::forall("X", Type::function(variable("X"), variable("Z")));
+    assert_ne!(free, other);
+    // `Y` is free in the first type and bound in the second.
+    let bound = Type::forall("Y", Type::function(variable("Y"), variable("Y")));
+    assert_ne!(free, bound);
+}
+
+#[test]
+fn binders_pair_up_in_order() {
+    let first = Type::forall(
+        "X",
+        Type::forall("Y", Type::function(variable("X"), variable("Y"))),
+    );
+    let swapped = Type::forall(
+        "Y",
+        Type::forall("X", Type::function(variable("Y"), variable("X"))),
+    );
+    let flipped = Type::forall(
+        "X",
+        Type::forall("Y", Type::function(variable("Y"), variable("X"))),
+    );
+    assert_eq!(first, swapped);
+    assert_ne!(first, flipped);
+}
+
+#[test]
+fn substitution_leaves_bound_occurrences_alone() {
+    let typ = Type::function(
+        variable("X"),
+        Type::forall("X", Type::function(variable("X"), variable("X"))),
+    );
+    assert_eq!(
+        typ.substitute("X", &Type::Int).to_string(),
+        "Int -> ∀X. X -> X"
+    );
+}
+
+#[test]
+fn substitution_renames_a_binder_that_would_capture() {
+    let typ = Type::forall("Y", Type::function(variable("X"), variable("Y")));
+    let substituted = typ.substitute("X", &variable("Y"));
+    assert_eq!(substituted.to_string(), "∀Y'. Y -> Y'");
+    assert_ne!(
+        substituted,
+        Type::forall("Y", Type::function(variable("Y"), variable("Y")))
+    );
+}
+
+#[test]
+fn renamed_binders_avoid_every_variable_in_scope() {
+    let typ = Type::forall(
+        "Y",
+        Type::function(variable("X"), Type::function(variable("Y'"), variable("Y"))),
+    );
+    let substituted = typ.substitute("X", &variable("Y"));
+    assert_eq!(substituted.to_string(), "∀Y''. Y -> Y' -> Y''");
+}
+
+#[test]
+fn shadowing_type_abstractions_stay_apart() {
+    let typ = infer("ΛX. λx:X. ΛX. λy:X. x").unwrap();
+    assert_eq!(typ.to_string(), "∀X. X -> ∀X'. X' -> X");
+    let typ = infer("ΛX. ΛX. ΛX'. λx:X. λy:X'. y").unwrap();
+    assert_eq!(typ.to_string(), "∀X. ∀X'. ∀X''. X' -> X'' -> X''");
+}
+
+#[test]
+fn type_application_instantiates_the_outermost_binder() {
+    let typ = infer("ΛX. (ΛY. ΛX. λy:Y. λx:X. y) [X]").unwrap();
+    assert_eq!(
+        typ,
+        Type::forall(
+            "A",
+            Type::forall(
+                "B",
+                Type::function(variable("A"), Type::function(variable("B"), variable("A")))
+            )
+        )
+    );
+}
+
+#[test]
+fn annotations_must_be_closed() {
+    assert!(matches!(
+        infer("λx:X. x"),
+        Err(Error::UndefinedTypeVariable { name, .. }) if name == "X"
+    ));
+}
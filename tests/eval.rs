@@ -0,0 +1,62 @@
+//! Runs `tc eval` on programs whose evaluation does not terminate.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn eval(source: &str, args: &[&str]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tc"))
+        .args(["eval", "--system", "stlc", "--stdin"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn assert_gives_up(output: Output, steps: usize) {
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let message = format!("no value reached after {} steps", steps);
+    assert!(stderr.contains(&message), "unexpected stderr: {}", stderr);
+}
+
+#[test]
+fn diverging_program_gives_up_after_the_default_steps() {
+    assert_gives_up(eval("fix (λf:Int -> Int. λx:Int. f x) 0", &[]), 10_000);
+}
+
+#[test]
+fn growing_program_gives_up_without_overflowing_the_stack() {
+    // The expression nests one level deeper at every step, far past the
+    // depth a recursive reduction could descend into.
+    let output = eval("fix (λloop:Int. loop + 1)", &["--steps", "100000"]);
+    assert_gives_up(output, 100_000);
+}
+
+#[test]
+fn terminating_program_reaches_its_value() {
+    let output = eval("(λx:Int. x * 2) 21", &[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains("42"));
+}
+
+#[test]
+fn trace_prints_every_step_before_the_value() {
+    let output = eval("(λx:Int. x * 2) 21", &["--trace"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("21 * 2").count(), 1, "stdout: {}", stdout);
+    assert!(
+        stdout.lines().last().unwrap().contains("42"),
+        "stdout: {}",
+        stdout
+    );
+}
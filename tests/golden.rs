@@ -0,0 +1,200 @@
+//! Runs every program of `tests/golden/<system>` through its checker and
+//! compares the inferred type, or the rendered diagnostic, with the `.out`
+//! file next to the program. The `stlc` proof scripts of
+//! `tests/golden/proofs` are checked the same way.
+//!
+//! Run with `BLESS=1` to overwrite the expected outputs when the output
+//! changes on purpose.
+
+use chumsky::error::Simple;
+use diagnostic::Diagnostic;
+use std::fs;
+use std::path::Path;
+
+fn check_stlc(filename: &str, source: &str) -> String {
+    match stlc::parser::parse(source) {
+        Ok(ast) => match stlc::typechecker::TypeChecker::new().infer(ast) {
+            Ok(typ) => format!("{}\n", typ),
+            Err(e) => render(|out| e.write(filename, source, out)),
+        },
+        Err(parse_error) => render_parse_errors(filename, source, parse_error),
+    }
+}
+
+fn check_proofs(filename: &str, source: &str) -> String {
+    match stlc::parser::parse_theorems(source) {
+        Ok(theorems) => {
+            let mut tc = stlc::typechecker::TypeChecker::new();
+            let mut out = String::new();
+            for theorem in theorems {
+                let (name, statement) = (theorem.name.clone(), theorem.statement.clone());
+                match tc.prove(theorem) {
+                    Ok(()) => out.push_str(&format!(
+                        "{} : {}  proof accepted\n",
+                        name.name,
+                        statement.as_proposition()
+                    )),
+                    Err(e) => {
+                        let diagnostic = e.proof_diagnostic(&name, &statement);
+                        out.push_str(&render(|out| diagnostic.write(filename, source, out)));
+                        break;
+                    }
+                }
+            }
+            out
+        }
+        Err(parse_error) => render_parse_errors(filename, source, parse_error),
+    }
+}
+
+fn check_hm(filename: &str, source: &str) -> String {
+    use hm::inference::Inference;
+    use im::HashMap;
+
+    match hm::parser::parse(source) {
+        Ok(ast) => {
+            let mut inference = Inference::new();
+            match inference.infer_scheme(ast, HashMap::new()) {
+                Ok(scheme) => format!("{}\n", scheme),
+                Err(e) => render(|out| e.write(filename, source, out)),
+            }
+        }
+        Err(parse_error) => render_parse_errors(filename, source, parse_error),
+    }
+}
+
+fn check_systemf(filename: &str, source: &str) -> String {
+    match systemf::parser::parse(source) {
+        Ok(ast) => match systemf::typechecker::TypeChecker::new().infer(ast) {
+            Ok(typ) => format!("{}\n", typ),
+            Err(e) => render(|out| e.write(filename, source, out)),
+        },
+        Err(parse_error) => render_parse_errors(filename, source, parse_error),
+    }
+}
+
+fn check_coc(filename: &str, source: &str) -> String {
+    match coc::parser::parse(source) {
+        Ok(program) => {
+            let mut tc = coc::typechecker::TypeChecker::new();
+            let mut out = String::new();
+            for declaration in program {
+                let name = declaration.name().name.clone();
+                match tc.declare(declaration) {
+                    Ok(typ) => out.push_str(&format!("{} : {}\n", name, typ)),
+                    Err(e) => {
+                        out.push_str(&render(|out| e.write(filename, source, out)));
+                        break;
+                    }
+                }
+            }
+            out
+        }
+        Err(parse_error) => render_parse_errors(filename, source, parse_error),
+    }
+}
+
+fn render_parse_errors(filename: &str, source: &str, errors: Vec<Simple<char>>) -> String {
+    errors
+        .into_iter()
+        .map(|e| render(|out| Diagnostic::from(e).write(filename, source, out)))
+        .collect()
+}
+
+fn render(write: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>) -> String {
+    let mut out = Vec::new();
+    write(&mut out).unwrap();
+    strip_colors(&String::from_utf8(out).unwrap())
+}
+
+/// Removes the ANSI escape sequences coloring diagnostics.
+fn strip_colors(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn run(system: &str, check: fn(&str, &str) -> String) {
+    run_in(system, system, check)
+}
+
+/// Runs the programs with the `extension` of `tests/golden/<directory>`.
+fn run_in(directory: &str, extension: &str, check: fn(&str, &str) -> String) {
+    let bless = std::env::var_os("BLESS").is_some();
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(directory);
+    let mut programs = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|found| found == extension))
+        .collect::<Vec<_>>();
+    programs.sort();
+    assert!(
+        !programs.is_empty(),
+        "no programs in {}",
+        directory.display()
+    );
+
+    let mut failures = Vec::new();
+    for program in programs {
+        let filename = program.file_name().unwrap().to_str().unwrap();
+        let source = fs::read_to_string(&program).unwrap();
+        let actual = check(filename, &source);
+        let expected_path = program.with_extension("out");
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => (),
+            Ok(expected) => failures.push(format!(
+                "{}: output differs\n--- expected\n{}--- actual\n{}",
+                filename, expected, actual
+            )),
+            Err(_) => failures.push(format!(
+                "{}: missing {}\n--- actual\n{}",
+                filename,
+                expected_path.display(),
+                actual
+            )),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\nRun with `BLESS=1` to accept the new output.",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn stlc() {
+    run("stlc", check_stlc);
+}
+
+#[test]
+fn proofs() {
+    run_in("proofs", "stlc", check_proofs);
+}
+
+#[test]
+fn hm() {
+    run("hm", check_hm);
+}
+
+#[test]
+fn systemf() {
+    run("systemf", check_systemf);
+}
+
+#[test]
+fn coc() {
+    run("coc", check_coc);
+}
@@ -0,0 +1,197 @@
+//! Talks to `tc lsp` as an editor would, over its standard input and output.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+struct Client {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    id: u64,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_tc"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Self {
+            child,
+            stdin,
+            stdout,
+            id: 0,
+        };
+        client.request("initialize", json!({ "capabilities": {} }));
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        let stdin = self.stdin.as_mut().unwrap();
+        write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.id += 1;
+        let id = self.id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                return message["result"].clone();
+            }
+        }
+    }
+
+    /// Opens a document and returns the diagnostics published for it.
+    fn open(&mut self, uri: &str, text: &str) -> Vec<Value> {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "", "version": 1, "text": text }
+            }),
+        );
+        self.diagnostics()
+    }
+
+    fn change(&mut self, uri: &str, text: &str) -> Vec<Value> {
+        self.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": 2 },
+                "contentChanges": [{ "text": text }]
+            }),
+        );
+        self.diagnostics()
+    }
+
+    fn diagnostics(&mut self) -> Vec<Value> {
+        let message = self.receive();
+        assert_eq!(message["method"], "textDocument/publishDiagnostics");
+        message["params"]["diagnostics"].as_array().unwrap().clone()
+    }
+
+    fn hover(&mut self, uri: &str, line: u32, character: u32) -> Value {
+        let result = self.request(
+            "textDocument/hover",
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character }
+            }),
+        );
+        result["contents"]["value"].clone()
+    }
+
+    fn definition(&mut self, uri: &str, line: u32, character: u32) -> Value {
+        let result = self.request(
+            "textDocument/definition",
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character }
+            }),
+        );
+        result["range"].clone()
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        // The server stops reading once its input is closed.
+        self.stdin = None;
+        self.child.wait().unwrap();
+    }
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Value {
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 }
+    })
+}
+
+#[test]
+fn publishes_diagnostics_on_change() {
+    let mut client = Client::start();
+    let uri = "file:///program.stlc";
+    assert!(client.open(uri, "λx:Int. x").is_empty());
+
+    let diagnostics = client.change(uri, "(λx:Int. x) ()");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["code"], "type-mismatch");
+    assert_eq!(diagnostics[0]["range"], range((0, 12), (0, 14)));
+
+    let diagnostics = client.change(uri, "(λx:Int. x");
+    assert_eq!(diagnostics[0]["code"], "parse-error");
+
+    assert!(client.change(uri, "(λx:Int. x) 1").is_empty());
+}
+
+#[test]
+fn hovers_the_innermost_subexpression() {
+    let mut client = Client::start();
+    let uri = "file:///program.stlc";
+    client.open(uri, "(λf:Int -> Int.\n  f 1) (λx:Int. x)");
+    assert_eq!(client.hover(uri, 1, 2), "```\nInt -> Int\n```");
+    assert_eq!(client.hover(uri, 1, 4), "```\nInt\n```");
+    assert_eq!(client.hover(uri, 0, 2), "```\nInt -> Int\n```");
+    assert_eq!(client.hover(uri, 1, 8), "```\nInt -> Int\n```");
+    assert_eq!(client.hover(uri, 1, 9), "```\nInt\n```");
+}
+
+#[test]
+fn hovers_solved_hm_types() {
+    let mut client = Client::start();
+    let uri = "file:///program.hm";
+    client.open(uri, "let f = \\x. x + 1; in f 2");
+    assert_eq!(client.hover(uri, 0, 12), "```\nInt\n```");
+    assert_eq!(client.hover(uri, 0, 4), "```\nInt -> Int\n```");
+}
+
+#[test]
+fn goes_to_binders() {
+    let mut client = Client::start();
+    let uri = "file:///program.stlc";
+    client.open(uri, "λx:Int. λy:Int.\n  x + y");
+    assert_eq!(client.definition(uri, 1, 2), range((0, 1), (0, 2)));
+    assert_eq!(client.definition(uri, 1, 6), range((0, 9), (0, 10)));
+
+    let uri = "file:///program.hm";
+    client.open(uri, "letrec f = \\x. g x; g = \\y. f y; in let h = f; in h");
+    assert_eq!(client.definition(uri, 0, 15), range((0, 20), (0, 21)));
+    assert_eq!(client.definition(uri, 0, 28), range((0, 7), (0, 8)));
+    assert_eq!(client.definition(uri, 0, 44), range((0, 7), (0, 8)));
+    assert_eq!(client.definition(uri, 0, 50), range((0, 40), (0, 41)));
+    assert_eq!(client.definition(uri, 0, 2), Value::Null);
+}
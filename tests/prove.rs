@@ -0,0 +1,52 @@
+//! Runs `tc prove --format json` on proof scripts.
+
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn prove_json(source: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tc"))
+        .args(["prove", "--system", "stlc", "--stdin", "--format", "json"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn lines(output: &Output) -> Vec<Value> {
+    String::from_utf8(output.stdout.clone())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn accepted_proofs_are_marked_as_such() {
+    let output = prove_json("theorem identity : A -> A := λa. a");
+    assert_eq!(output.status.code(), Some(0));
+    let lines = lines(&output);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["name"], "identity");
+    assert_eq!(lines[0]["status"], "accepted");
+}
+
+#[test]
+fn rejected_proofs_are_reported_as_errors() {
+    let output =
+        prove_json("theorem identity : A -> A := λa. a\n\ntheorem wrong : A -> B := λa. a");
+    assert_eq!(output.status.code(), Some(1));
+    let lines = lines(&output);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["status"], "accepted");
+    assert_eq!(lines[1]["severity"], "error");
+    assert!(lines[1].get("status").is_none());
+}
@@ -0,0 +1,76 @@
+//! Runs `tc repl` on scripted sessions read from its standard input.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// The standard output and error of the session, without colors.
+fn session(lines: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tc"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(lines.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let plain = |bytes| strip_colors(&String::from_utf8(bytes).unwrap());
+    (plain(output.stdout), plain(output.stderr))
+}
+
+fn strip_colors(text: &str) -> String {
+    let mut plain = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+#[test]
+fn let_binds_a_name_for_the_next_lines() {
+    let (stdout, _) = session(":let double = λn:Int. n * 2\ndouble 21\n");
+    assert_eq!(stdout, "double : Int -> Int\nInt\n");
+}
+
+#[test]
+fn let_rejects_keywords_of_the_current_system() {
+    let (stdout, stderr) = session(":mode hm\n:let let = 1\n:let in = 2\n");
+    assert!(stdout.is_empty(), "unexpected stdout: {}", stdout);
+    assert!(stderr.contains("`let` is not a valid `hm` variable"));
+    assert!(stderr.contains("`in` is not a valid `hm` variable"));
+}
+
+#[test]
+fn constraints_are_those_of_the_last_inference() {
+    let (stdout, _) = session(":mode hm\nλx. x + 1\n1 + 2\n:constraints\n");
+    let constraints = &stdout[stdout.find("Constraints:").unwrap()..];
+    assert_eq!(
+        constraints,
+        "Constraints:\n0: Int = Int\n1: Int = Int\n\nSubstitutions:\n"
+    );
+}
+
+#[test]
+fn load_prints_the_type_of_a_file() {
+    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/hm/compose.hm");
+    let (stdout, _) = session(&format!(":mode hm\n:load {}\n", file));
+    assert_eq!(stdout, "∀a b c. (a -> b) -> (c -> a) -> c -> b\n");
+}
+
+#[test]
+fn mode_switches_the_type_system() {
+    let (stdout, stderr) = session(":mode systemf\nΛX. λx:X. x\n:mode lambda\n");
+    assert_eq!(stdout, "∀X. X -> X\n");
+    assert!(stderr.contains("unknown type system `lambda`"));
+}
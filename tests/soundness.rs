@@ -0,0 +1,442 @@
+//! Property-based evidence that `stlc` is sound: random well-typed terms are
+//! generated from a target type, then progress and preservation are checked
+//! on every step of their reduction, whose result must agree with the
+//! big-step evaluator. On the fragment shared with `hm`, the type scheme
+//! inferred by each of its algorithms must be at least as general as the
+//! generated type.
+
+use proptest::prelude::*;
+use proptest::strategy::Union;
+use std::collections::HashMap;
+use stlc::ast::{Branch, Expression, Operator, Type, Variable};
+use stlc::evaluator;
+use stlc::symbol::Symbol;
+use stlc::typechecker::TypeChecker;
+
+/// Bindings in scope, the innermost last.
+type Context = Vec<(Symbol, Type)>;
+
+/// Few names so that generated terms shadow each other.
+const NAMES: [&str; 3] = ["x", "y", "z"];
+
+/// Reduction steps after which a trace is cut short. Well-typed terms without
+/// `fix` always terminate, so this only bounds the running time.
+const MAX_STEPS: usize = 500;
+
+fn function(param: Type, ret: Type) -> Type {
+    Type::Function(Box::new(param), Box::new(ret))
+}
+
+fn variable(name: Symbol) -> Variable {
+    Variable { name, span: 0..0 }
+}
+
+/// Random types; `shared` restricts them to the ones `hm` knows about.
+fn typ(shared: bool) -> BoxedStrategy<Type> {
+    let leaf = prop_oneof![Just(Type::Int), Just(Type::Bool), Just(Type::Unit)];
+    leaf.prop_recursive(2, 6, 2, move |inner| {
+        let arrow = (inner.clone(), inner.clone()).prop_map(|(param, ret)| function(param, ret));
+        if shared {
+            return arrow.boxed();
+        }
+        prop_oneof![
+            arrow,
+            (inner.clone(), inner.clone())
+                .prop_map(|(lhs, rhs)| Type::Product(Box::new(lhs), Box::new(rhs))),
+            (inner.clone(), inner).prop_map(|(lhs, rhs)| Type::Sum(Box::new(lhs), Box::new(rhs))),
+        ]
+        .boxed()
+    })
+    .boxed()
+}
+
+/// Closed terms of type `typ` in `context`, using eliminations up to `depth`
+/// levels deep.
+fn expression(typ: Type, context: Context, depth: u32, shared: bool) -> BoxedStrategy<Expression> {
+    let mut choices = vec![introduction(typ.clone(), context.clone(), depth, shared)];
+
+    let mut visible = Vec::new();
+    for (name, t) in context.iter().rev() {
+        if !visible.iter().any(|(n, _)| n == name) {
+            visible.push((name.clone(), t.clone()));
+        }
+    }
+    let variables = visible
+        .into_iter()
+        .filter(|(_, t)| *t == typ)
+        .map(|(name, _)| Expression::Variable(variable(name)))
+        .collect::<Vec<_>>();
+    if !variables.is_empty() {
+        choices.push(proptest::sample::select(variables).boxed());
+    }
+
+    if depth > 0 {
+        let depth = depth - 1;
+        // Application
+        choices.push({
+            let (typ, context) = (typ.clone(), context.clone());
+            self::typ(shared)
+                .prop_flat_map(move |param| {
+                    (
+                        expression(
+                            function(param.clone(), typ.clone()),
+                            context.clone(),
+                            depth,
+                            shared,
+                        ),
+                        expression(param, context.clone(), depth, shared),
+                    )
+                })
+                .prop_map(|(callee, arg)| Expression::Application {
+                    callee: Box::new(callee),
+                    arg: Box::new(arg),
+                    span: 0..0,
+                })
+                .boxed()
+        });
+        // Conditional
+        choices.push(
+            (
+                expression(Type::Bool, context.clone(), depth, shared),
+                expression(typ.clone(), context.clone(), depth, shared),
+                expression(typ.clone(), context.clone(), depth, shared),
+            )
+                .prop_map(|(condition, then_branch, else_branch)| Expression::If {
+                    condition: Box::new(condition),
+                    then_branch: Box::new(then_branch),
+                    else_branch: Box::new(else_branch),
+                    span: 0..0,
+                })
+                .boxed(),
+        );
+        if !shared {
+            // Projections
+            choices.push({
+                let (typ, context) = (typ.clone(), context.clone());
+                (self::typ(shared), any::<bool>())
+                    .prop_flat_map(move |(other, first)| {
+                        let pair = if first {
+                            Type::Product(Box::new(typ.clone()), Box::new(other))
+                        } else {
+                            Type::Product(Box::new(other), Box::new(typ.clone()))
+                        };
+                        (
+                            expression(pair, context.clone(), depth, shared),
+                            Just(first),
+                        )
+                    })
+                    .prop_map(|(pair, first)| {
+                        let pair = Box::new(pair);
+                        if first {
+                            Expression::First { pair, span: 0..0 }
+                        } else {
+                            Expression::Second { pair, span: 0..0 }
+                        }
+                    })
+                    .boxed()
+            });
+            // Case
+            choices.push({
+                let (typ, context) = (typ.clone(), context.clone());
+                (
+                    self::typ(shared),
+                    self::typ(shared),
+                    proptest::sample::select(&NAMES[..]),
+                    proptest::sample::select(&NAMES[..]),
+                )
+                    .prop_flat_map(move |(lhs, rhs, left, right)| {
+                        let (left, right) = (Symbol::intern(left), Symbol::intern(right));
+                        let mut left_context = context.clone();
+                        left_context.push((left.clone(), lhs.clone()));
+                        let mut right_context = context.clone();
+                        right_context.push((right.clone(), rhs.clone()));
+                        (
+                            expression(
+                                Type::Sum(Box::new(lhs), Box::new(rhs)),
+                                context.clone(),
+                                depth,
+                                shared,
+                            ),
+                            Just(left),
+                            expression(typ.clone(), left_context, depth, shared),
+                            Just(right),
+                            expression(typ.clone(), right_context, depth, shared),
+                        )
+                    })
+                    .prop_map(|(expression, left, e1, right, e2)| Expression::Case {
+                        expression: Box::new(expression),
+                        left: Box::new(Branch {
+                            variable: variable(left),
+                            expression: e1,
+                        }),
+                        right: Box::new(Branch {
+                            variable: variable(right),
+                            expression: e2,
+                        }),
+                        span: 0..0,
+                    })
+                    .boxed()
+            });
+        }
+        // Operators
+        let operators = match typ {
+            Type::Int => vec![Operator::Add, Operator::Sub, Operator::Mul],
+            Type::Bool => vec![Operator::Eq, Operator::Lt],
+            _ => vec![],
+        };
+        if !operators.is_empty() {
+            choices.push(
+                (
+                    proptest::sample::select(operators),
+                    expression(Type::Int, context.clone(), depth, shared),
+                    expression(Type::Int, context, depth, shared),
+                )
+                    .prop_map(|(operator, lhs, rhs)| Expression::Binary {
+                        operator,
+                        lhs: Box::new(lhs),
+                        rhs: Box::new(rhs),
+                        span: 0..0,
+                    })
+                    .boxed(),
+            );
+        }
+    }
+
+    Union::new(choices).boxed()
+}
+
+/// The introduction forms of `typ`, which inhabit every generated type. No
+/// type is generated out of atoms or `False`, which no value inhabits.
+fn introduction(
+    typ: Type,
+    context: Context,
+    depth: u32,
+    shared: bool,
+) -> BoxedStrategy<Expression> {
+    match typ {
+        Type::Int => (0..100)
+            .prop_map(|n| Expression::Int { n, span: 0..0 })
+            .boxed(),
+        Type::Bool => any::<bool>()
+            .prop_map(|value| Expression::Bool { value, span: 0..0 })
+            .boxed(),
+        Type::Unit => Just(Expression::Unit { span: 0..0 }).boxed(),
+        Type::Empty | Type::Atom(_) => unreachable!("`{}` has no introduction form", typ),
+        Type::Function(param, ret) => proptest::sample::select(&NAMES[..])
+            .prop_flat_map(move |name| {
+                let name = Symbol::intern(name);
+                let mut context = context.clone();
+                context.push((name.clone(), (*param).clone()));
+                (
+                    Just(name),
+                    Just((*param).clone()),
+                    expression((*ret).clone(), context, depth, shared),
+                )
+            })
+            .prop_map(|(name, typ, body)| Expression::Abstraction {
+                variable: variable(name),
+                typ: Some(typ),
+                expression: Box::new(body),
+                span: 0..0,
+            })
+            .boxed(),
+        Type::Product(first, second) => (
+            expression(*first, context.clone(), depth, shared),
+            expression(*second, context, depth, shared),
+        )
+            .prop_map(|(first, second)| Expression::Pair {
+                first: Box::new(first),
+                second: Box::new(second),
+                span: 0..0,
+            })
+            .boxed(),
+        Type::Sum(lhs, rhs) => {
+            let typ = Type::Sum(lhs.clone(), rhs.clone());
+            prop_oneof![
+                expression(*lhs, context.clone(), depth, shared).prop_map({
+                    let typ = typ.clone();
+                    move |expression| Expression::Left {
+                        expression: Box::new(expression),
+                        typ: typ.clone(),
+                        span: 0..0,
+                    }
+                }),
+                expression(*rhs, context, depth, shared).prop_map(move |expression| {
+                    Expression::Right {
+                        expression: Box::new(expression),
+                        typ: typ.clone(),
+                        span: 0..0,
+                    }
+                }),
+            ]
+            .boxed()
+        }
+    }
+}
+
+/// A random type and a closed term of that type.
+fn program(shared: bool) -> impl Strategy<Value = (Type, Expression)> {
+    typ(shared).prop_flat_map(move |typ| (Just(typ.clone()), expression(typ, vec![], 3, shared)))
+}
+
+/// Erases the annotations of a term of the fragment shared with `hm`.
+fn erase(expr: &Expression) -> hm::ast::Expression {
+    use hm::ast::Expression as Hm;
+
+    let variable = |v: &Variable| hm::ast::Variable {
+        name: v.name.to_string(),
+        span: 0..0,
+    };
+    match expr {
+        Expression::Variable(v) => Hm::Variable(variable(v)),
+        Expression::Abstraction {
+            variable: v,
+            expression,
+            ..
+        } => Hm::Abstraction {
+            variable: variable(v),
+            expression: Box::new(erase(expression)),
+            span: 0..0,
+        },
+        Expression::Application { callee, arg, .. } => Hm::Application {
+            callee: Box::new(erase(callee)),
+            arg: Box::new(erase(arg)),
+            span: 0..0,
+        },
+        Expression::Binary {
+            operator, lhs, rhs, ..
+        } => Hm::Binary {
+            operator: match operator {
+                Operator::Add => hm::ast::Operator::Add,
+                Operator::Sub => hm::ast::Operator::Sub,
+                Operator::Mul => hm::ast::Operator::Mul,
+                Operator::Eq => hm::ast::Operator::Eq,
+                Operator::Lt => hm::ast::Operator::Lt,
+            },
+            lhs: Box::new(erase(lhs)),
+            rhs: Box::new(erase(rhs)),
+            span: 0..0,
+        },
+        Expression::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => Hm::If {
+            condition: Box::new(erase(condition)),
+            then_branch: Box::new(erase(then_branch)),
+            else_branch: Box::new(erase(else_branch)),
+            span: 0..0,
+        },
+        Expression::Int { n, .. } => Hm::Int { n: *n, span: 0..0 },
+        Expression::Bool { value, .. } => Hm::Bool {
+            value: *value,
+            span: 0..0,
+        },
+        Expression::Unit { .. } => Hm::Unit { span: 0..0 },
+        expr => unreachable!("`{}` is not in the fragment shared with `hm`", expr),
+    }
+}
+
+/// Whether `specific` is an instance of `general`, the type variables of
+/// `general` being bound in `mapping` along the way.
+fn instance_of(
+    general: &hm::inference::Type,
+    specific: &Type,
+    mapping: &mut HashMap<usize, Type>,
+) -> bool {
+    use hm::inference::Type as Hm;
+
+    match (general, specific) {
+        (Hm::Variable(id), t) => match mapping.get(id) {
+            Some(bound) => bound == t,
+            None => {
+                mapping.insert(*id, t.clone());
+                true
+            }
+        },
+        (Hm::Function(param1, ret1), Type::Function(param2, ret2)) => {
+            instance_of(param1, param2, mapping) && instance_of(ret1, ret2, mapping)
+        }
+        (Hm::Int, Type::Int) | (Hm::Bool, Type::Bool) | (Hm::Unit, Type::Unit) => true,
+        _ => false,
+    }
+}
+
+proptest! {
+    #[test]
+    fn generated_terms_have_their_type((typ, expr) in program(false)) {
+        let inferred = TypeChecker::new().infer(expr.clone());
+        prop_assert_eq!(inferred.ok(), Some(typ), "{}", expr);
+    }
+
+    #[test]
+    fn derivations_conclude_with_the_inferred_type((typ, expr) in program(false)) {
+        let derivation = TypeChecker::new().derive(expr.clone());
+        prop_assert_eq!(derivation.ok().map(|derivation| derivation.typ), Some(typ), "{}", expr);
+    }
+
+    #[test]
+    fn progress_and_preservation((typ, expr) in program(false)) {
+        let mut current = expr.clone();
+        for _ in 0..MAX_STEPS {
+            // Progress: a well-typed term is a value or can take a step.
+            let Some(next) = evaluator::step(&current) else {
+                prop_assert!(evaluator::is_value(&current), "`{}` is stuck", current);
+                let value = evaluator::eval(expr, &evaluator::Environment::new());
+                prop_assert_eq!(Some(current.to_string()), value.ok().map(|value| value.to_string()));
+                return Ok(());
+            };
+            // Preservation: the step keeps the type.
+            let inferred = TypeChecker::new().infer(next.clone());
+            prop_assert_eq!(
+                inferred.ok(),
+                Some(typ.clone()),
+                "`{}` steps to `{}`",
+                current,
+                next
+            );
+            current = next;
+        }
+    }
+
+    #[test]
+    fn hm_infers_a_more_general_type((typ, expr) in program(true)) {
+        use hm::strategy::Strategy;
+
+        let strategies: [(&str, Box<dyn Strategy>); 3] = [
+            ("constraints", Box::new(hm::inference::Inference::new())),
+            ("w", Box::new(hm::w::AlgorithmW::new())),
+            ("m", Box::new(hm::m::AlgorithmM::new())),
+        ];
+        for (name, mut strategy) in strategies {
+            let Ok(scheme) = strategy.infer_scheme(erase(&expr), im::HashMap::new()) else {
+                return Err(TestCaseError::fail(format!("`{}` rejects `{}`", name, expr)));
+            };
+            prop_assert!(
+                instance_of(&scheme.typ, &typ, &mut HashMap::new()),
+                "`{}` is not an instance of `{}` inferred by `{}` for `{}`",
+                typ,
+                scheme,
+                name,
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn elaborations_of_hm_programs_check((_, expr) in program(true)) {
+        let ast = erase(&expr);
+        let mut inference = hm::inference::Inference::new();
+        let Ok(scheme) = inference.infer_scheme(ast.clone(), im::HashMap::new()) else {
+            return Err(TestCaseError::fail(format!("`{}` is rejected", expr)));
+        };
+        let elaboration = hm::elaboration::elaborate(&inference, &ast, &scheme);
+        if let Err(e) = elaboration.recheck() {
+            return Err(TestCaseError::fail(format!(
+                "`{}` elaborates to `{}`: {}",
+                expr, elaboration.term, e
+            )));
+        }
+    }
+}